@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod tests;
+
+use crate::bus::Quality;
+use crate::co2;
+use crate::registers::{self, Point, RegisterBank};
+
+// Alarms for an HMI
+//
+//  Each alarm is a discrete input, on while its condition holds, and a coil
+//  at the same address that latches it: the coil is set whenever the input
+//  comes on and stays set until the HMI writes 0 to it. Writing 0 while the
+//  condition still holds acknowledges nothing -- the coil is set again at the
+//  next update. The conditions are evaluated from the input registers every
+//  time they change, so an alarm that comes and goes between two polls is
+//  still latched.
+
+pub struct Alarm {
+    pub name: &'static str,
+    active: fn(&RegisterBank) -> bool,
+}
+
+// stale, out of range or failing -- not warming up, and not absent
+fn fault(registers: &RegisterBank, point: &Point) -> bool {
+    let code = registers.get(point);
+    [Quality::Stale, Quality::OutOfRange, Quality::SensorError].iter().any(|q| q.code() as f64 == code)
+}
+
+pub const ALARMS: [Alarm; 8] = [
+    Alarm { name: "particulate sensor fault", active: |r| fault(r, &registers::PM_QUALITY) },
+    Alarm { name: "temperature/humidity sensor fault", active: |r| fault(r, &registers::TEMP_HUM_QUALITY) },
+    Alarm { name: "CO2 sensor fault", active: |r| fault(r, &registers::CO2_QUALITY) },
+    Alarm { name: "VOC/NOx sensor fault", active: |r| fault(r, &registers::GAS_QUALITY) },
+    // unhealthy for sensitive groups and worse
+    Alarm { name: "AQI above 100", active: |r| r.get(&registers::AQI) > 100.0 },
+    Alarm { name: "CO2 elevated", active: |r| co2::level(r.get(&registers::CO2)) != "fine" },
+    Alarm { name: "CO2 too high", active: |r| co2::level(r.get(&registers::CO2)) == "too high" },
+    Alarm { name: "indoor air poor or unhealthy", active: |r| r.get(&registers::IAQ_SCORE) >= 4.0 },
+];
+
+// human-readable map, one row per alarm, as printed at startup
+pub fn dump() -> String {
+    let mut s = String::from("| Coil / discrete input | Alarm |\n| --- | --- |\n");
+    for (i, alarm) in ALARMS.iter().enumerate() {
+        s.push_str(&format!("| {} | {} |\n", i, alarm.name));
+    }
+
+    s
+}
+
+#[derive(Debug, Default)]
+pub struct Alarms {
+    active: [bool; ALARMS.len()],
+    latched: [bool; ALARMS.len()],
+}
+
+// the bits at addr..addr + cnt -- None if any of them is past the end
+fn bits(bits: &[bool], addr: u16, cnt: u16) -> Option<Vec<bool>> {
+    let start = addr as usize;
+    bits.get(start..start.checked_add(cnt as usize)?).map(<[bool]>::to_vec)
+}
+
+impl Alarms {
+    pub fn update(&mut self, registers: &RegisterBank) {
+        for (i, alarm) in ALARMS.iter().enumerate() {
+            self.active[i] = (alarm.active)(registers);
+            self.latched[i] |= self.active[i];
+        }
+    }
+
+    pub fn discrete_inputs(&self, addr: u16, cnt: u16) -> Option<Vec<bool>> {
+        bits(&self.active, addr, cnt)
+    }
+
+    pub fn coils(&self, addr: u16, cnt: u16) -> Option<Vec<bool>> {
+        bits(&self.latched, addr, cnt)
+    }
+
+    // None, and nothing written, if any of the coils doesn't exist
+    pub fn write_coils(&mut self, addr: u16, values: &[bool]) -> Option<()> {
+        let start = addr as usize;
+        let coils = self.latched.get_mut(start..start.checked_add(values.len())?)?;
+        coils.copy_from_slice(values);
+        // an acknowledgement doesn't clear an alarm that's still on
+        for (latched, active) in self.latched.iter_mut().zip(self.active) {
+            *latched |= active;
+        }
+        Some(())
+    }
+}
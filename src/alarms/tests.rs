@@ -0,0 +1,64 @@
+#[cfg(test)]
+mod alarms_tests {
+    use crate::alarms::*;
+    use crate::bus::Quality;
+    use crate::registers::{self, RegisterBank, INPUT_REGISTERS};
+
+    fn bank() -> RegisterBank {
+        RegisterBank::new(INPUT_REGISTERS, None)
+    }
+
+    #[test]
+    fn quiet_at_start() {
+        let mut alarms = Alarms::default();
+        let mut registers = bank();
+        registers.set(&registers::CO2_QUALITY, Quality::NoData.code() as f64).unwrap();
+        alarms.update(&registers);
+
+        assert_eq!(alarms.discrete_inputs(0, ALARMS.len() as u16), Some(vec![false; ALARMS.len()]));
+        assert_eq!(alarms.coils(0, ALARMS.len() as u16), Some(vec![false; ALARMS.len()]));
+        assert_eq!(alarms.discrete_inputs(0, ALARMS.len() as u16 + 1), None);
+        assert_eq!(alarms.coils(ALARMS.len() as u16, 1), None);
+    }
+
+    #[test]
+    fn conditions() {
+        let mut alarms = Alarms::default();
+        let mut registers = bank();
+        registers.set(&registers::TEMP_HUM_QUALITY, Quality::Stale.code() as f64).unwrap();
+        registers.set(&registers::AQI, 101.0).unwrap();
+        registers.set(&registers::CO2, 1200.0).unwrap();
+        registers.set(&registers::IAQ_SCORE, 4.0).unwrap();
+        alarms.update(&registers);
+
+        assert_eq!(alarms.discrete_inputs(0, 8), Some(vec![false, true, false, false, true, true, false, true]));
+    }
+
+    #[test]
+    fn coils_latch_until_acknowledged() {
+        let mut alarms = Alarms::default();
+        let mut registers = bank();
+        registers.set(&registers::CO2, 2500.0).unwrap();
+        alarms.update(&registers);
+        registers.set(&registers::CO2, 1500.0).unwrap();
+        alarms.update(&registers);
+
+        // "too high" has cleared but is still latched
+        assert_eq!(alarms.discrete_inputs(5, 2), Some(vec![true, false]));
+        assert_eq!(alarms.coils(5, 2), Some(vec![true, true]));
+
+        // acknowledging both only clears the one that's gone
+        assert_eq!(alarms.write_coils(5, &[false, false]), Some(()));
+        assert_eq!(alarms.coils(5, 2), Some(vec![true, false]));
+
+        assert_eq!(alarms.write_coils(7, &[true, true]), None);
+        assert_eq!(alarms.coils(7, 1), Some(vec![false]));
+    }
+
+    #[test]
+    fn dump_lists_every_alarm() {
+        let dump = dump();
+        assert_eq!(dump.lines().count(), ALARMS.len() + 2);
+        assert!(dump.contains("| 6 | CO2 too high |"));
+    }
+}
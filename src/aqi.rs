@@ -0,0 +1,242 @@
+#[cfg(test)]
+mod tests;
+
+pub mod schemes;
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+
+// US EPA Air Quality Index for particulate matter
+//
+//  CF: https://forum.airnowtech.org/t/the-aqi-equation/169
+//      https://www.airnow.gov/sites/default/files/2020-05/aqi-technical-assistance-document-sept2018.pdf
+//
+//  Each pollutant gets its own sub-index; the AQI is the largest of them and
+//  that pollutant is the dominant one. Concentrations are truncated before the
+//  lookup -- PM2.5 to 0.1 ug/m^3, PM10 to 1 ug/m^3 -- so the breakpoint table
+//  has no gaps (12.05 is 12.0, not "between" 12.0 and 12.1), and the index is
+//  rounded to the nearest integer.
+//
+//  The PM2.5 table was revised in 2024 (the "good" band now ends at 9.0); the
+//  2012 one is kept for comparison with older data.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pollutant {
+    Pm2_5,
+    Pm10,
+}
+
+impl Pollutant {
+    // as published in the AQI_DOMINANT register
+    pub fn code(self) -> u16 {
+        match self {
+            Pollutant::Pm2_5 => 0,
+            Pollutant::Pm10 => 1,
+        }
+    }
+
+    fn truncate(self, concentration: f64) -> f64 {
+        match self {
+            // the nudge keeps 35.4, which is 35.39999.. in binary, from truncating to 35.3
+            Pollutant::Pm2_5 => (concentration * 10.0 + 1e-9).trunc() / 10.0,
+            Pollutant::Pm10 => concentration.trunc(),
+        }
+    }
+}
+
+impl fmt::Display for Pollutant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Pollutant::Pm2_5 => write!(f, "PM2.5"),
+            Pollutant::Pm10 => write!(f, "PM10"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Breakpoints {
+    #[default]
+    Epa2024,
+    Epa2012,
+}
+
+impl FromStr for Breakpoints {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "epa-2024" | "2024" => Ok(Breakpoints::Epa2024),
+            "epa-2012" | "2012" => Ok(Breakpoints::Epa2012),
+            _ => Err(format!("unknown breakpoints '{}', expected epa-2024 or epa-2012", s)),
+        }
+    }
+}
+
+// (concentration low, concentration high, index low, index high)
+type Band = (f64, f64, f64, f64);
+
+const PM2_5_2024: [Band; 6] = [
+    (  0.0,   9.0,   0.0,  50.0),
+    (  9.1,  35.4,  51.0, 100.0),
+    ( 35.5,  55.4, 101.0, 150.0),
+    ( 55.5, 125.4, 151.0, 200.0),
+    (125.5, 225.4, 201.0, 300.0),
+    (225.5, 325.4, 301.0, 500.0),
+];
+
+const PM2_5_2012: [Band; 7] = [
+    (  0.0,  12.0,   0.0,  50.0),
+    ( 12.1,  35.4,  51.0, 100.0),
+    ( 35.5,  55.4, 101.0, 150.0),
+    ( 55.5, 150.4, 151.0, 200.0),
+    (150.5, 250.4, 201.0, 300.0),
+    (250.5, 350.4, 301.0, 400.0),
+    (350.5, 500.4, 401.0, 500.0),
+];
+
+// unchanged in 2024 apart from merging the top two bands
+const PM10: [Band; 6] = [
+    (  0.0,  54.0,   0.0,  50.0),
+    ( 55.0, 154.0,  51.0, 100.0),
+    (155.0, 254.0, 101.0, 150.0),
+    (255.0, 354.0, 151.0, 200.0),
+    (355.0, 424.0, 201.0, 300.0),
+    (425.0, 604.0, 301.0, 500.0),
+];
+
+fn table(pollutant: Pollutant, breakpoints: Breakpoints) -> &'static [Band] {
+    match (pollutant, breakpoints) {
+        (Pollutant::Pm2_5, Breakpoints::Epa2024) => &PM2_5_2024,
+        (Pollutant::Pm2_5, Breakpoints::Epa2012) => &PM2_5_2012,
+        (Pollutant::Pm10, _) => &PM10,
+    }
+}
+
+// sub-index for one pollutant; "beyond the AQI" concentrations carry on up the
+//  slope of the top band rather than stopping at 500
+pub fn sub_index(pollutant: Pollutant, concentration: f64, breakpoints: Breakpoints) -> u16 {
+    let c = pollutant.truncate(concentration.max(0.0));
+
+    interpolate(table(pollutant, breakpoints), c).round().min(u16::MAX as f64) as u16
+}
+
+// linear within the band the concentration falls in; past the top band, along
+//  its slope. Also used by the other index schemes.
+fn interpolate(bands: &[Band], c: f64) -> f64 {
+    let &(c_lo, c_hi, i_lo, i_hi) = bands.iter()
+        .find(|(_, c_hi, _, _)| c <= *c_hi)
+        .unwrap_or(&bands[bands.len() - 1]);
+
+    ((i_hi - i_lo) / (c_hi - c_lo) * (c - c_lo) + i_lo).min(u16::MAX as f64)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Aqi {
+    pub value: u16,
+    pub dominant: Pollutant,
+    pub pm2_5: u16,
+    pub pm10: u16,
+}
+
+// the AQI is the worst of the sub-indices -- never their average
+pub fn aqi(pm2_5: f64, pm10: f64, breakpoints: Breakpoints) -> Aqi {
+    let i2_5 = sub_index(Pollutant::Pm2_5, pm2_5, breakpoints);
+    let i10 = sub_index(Pollutant::Pm10, pm10, breakpoints);
+    let (value, dominant) = if i10 > i2_5 { (i10, Pollutant::Pm10) } else { (i2_5, Pollutant::Pm2_5) };
+
+    Aqi { value, dominant, pm2_5: i2_5, pm10: i10 }
+}
+
+// NowCast
+//
+//  PM AQI is defined on 24 hour averages, which react far too slowly for
+//  "should I close the windows". The NowCast weights the last 12 hourly
+//  averages, newest first, by w^0, w^1, ... where w is min/max of those hours
+//  (no lower than 0.5): steady air is averaged over most of the 12 hours,
+//  changing air follows the latest hours.
+//
+//  Hours are clock hours of the timestamps given; the current, partial hour
+//  counts as the newest. There is no NowCast until two of the three newest
+//  hours have readings.
+const NOWCAST_HOURS: u64 = 12;
+const NOWCAST_MIN_WEIGHT: f64 = 0.5;
+
+#[derive(Debug, Default)]
+pub struct HourlyHistory {
+    // (hour number since the epoch, sum, count), oldest first
+    hours: VecDeque<(u64, f64, u32)>,
+}
+
+impl HourlyHistory {
+    pub fn push(&mut self, epoch_secs: u64, value: f64) {
+        let hour = epoch_secs / 3600;
+        match self.hours.back_mut() {
+            Some((h, sum, n)) if *h == hour => {
+                *sum += value;
+                *n += 1;
+            },
+            // a clock stepped backwards starts the history over rather than
+            //  filing readings under the wrong hour
+            Some((h, _, _)) if *h > hour => {
+                self.hours.clear();
+                self.hours.push_back((hour, value, 1));
+            },
+            _ => self.hours.push_back((hour, value, 1)),
+        }
+        while self.hours.front().is_some_and(|(h, _, _)| h + NOWCAST_HOURS <= hour) {
+            self.hours.pop_front();
+        }
+    }
+
+    // NowCast concentration as of `epoch_secs`, None without enough recent data
+    pub fn nowcast(&self, epoch_secs: u64) -> Option<f64> {
+        let now = epoch_secs / 3600;
+
+        // (hours ago, hourly average), newest first
+        let averages: Vec<(u64, f64)> = self.hours.iter().rev()
+            .filter(|(h, _, _)| *h <= now && now - h < NOWCAST_HOURS)
+            .map(|(h, sum, n)| (now - h, sum / *n as f64))
+            .collect();
+        if averages.iter().filter(|(ago, _)| *ago < 3).count() < 2 {
+            return None;
+        }
+
+        let max = averages.iter().map(|(_, c)| *c).fold(f64::MIN, f64::max);
+        let min = averages.iter().map(|(_, c)| *c).fold(f64::MAX, f64::min);
+        let w = if max > 0.0 { (min / max).max(NOWCAST_MIN_WEIGHT) } else { 1.0 };
+
+        let (sum, weights) = averages.iter()
+            .fold((0.0, 0.0), |(sum, weights), (ago, c)| {
+                let k = w.powi(*ago as i32);
+                (sum + k * c, weights + k)
+            });
+
+        Some(sum / weights)
+    }
+}
+
+// NowCast histories for both pollutants
+#[derive(Debug, Default)]
+pub struct NowCast {
+    pm2_5: HourlyHistory,
+    pm10: HourlyHistory,
+}
+
+impl NowCast {
+    pub fn new() -> Self {
+        NowCast::default()
+    }
+
+    pub fn push(&mut self, epoch_secs: u64, pm2_5: f64, pm10: f64) {
+        self.pm2_5.push(epoch_secs, pm2_5);
+        self.pm10.push(epoch_secs, pm10);
+    }
+
+    pub fn aqi(&self, epoch_secs: u64, breakpoints: Breakpoints) -> Option<Aqi> {
+        let pm2_5 = self.pm2_5.nowcast(epoch_secs)?;
+        let pm10 = self.pm10.nowcast(epoch_secs)?;
+
+        Some(aqi(pm2_5, pm10, breakpoints))
+    }
+}
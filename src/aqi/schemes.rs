@@ -0,0 +1,296 @@
+#[cfg(test)]
+mod tests;
+
+use super::{interpolate, sub_index, Band, Breakpoints, Pollutant};
+
+// Air quality index schemes
+//
+//  Every country bands the same PM concentrations differently. A scheme turns
+//  a PM2.5 and a PM10 concentration into a sub-index each, and the worst
+//  sub-index is the index; its categories give the name and LCD colour for an
+//  index value. Pick one with `[aqi] scheme` -- see `from_name` for the names.
+//
+//  Most schemes are defined on hourly or 24 hour averages; like the US EPA
+//  instantaneous AQI, they are applied here to the latest reading.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Category {
+    pub name: &'static str,
+    // fits the LCD's second line next to the time -- at most 10 characters
+    pub short: &'static str,
+    // highest index value in the category, inclusive
+    pub upper: u16,
+    pub rgb: (u8, u8, u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Index {
+    pub value: u16,
+    pub dominant: Pollutant,
+    // position in the scheme's categories, as published in INDEX_CATEGORY
+    pub category: u16,
+}
+
+pub trait IndexScheme: Send + Sync {
+    // as published in INDEX_SCHEME
+    fn code(&self) -> u16;
+    fn name(&self) -> &'static str;
+    // index name on the LCD, at most 4 characters
+    fn label(&self) -> &'static str;
+    fn categories(&self) -> &'static [Category];
+    fn sub_index(&self, pollutant: Pollutant, concentration: f64) -> u16;
+
+    fn index(&self, pm2_5: f64, pm10: f64) -> Index {
+        let i2_5 = self.sub_index(Pollutant::Pm2_5, pm2_5);
+        let i10 = self.sub_index(Pollutant::Pm10, pm10);
+        let (value, dominant) = if i10 > i2_5 { (i10, Pollutant::Pm10) } else { (i2_5, Pollutant::Pm2_5) };
+
+        Index { value, dominant, category: self.category_of(value) }
+    }
+
+    fn category_of(&self, value: u16) -> u16 {
+        let categories = self.categories();
+        categories.iter().position(|c| value <= c.upper).unwrap_or(categories.len() - 1) as u16
+    }
+
+    // out of range codes (a register read before the first reading) fall back
+    //  to the first category
+    fn category(&self, code: u16) -> &'static Category {
+        let categories = self.categories();
+        categories.get(code as usize).unwrap_or(&categories[0])
+    }
+}
+
+pub const SCHEME_NAMES: &str = "us-epa, eu-caqi, uk-daqi, in-naqi, cn-aqi or ca-aqhi";
+
+pub fn from_name(name: &str, breakpoints: Breakpoints) -> Result<Box<dyn IndexScheme>, String> {
+    let scheme: Box<dyn IndexScheme> = match name.to_ascii_lowercase().as_str() {
+        "us-epa" => Box::new(UsEpa { breakpoints }),
+        "eu-caqi" => Box::new(EuCaqi),
+        "uk-daqi" => Box::new(UkDaqi),
+        "in-naqi" => Box::new(InNaqi),
+        "cn-aqi" => Box::new(CnAqi),
+        "ca-aqhi" => Box::new(CaAqhi),
+        _ => return Err(format!("unknown scheme '{}', expected {}", name, SCHEME_NAMES)),
+    };
+
+    Ok(scheme)
+}
+
+// US EPA AQI -- the colours are the ones the display has always used
+pub struct UsEpa {
+    pub breakpoints: Breakpoints,
+}
+
+const US_EPA: [Category; 6] = [
+    Category { name: "Good", short: "Good", upper: 50, rgb: (0x00, 0x80, 0x00) },
+    Category { name: "Moderate", short: "Moderate", upper: 100, rgb: (0x80, 0x80, 0x00) },
+    Category { name: "Unhealthy for Sensitive Groups", short: "Sensitive", upper: 150, rgb: (0xF0, 0x40, 0x00) },
+    Category { name: "Unhealthy", short: "Unhealthy", upper: 200, rgb: (0xF0, 0x00, 0x00) },
+    Category { name: "Very Unhealthy", short: "Very Unhl", upper: 300, rgb: (0xA0, 0x00, 0x40) },
+    Category { name: "Hazardous", short: "Hazardous", upper: u16::MAX, rgb: (0xFF, 0x00, 0xFF) },
+];
+
+impl IndexScheme for UsEpa {
+    fn code(&self) -> u16 { 0 }
+    fn name(&self) -> &'static str { "US EPA AQI" }
+    fn label(&self) -> &'static str { "AQI" }
+    fn categories(&self) -> &'static [Category] { &US_EPA }
+
+    fn sub_index(&self, pollutant: Pollutant, concentration: f64) -> u16 {
+        sub_index(pollutant, concentration, self.breakpoints)
+    }
+}
+
+// EU Common Air Quality Index, hourly background grid
+//  CF: https://www.airqualitynow.eu/about_indices_definition.php
+pub struct EuCaqi;
+
+const CAQI_PM2_5: [Band; 4] = [
+    ( 0.0,  15.0,  0.0,  25.0),
+    (15.0,  30.0, 25.0,  50.0),
+    (30.0,  55.0, 50.0,  75.0),
+    (55.0, 110.0, 75.0, 100.0),
+];
+
+const CAQI_PM10: [Band; 4] = [
+    ( 0.0,  25.0,  0.0,  25.0),
+    (25.0,  50.0, 25.0,  50.0),
+    (50.0,  90.0, 50.0,  75.0),
+    (90.0, 180.0, 75.0, 100.0),
+];
+
+const EU_CAQI: [Category; 5] = [
+    Category { name: "Very low", short: "Very low", upper: 25, rgb: (0x40, 0xA0, 0x30) },
+    Category { name: "Low", short: "Low", upper: 50, rgb: (0x80, 0xB0, 0x20) },
+    Category { name: "Medium", short: "Medium", upper: 75, rgb: (0xE0, 0xA0, 0x00) },
+    Category { name: "High", short: "High", upper: 100, rgb: (0xF0, 0x60, 0x00) },
+    Category { name: "Very high", short: "Very high", upper: u16::MAX, rgb: (0xE0, 0x20, 0x40) },
+];
+
+impl IndexScheme for EuCaqi {
+    fn code(&self) -> u16 { 1 }
+    fn name(&self) -> &'static str { "EU CAQI" }
+    fn label(&self) -> &'static str { "CAQI" }
+    fn categories(&self) -> &'static [Category] { &EU_CAQI }
+
+    fn sub_index(&self, pollutant: Pollutant, concentration: f64) -> u16 {
+        let bands = match pollutant {
+            Pollutant::Pm2_5 => &CAQI_PM2_5,
+            Pollutant::Pm10 => &CAQI_PM10,
+        };
+        interpolate(bands, concentration.max(0.0)).round() as u16
+    }
+}
+
+// UK Daily Air Quality Index, bands 1..10 on whole ug/m^3
+//  CF: https://uk-air.defra.gov.uk/air-pollution/daqi
+pub struct UkDaqi;
+
+// upper concentration of bands 1..9; anything above is 10
+const DAQI_PM2_5: [f64; 9] = [11.0, 23.0, 35.0, 41.0, 47.0, 53.0, 58.0, 64.0, 70.0];
+const DAQI_PM10: [f64; 9] = [16.0, 33.0, 50.0, 58.0, 66.0, 75.0, 83.0, 91.0, 100.0];
+
+const UK_DAQI: [Category; 4] = [
+    Category { name: "Low", short: "Low", upper: 3, rgb: (0x00, 0x80, 0x00) },
+    Category { name: "Moderate", short: "Moderate", upper: 6, rgb: (0xF0, 0x80, 0x00) },
+    Category { name: "High", short: "High", upper: 9, rgb: (0xF0, 0x00, 0x00) },
+    Category { name: "Very High", short: "Very High", upper: u16::MAX, rgb: (0xA0, 0x00, 0x80) },
+];
+
+impl IndexScheme for UkDaqi {
+    fn code(&self) -> u16 { 2 }
+    fn name(&self) -> &'static str { "UK DAQI" }
+    fn label(&self) -> &'static str { "DAQI" }
+    fn categories(&self) -> &'static [Category] { &UK_DAQI }
+
+    fn sub_index(&self, pollutant: Pollutant, concentration: f64) -> u16 {
+        let uppers = match pollutant {
+            Pollutant::Pm2_5 => &DAQI_PM2_5,
+            Pollutant::Pm10 => &DAQI_PM10,
+        };
+        let c = concentration.max(0.0).trunc();
+        uppers.iter().position(|u| c <= *u).unwrap_or(uppers.len()) as u16 + 1
+    }
+}
+
+// India National Air Quality Index, on whole ug/m^3
+//  CF: https://app.cpcbccr.com/ccr_docs/FINAL-REPORT_AQI_.pdf
+pub struct InNaqi;
+
+const NAQI_PM2_5: [Band; 6] = [
+    (  0.0,  30.0,   0.0,  50.0),
+    ( 31.0,  60.0,  51.0, 100.0),
+    ( 61.0,  90.0, 101.0, 200.0),
+    ( 91.0, 120.0, 201.0, 300.0),
+    (121.0, 250.0, 301.0, 400.0),
+    (251.0, 380.0, 401.0, 500.0),
+];
+
+const NAQI_PM10: [Band; 6] = [
+    (  0.0,  50.0,   0.0,  50.0),
+    ( 51.0, 100.0,  51.0, 100.0),
+    (101.0, 250.0, 101.0, 200.0),
+    (251.0, 350.0, 201.0, 300.0),
+    (351.0, 430.0, 301.0, 400.0),
+    (431.0, 510.0, 401.0, 500.0),
+];
+
+const IN_NAQI: [Category; 6] = [
+    Category { name: "Good", short: "Good", upper: 50, rgb: (0x00, 0x80, 0x20) },
+    Category { name: "Satisfactory", short: "Satisfy", upper: 100, rgb: (0x60, 0xB0, 0x20) },
+    Category { name: "Moderately polluted", short: "Moderate", upper: 200, rgb: (0x80, 0x80, 0x00) },
+    Category { name: "Poor", short: "Poor", upper: 300, rgb: (0xF0, 0x60, 0x00) },
+    Category { name: "Very poor", short: "Very poor", upper: 400, rgb: (0xF0, 0x00, 0x00) },
+    Category { name: "Severe", short: "Severe", upper: u16::MAX, rgb: (0x80, 0x00, 0x00) },
+];
+
+impl IndexScheme for InNaqi {
+    fn code(&self) -> u16 { 3 }
+    fn name(&self) -> &'static str { "India NAQI" }
+    fn label(&self) -> &'static str { "AQI" }
+    fn categories(&self) -> &'static [Category] { &IN_NAQI }
+
+    fn sub_index(&self, pollutant: Pollutant, concentration: f64) -> u16 {
+        let bands = match pollutant {
+            Pollutant::Pm2_5 => &NAQI_PM2_5,
+            Pollutant::Pm10 => &NAQI_PM10,
+        };
+        interpolate(bands, concentration.max(0.0).trunc()).round() as u16
+    }
+}
+
+// China AQI (HJ 633-2012); individual indices are rounded up
+pub struct CnAqi;
+
+const CN_PM2_5: [Band; 7] = [
+    (  0.0,  35.0,   0.0,  50.0),
+    ( 35.0,  75.0,  50.0, 100.0),
+    ( 75.0, 115.0, 100.0, 150.0),
+    (115.0, 150.0, 150.0, 200.0),
+    (150.0, 250.0, 200.0, 300.0),
+    (250.0, 350.0, 300.0, 400.0),
+    (350.0, 500.0, 400.0, 500.0),
+];
+
+const CN_PM10: [Band; 7] = [
+    (  0.0,  50.0,   0.0,  50.0),
+    ( 50.0, 150.0,  50.0, 100.0),
+    (150.0, 250.0, 100.0, 150.0),
+    (250.0, 350.0, 150.0, 200.0),
+    (350.0, 420.0, 200.0, 300.0),
+    (420.0, 500.0, 300.0, 400.0),
+    (500.0, 600.0, 400.0, 500.0),
+];
+
+const CN_AQI: [Category; 6] = [
+    Category { name: "Excellent", short: "Excellent", upper: 50, rgb: (0x00, 0x80, 0x00) },
+    Category { name: "Good", short: "Good", upper: 100, rgb: (0x80, 0x80, 0x00) },
+    Category { name: "Lightly polluted", short: "Light", upper: 150, rgb: (0xF0, 0x40, 0x00) },
+    Category { name: "Moderately polluted", short: "Moderate", upper: 200, rgb: (0xF0, 0x00, 0x00) },
+    Category { name: "Heavily polluted", short: "Heavy", upper: 300, rgb: (0xA0, 0x00, 0x40) },
+    Category { name: "Severely polluted", short: "Severe", upper: u16::MAX, rgb: (0x80, 0x00, 0x20) },
+];
+
+impl IndexScheme for CnAqi {
+    fn code(&self) -> u16 { 4 }
+    fn name(&self) -> &'static str { "China AQI" }
+    fn label(&self) -> &'static str { "AQI" }
+    fn categories(&self) -> &'static [Category] { &CN_AQI }
+
+    fn sub_index(&self, pollutant: Pollutant, concentration: f64) -> u16 {
+        let bands = match pollutant {
+            Pollutant::Pm2_5 => &CN_PM2_5,
+            Pollutant::Pm10 => &CN_PM10,
+        };
+        // the nudge keeps exact band edges, 75 -> 100.00000000000001, from rounding up
+        (interpolate(bands, concentration.max(0.0)) - 1e-9).ceil().max(0.0) as u16
+    }
+}
+
+// Canada AQHI-style: the full AQHI needs ozone and NO2, which we don't measure.
+//  This is AQHI+, the PM2.5-only amendment used for wildfire smoke -- one point
+//  per 10 ug/m^3, rounded up, never below 1. PM10 doesn't count.
+//  CF: https://www.canada.ca/en/environment-climate-change/services/air-quality-health-index/about.html
+pub struct CaAqhi;
+
+const CA_AQHI: [Category; 4] = [
+    Category { name: "Low risk", short: "Low risk", upper: 3, rgb: (0x00, 0x40, 0xA0) },
+    Category { name: "Moderate risk", short: "Moderate", upper: 6, rgb: (0x80, 0x80, 0x00) },
+    Category { name: "High risk", short: "High risk", upper: 10, rgb: (0xF0, 0x00, 0x00) },
+    Category { name: "Very high risk", short: "Very high", upper: u16::MAX, rgb: (0x80, 0x00, 0x20) },
+];
+
+impl IndexScheme for CaAqhi {
+    fn code(&self) -> u16 { 5 }
+    fn name(&self) -> &'static str { "Canada AQHI+" }
+    fn label(&self) -> &'static str { "AQHI" }
+    fn categories(&self) -> &'static [Category] { &CA_AQHI }
+
+    fn sub_index(&self, pollutant: Pollutant, concentration: f64) -> u16 {
+        match pollutant {
+            Pollutant::Pm2_5 => (concentration / 10.0).ceil().max(1.0).min(u16::MAX as f64) as u16,
+            Pollutant::Pm10 => 0,
+        }
+    }
+}
@@ -0,0 +1,102 @@
+#[cfg(test)]
+mod schemes_tests {
+    use crate::aqi::schemes::*;
+    use crate::aqi::{Breakpoints, Pollutant};
+
+    fn scheme(name: &str) -> Box<dyn IndexScheme> {
+        from_name(name, Breakpoints::Epa2024).unwrap()
+    }
+
+    #[test]
+    fn every_scheme_has_a_name_and_a_unique_code() {
+        let names = ["us-epa", "eu-caqi", "uk-daqi", "in-naqi", "cn-aqi", "ca-aqhi"];
+        let mut codes: Vec<u16> = names.iter().map(|n| scheme(n).code()).collect();
+        codes.sort();
+        codes.dedup();
+
+        assert_eq!(codes.len(), names.len());
+        assert!(from_name("EU-CAQI", Breakpoints::Epa2024).is_ok());
+        assert!(from_name("who", Breakpoints::Epa2024).is_err());
+    }
+
+    #[test]
+    fn categories_cover_every_value_and_fit_the_lcd() {
+        for name in ["us-epa", "eu-caqi", "uk-daqi", "in-naqi", "cn-aqi", "ca-aqhi"] {
+            let s = scheme(name);
+            let cats = s.categories();
+
+            assert_eq!(cats.last().unwrap().upper, u16::MAX, "{}", name);
+            assert!(cats.windows(2).all(|w| w[0].upper < w[1].upper), "{}", name);
+            assert!(cats.iter().all(|c| c.short.len() <= 10), "{}", name);
+            assert!(s.label().len() <= 4, "{}", name);
+        }
+    }
+
+    #[test]
+    fn us_epa_matches_the_aqi_module() {
+        let i = scheme("us-epa").index(40.0, 20.0);
+
+        assert_eq!(i.value, crate::aqi::aqi(40.0, 20.0, Breakpoints::Epa2024).value);
+        assert_eq!(i.category, 2);
+        assert_eq!(scheme("us-epa").category(i.category).name, "Unhealthy for Sensitive Groups");
+    }
+
+    #[test]
+    fn eu_caqi_bands() {
+        let s = scheme("eu-caqi");
+        assert_eq!(s.sub_index(Pollutant::Pm2_5, 15.0), 25);
+        assert_eq!(s.sub_index(Pollutant::Pm2_5, 55.0), 75);
+        assert_eq!(s.sub_index(Pollutant::Pm10, 70.0), 63);
+
+        let i = s.index(10.0, 200.0);
+        assert_eq!(i.dominant, Pollutant::Pm10);
+        assert_eq!(s.category(i.category).name, "Very high");
+    }
+
+    #[test]
+    fn uk_daqi_bands() {
+        let s = scheme("uk-daqi");
+        assert_eq!(s.sub_index(Pollutant::Pm2_5, 0.0), 1);
+        assert_eq!(s.sub_index(Pollutant::Pm2_5, 11.9), 1);
+        assert_eq!(s.sub_index(Pollutant::Pm2_5, 12.0), 2);
+        assert_eq!(s.sub_index(Pollutant::Pm2_5, 71.0), 10);
+        assert_eq!(s.sub_index(Pollutant::Pm10, 51.0), 4);
+
+        assert_eq!(s.category(s.index(36.0, 0.0).category).name, "Moderate");
+    }
+
+    #[test]
+    fn in_naqi_bands() {
+        let s = scheme("in-naqi");
+        assert_eq!(s.sub_index(Pollutant::Pm2_5, 30.0), 50);
+        assert_eq!(s.sub_index(Pollutant::Pm2_5, 30.9), 50);
+        assert_eq!(s.sub_index(Pollutant::Pm2_5, 31.0), 51);
+        assert_eq!(s.sub_index(Pollutant::Pm10, 250.0), 200);
+    }
+
+    #[test]
+    fn cn_aqi_rounds_up() {
+        let s = scheme("cn-aqi");
+        assert_eq!(s.sub_index(Pollutant::Pm2_5, 35.0), 50);
+        assert_eq!(s.sub_index(Pollutant::Pm2_5, 35.1), 51);
+        assert_eq!(s.sub_index(Pollutant::Pm2_5, 75.0), 100);
+        assert_eq!(s.sub_index(Pollutant::Pm10, 420.0), 300);
+    }
+
+    #[test]
+    fn ca_aqhi_is_pm2_5_only() {
+        let s = scheme("ca-aqhi");
+        assert_eq!(s.sub_index(Pollutant::Pm2_5, 0.0), 1);
+        assert_eq!(s.sub_index(Pollutant::Pm2_5, 10.0), 1);
+        assert_eq!(s.sub_index(Pollutant::Pm2_5, 10.1), 2);
+
+        let i = s.index(5.0, 500.0);
+        assert_eq!((i.value, i.dominant), (1, Pollutant::Pm2_5));
+        assert_eq!(s.category(s.index(115.0, 0.0).category).name, "Very high risk");
+    }
+
+    #[test]
+    fn unknown_category_codes_fall_back() {
+        assert_eq!(scheme("uk-daqi").category(99).name, "Low");
+    }
+}
@@ -0,0 +1,108 @@
+#[cfg(test)]
+mod aqi_tests {
+    use crate::aqi::*;
+
+    const HOUR: u64 = 3600;
+    // some clock hour boundary
+    const T0: u64 = 1_700_000_000 / HOUR * HOUR;
+
+    #[test]
+    fn breakpoint_edges_2024() {
+        let b = Breakpoints::Epa2024;
+        assert_eq!(sub_index(Pollutant::Pm2_5, 0.0, b), 0);
+        assert_eq!(sub_index(Pollutant::Pm2_5, 9.0, b), 50);
+        assert_eq!(sub_index(Pollutant::Pm2_5, 9.1, b), 51);
+        assert_eq!(sub_index(Pollutant::Pm2_5, 35.4, b), 100);
+        assert_eq!(sub_index(Pollutant::Pm2_5, 35.5, b), 101);
+        assert_eq!(sub_index(Pollutant::Pm2_5, 125.4, b), 200);
+        assert_eq!(sub_index(Pollutant::Pm2_5, 325.4, b), 500);
+    }
+
+    #[test]
+    fn breakpoint_edges_2012() {
+        let b = Breakpoints::Epa2012;
+        assert_eq!(sub_index(Pollutant::Pm2_5, 12.0, b), 50);
+        assert_eq!(sub_index(Pollutant::Pm2_5, 12.1, b), 51);
+        assert_eq!(sub_index(Pollutant::Pm2_5, 150.4, b), 200);
+        assert_eq!(sub_index(Pollutant::Pm2_5, 500.4, b), 500);
+    }
+
+    #[test]
+    fn truncates_before_lookup() {
+        // used to fall in the gap between 12.0 and 12.1 and come out as ~50.6
+        assert_eq!(sub_index(Pollutant::Pm2_5, 12.05, Breakpoints::Epa2012), 50);
+        assert_eq!(sub_index(Pollutant::Pm2_5, 9.09, Breakpoints::Epa2024), 50);
+        assert_eq!(sub_index(Pollutant::Pm10, 54.9, Breakpoints::Epa2024), 50);
+        assert_eq!(sub_index(Pollutant::Pm10, 55.0, Breakpoints::Epa2024), 51);
+    }
+
+    #[test]
+    fn aqi_is_the_worst_sub_index() {
+        let a = aqi(5.0, 200.0, Breakpoints::Epa2024);
+
+        assert_eq!(a.pm2_5, 28);
+        assert_eq!(a.pm10, 123);
+        assert_eq!(a.value, 123);
+        assert_eq!(a.dominant, Pollutant::Pm10);
+
+        let a = aqi(40.0, 20.0, Breakpoints::Epa2024);
+        assert_eq!(a.value, 112);
+        assert_eq!(a.dominant, Pollutant::Pm2_5);
+    }
+
+    #[test]
+    fn beyond_the_aqi_keeps_climbing() {
+        assert!(sub_index(Pollutant::Pm2_5, 400.0, Breakpoints::Epa2024) > 500);
+        assert!(sub_index(Pollutant::Pm10, 700.0, Breakpoints::Epa2024) > 500);
+    }
+
+    #[test]
+    fn nowcast_needs_two_of_the_last_three_hours() {
+        let mut h = HourlyHistory::default();
+        h.push(T0, 10.0);
+        assert_eq!(h.nowcast(T0), None);
+
+        h.push(T0 + HOUR, 10.0);
+        assert_eq!(h.nowcast(T0 + HOUR), Some(10.0));
+
+        // both are now too old
+        assert_eq!(h.nowcast(T0 + 4 * HOUR), None);
+    }
+
+    #[test]
+    fn nowcast_weights_recent_hours() {
+        // hourly PM2.5, newest first; w = 57/77
+        let hourly = [64.0, 63.0, 72.0, 77.0, 65.0, 61.0, 70.0, 71.0, 64.0, 57.0, 58.0, 64.0];
+        let mut h = HourlyHistory::default();
+        for (ago, c) in hourly.iter().enumerate().rev() {
+            h.push(T0 + (11 - ago as u64) * HOUR, *c);
+        }
+
+        let nowcast = h.nowcast(T0 + 11 * HOUR).unwrap();
+        assert!((nowcast - 66.57).abs() < 0.01, "{}", nowcast);
+    }
+
+    #[test]
+    fn nowcast_weights_bottom_out_at_a_half() {
+        let mut h = HourlyHistory::default();
+        h.push(T0, 0.0);
+        h.push(T0 + HOUR, 100.0);
+
+        // w = 0.5: (100 + 0.5 * 0) / 1.5
+        let nowcast = h.nowcast(T0 + HOUR).unwrap();
+        assert!((nowcast - 66.67).abs() < 0.01, "{}", nowcast);
+    }
+
+    #[test]
+    fn nowcast_averages_within_the_hour() {
+        let mut n = NowCast::new();
+        n.push(T0, 8.0, 20.0);
+        n.push(T0 + HOUR, 4.0, 20.0);
+        n.push(T0 + HOUR + 60, 6.0, 20.0);
+
+        let a = n.aqi(T0 + HOUR + 60, Breakpoints::Epa2024).unwrap();
+        // w = 5/8; (5 + 0.625 * 8) / 1.625
+        assert_eq!(a.pm2_5, sub_index(Pollutant::Pm2_5, 10.0 / 1.625, Breakpoints::Epa2024));
+        assert_eq!(a.pm10, 19);
+    }
+}
@@ -0,0 +1,339 @@
+#[cfg(test)]
+mod tests;
+
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::aqi::{schemes::Index, Aqi};
+use crate::duty_cycle::Phase;
+use crate::payload::{bme680::Bme680Reading, sds011::Sds011Reading, sps30::Sps30Reading, FrameStats, Pms5003Reading};
+use crate::sensors::{simulated::SimulatedPm, Health, Kind};
+
+// Measurement bus
+//
+//  Sensors publish typed events; outputs (the Modbus registers, the LCD, the
+//  log) subscribe independently and each get their own copy of every event
+//  published after they subscribed. Nothing is shared between them but the bus,
+//  so a new sink is one more subscriber and a slow one holds up no one else.
+//
+//  Subscriptions are plain channels. A subscriber that goes away is dropped from
+//  the bus on the next publish.
+
+pub struct Bus<T> {
+    subscribers: Arc<Mutex<Vec<mpsc::Sender<T>>>>,
+}
+
+// every clone publishes to the same subscribers
+impl<T> Clone for Bus<T> {
+    fn clone(&self) -> Self {
+        Bus { subscribers: self.subscribers.clone() }
+    }
+}
+
+impl<T> Default for Bus<T> {
+    fn default() -> Self {
+        Bus { subscribers: Arc::new(Mutex::new(Vec::new())) }
+    }
+}
+
+impl<T: Clone + Send + 'static> Bus<T> {
+    pub fn new() -> Self {
+        Bus::default()
+    }
+
+    pub fn subscribe(&self) -> mpsc::Receiver<T> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers.lock().unwrap().push(tx);
+        rx
+    }
+
+    // runs the observer on its own thread until the bus goes away
+    pub fn attach<O: Observer<T>>(&self, mut observer: O) -> thread::JoinHandle<()> {
+        let events = self.subscribe();
+        thread::spawn(move || {
+            for event in events {
+                observer.update(&event);
+            }
+        })
+    }
+
+    // same as attach, but the observer also gets a tick every `every`, events
+    //  or not
+    pub fn attach_ticking<O: Observer<T>>(&self, mut observer: O, every: Duration) -> thread::JoinHandle<()> {
+        let events = self.subscribe();
+        thread::spawn(move || {
+            let mut next = Instant::now() + every;
+            loop {
+                match events.recv_timeout(next.saturating_duration_since(Instant::now())) {
+                    Ok(event) => observer.update(&event),
+                    Err(mpsc::RecvTimeoutError::Timeout) => {},
+                    Err(mpsc::RecvTimeoutError::Disconnected) => return,
+                }
+                if Instant::now() >= next {
+                    observer.tick();
+                    next = Instant::now() + every;
+                }
+            }
+        })
+    }
+
+    pub fn publish(&self, event: T) {
+        self.subscribers.lock().unwrap()
+            .retain(|s| s.send(event.clone()).is_ok());
+    }
+}
+
+pub trait Observer<T>: Send + 'static {
+    fn update(&mut self, event: &T);
+
+    // only called for observers attached with attach_ticking
+    fn tick(&mut self) {}
+}
+
+// what the sensors publish
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    // straight from the sensor -- the indices are computed downstream
+    Pm(PmReading),
+    Particulate(Particulate),
+    Climate(Climate),
+    Co2(Co2),
+    // straight from the sensor -- the VOC and NOx indices too are computed
+    //  downstream
+    Voc(GasReading),
+    Gas(Gas),
+    IndoorAir(IndoorAir),
+    Link(Link),
+    ClimateHealth(ClimateHealth),
+    // whenever a sensor's quality changes
+    Quality(Kind, Quality),
+}
+
+// a particulate reading, from whichever sensor the unit has
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PmReading {
+    Pms5003(Pms5003Reading),
+    Sds011(Sds011Reading),
+    Sps30(Sps30Reading),
+    Simulated(SimulatedPm),
+}
+
+impl PmReading {
+    // as published in the PM_SENSOR register
+    pub fn code(&self) -> u16 {
+        match self {
+            PmReading::Pms5003(_) => 0,
+            PmReading::Sds011(_) => 1,
+            PmReading::Sps30(_) => 2,
+            PmReading::Simulated(_) => 3,
+        }
+    }
+
+    // ug/m^3, CF=1 for the PMS5003
+    pub fn pm2_5(&self) -> f64 {
+        match self {
+            PmReading::Pms5003(r) => r.pm2_5_cf1 as f64,
+            PmReading::Sds011(r) => r.pm2_5,
+            PmReading::Sps30(r) => r.pm2_5,
+            PmReading::Simulated(r) => r.pm2_5,
+        }
+    }
+
+    pub fn pm10(&self) -> f64 {
+        match self {
+            PmReading::Pms5003(r) => r.pm10_cf1 as f64,
+            PmReading::Sds011(r) => r.pm10,
+            PmReading::Sps30(r) => r.pm10,
+            PmReading::Simulated(r) => r.pm10,
+        }
+    }
+}
+
+// a stabilized particulate reading and the indices computed from it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Particulate {
+    pub reading: PmReading,
+    // PM2.5 and PM10 as fed to the indices, after any correction
+    pub pm2_5: f64,
+    pub pm10: f64,
+    pub aqi: Aqi,
+    // None until there is enough history
+    pub nowcast: Option<Aqi>,
+    // in the configured scheme
+    pub index: Index,
+    pub at: SystemTime,
+}
+
+// temperature in degC and relative humidity in %, from whichever sensor the
+//  unit has -- and pressure in hPa from those that measure it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Climate {
+    pub temperature: f64,
+    pub humidity: f64,
+    pub pressure: Option<f64>,
+    pub at: SystemTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Co2Sensor {
+    SenseairS8,
+    Mhz19,
+    Scd30,
+    Scd4x,
+}
+
+impl Co2Sensor {
+    // as published in the CO2_SENSOR register
+    pub fn code(&self) -> u16 {
+        match self {
+            Co2Sensor::SenseairS8 => 0,
+            Co2Sensor::Mhz19 => 1,
+            Co2Sensor::Scd30 => 2,
+            Co2Sensor::Scd4x => 3,
+        }
+    }
+}
+
+// CO2 in ppm -- `ppm` after any temperature and humidity compensation, `raw`
+//  as the sensor reported it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Co2 {
+    pub sensor: Co2Sensor,
+    pub ppm: f64,
+    pub raw: f64,
+    pub compensated: bool,
+    pub at: SystemTime,
+}
+
+// a gas sensor reading, from whichever sensor the unit has
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GasReading {
+    // raw signals in ticks
+    Sgp40 { voc: u16 },
+    Sgp41 { voc: u16, nox: u16 },
+    Bme680(Bme680Reading),
+}
+
+impl GasReading {
+    // as published in the GAS_SENSOR register
+    pub fn code(&self) -> u16 {
+        match self {
+            GasReading::Sgp40 { .. } => 0,
+            GasReading::Sgp41 { .. } => 1,
+            GasReading::Bme680(_) => 2,
+        }
+    }
+}
+
+// a gas reading and the Sensirion indices computed from it -- None for sensors
+//  without the signal, and for the first 45 s after a start
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gas {
+    pub reading: GasReading,
+    pub voc_index: Option<u16>,
+    pub nox_index: Option<u16>,
+    pub at: SystemTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pollutant {
+    Pm2_5,
+    Co2,
+    Voc,
+    Nox,
+}
+
+impl Pollutant {
+    // as published in the IAQ_DOMINANT register
+    pub fn code(&self) -> u16 {
+        match self {
+            Pollutant::Pm2_5 => 0,
+            Pollutant::Co2 => 1,
+            Pollutant::Voc => 2,
+            Pollutant::Nox => 3,
+        }
+    }
+}
+
+// the combined indoor air score, 1 (excellent) to 5 (unhealthy) -- the worst of
+//  whatever the unit measures, and which measurement that was
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IndoorAir {
+    pub score: u8,
+    pub dominant: Pollutant,
+    pub at: SystemTime,
+}
+
+// PMS5003 serial link health, on every pass of the sampling loop whether or not
+//  a frame arrived
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Link {
+    pub stats: FrameStats,
+    // None before the first good frame
+    pub frame_age: Option<Duration>,
+    pub phase: Phase,
+}
+
+// AM2302 / DHT read health, on every pass of the driver whether or not a read
+//  got through
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClimateHealth {
+    // readings accepted, and failed reads counting every retry
+    pub health: Health,
+    // readings that passed their checksum but not the plausibility filter
+    pub rejected: u64,
+}
+
+// how far the latest reading of a measurement can be trusted -- one word per
+//  sensor kind, kept up by the sampling threads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quality {
+    Valid,
+    // nothing from the sensor since the start
+    NoData,
+    // the latest reading is older than the sensor's interval allows
+    Stale,
+    // readings coming in, but before the sensor's specified warm-up time
+    WarmingUp,
+    // a reading beyond what the sensor is specified for -- published anyway
+    OutOfRange,
+    // the sensor failed to initialise or its last read failed
+    SensorError,
+    // readings that don't come from hardware -- see sensors/simulated.rs
+    Simulated,
+}
+
+impl Quality {
+    // as published in the quality registers
+    pub fn code(&self) -> u16 {
+        match self {
+            Quality::Valid => 0,
+            Quality::NoData => 1,
+            Quality::Stale => 2,
+            Quality::WarmingUp => 3,
+            Quality::OutOfRange => 4,
+            Quality::SensorError => 5,
+            Quality::Simulated => 6,
+        }
+    }
+
+    // whether the value is fit to show
+    pub fn is_valid(&self) -> bool {
+        matches!(self, Quality::Valid | Quality::Simulated)
+    }
+
+    // for the LCD's status line -- 16 chars at most
+    pub fn label(&self) -> &'static str {
+        match self {
+            Quality::Valid => "valid",
+            Quality::NoData => "no reading yet",
+            Quality::Stale => "stale",
+            Quality::WarmingUp => "warming up",
+            Quality::OutOfRange => "out of range",
+            Quality::SensorError => "sensor error",
+            Quality::Simulated => "simulated",
+        }
+    }
+}
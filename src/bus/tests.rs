@@ -0,0 +1,100 @@
+#[cfg(test)]
+mod bus_tests {
+    use crate::bus::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn every_subscriber_gets_every_event() {
+        let bus = Bus::new();
+        let (a, b) = (bus.subscribe(), bus.subscribe());
+        bus.publish(1);
+        bus.publish(2);
+
+        assert_eq!(a.try_iter().collect::<Vec<u32>>(), vec![1, 2]);
+        assert_eq!(b.try_iter().collect::<Vec<u32>>(), vec![1, 2]);
+    }
+
+    #[test]
+    fn late_subscribers_only_see_later_events() {
+        let bus = Bus::new();
+        let early = bus.subscribe();
+        bus.publish(1);
+        let late = bus.subscribe();
+        bus.publish(2);
+
+        assert_eq!(early.try_iter().count(), 2);
+        assert_eq!(late.try_iter().collect::<Vec<u32>>(), vec![2]);
+    }
+
+    #[test]
+    fn clones_publish_to_the_same_subscribers() {
+        let bus = Bus::new();
+        let events = bus.subscribe();
+        let publisher = bus.clone();
+        std::thread::spawn(move || publisher.publish(7)).join().unwrap();
+
+        assert_eq!(events.try_recv(), Ok(7u32));
+    }
+
+    #[test]
+    fn dropped_subscribers_are_removed() {
+        let bus = Bus::new();
+        let kept = bus.subscribe();
+        drop(bus.subscribe());
+        assert_eq!(bus.subscribers.lock().unwrap().len(), 2);
+
+        bus.publish(1u32);
+        assert_eq!(bus.subscribers.lock().unwrap().len(), 1);
+        assert_eq!(kept.try_recv(), Ok(1));
+    }
+
+    struct Forward(mpsc::Sender<u32>);
+
+    impl Observer<u32> for Forward {
+        fn update(&mut self, event: &u32) {
+            let _ = self.0.send(event * 10);
+        }
+    }
+
+    #[test]
+    fn attached_observers_run_on_their_own_thread() {
+        let bus = Bus::new();
+        let (tx, rx) = mpsc::channel();
+        let handle = bus.attach(Forward(tx));
+        bus.publish(1);
+        bus.publish(2);
+
+        assert_eq!(rx.recv_timeout(Duration::from_secs(1)), Ok(10));
+        assert_eq!(rx.recv_timeout(Duration::from_secs(1)), Ok(20));
+
+        // the observer's thread ends with the bus
+        drop(bus);
+        handle.join().unwrap();
+    }
+
+    struct Ticks(mpsc::Sender<u32>);
+
+    impl Observer<u32> for Ticks {
+        fn update(&mut self, event: &u32) {
+            let _ = self.0.send(*event);
+        }
+
+        fn tick(&mut self) {
+            let _ = self.0.send(0);
+        }
+    }
+
+    #[test]
+    fn ticking_observers_tick_without_events() {
+        let bus = Bus::new();
+        let (tx, rx) = mpsc::channel();
+        let handle = bus.attach_ticking(Ticks(tx), Duration::from_millis(10));
+        assert_eq!(rx.recv_timeout(Duration::from_secs(1)), Ok(0));
+        bus.publish(5);
+        assert_eq!(rx.iter().find(|&e| e != 0), Some(5));
+
+        drop(bus);
+        handle.join().unwrap();
+    }
+}
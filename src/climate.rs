@@ -0,0 +1,71 @@
+#[cfg(test)]
+mod tests;
+
+use std::ops::RangeInclusive;
+
+// Plausibility filtering for temperature and humidity readings
+//
+//  A bit-banged read that passes its checksum can still be garbage -- a bit
+//  slipped early enough shifts everything after it. Readings outside what the
+//  sensor can measure are dropped outright. A reading that jumps further than
+//  the air plausibly changes between two reads is held back until the next
+//  one agrees with it: a real change shows up twice, a glitch doesn't.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Accept,
+    OutOfRange,
+    // too far from the last accepted reading, and not confirmed yet
+    Jump,
+}
+
+#[derive(Debug, Clone)]
+pub struct Plausibility {
+    temperature: RangeInclusive<f64>,
+    humidity: RangeInclusive<f64>,
+    max_temp_step: f64,
+    max_humidity_step: f64,
+    last: Option<(f64, f64)>,
+    pending: Option<(f64, f64)>,
+}
+
+impl Plausibility {
+    // the sensor's measuring range, and the largest believable change between
+    //  consecutive readings
+    pub fn new(temperature: RangeInclusive<f64>, humidity: RangeInclusive<f64>, max_temp_step: f64, max_humidity_step: f64) -> Self {
+        Plausibility { temperature, humidity, max_temp_step, max_humidity_step, last: None, pending: None }
+    }
+
+    fn close(&self, a: (f64, f64), b: (f64, f64)) -> bool {
+        (a.0 - b.0).abs() <= self.max_temp_step && (a.1 - b.1).abs() <= self.max_humidity_step
+    }
+
+    // degC and %RH
+    pub fn check(&mut self, temperature: f64, humidity: f64) -> Verdict {
+        if !self.temperature.contains(&temperature) || !self.humidity.contains(&humidity) {
+            return Verdict::OutOfRange;
+        }
+
+        let reading = (temperature, humidity);
+        let accept = match (self.last, self.pending) {
+            (None, _) => true,
+            (Some(last), _) if self.close(last, reading) => true,
+            (_, Some(pending)) => self.close(pending, reading),
+            _ => false,
+        };
+        if !accept {
+            self.pending = Some(reading);
+            return Verdict::Jump;
+        }
+
+        self.last = Some(reading);
+        self.pending = None;
+        Verdict::Accept
+    }
+
+    // after a long gap the last reading says nothing about the next one
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.pending = None;
+    }
+}
@@ -0,0 +1,45 @@
+#[cfg(test)]
+mod climate_tests {
+    use crate::climate::*;
+
+    fn am2302() -> Plausibility {
+        Plausibility::new(-40.0..=80.0, 0.0..=100.0, 5.0, 20.0)
+    }
+
+    #[test]
+    fn out_of_range_is_dropped() {
+        let mut p = am2302();
+        assert_eq!(p.check(21.0, 45.0), Verdict::Accept);
+        assert_eq!(p.check(3276.7, 45.0), Verdict::OutOfRange);
+        assert_eq!(p.check(21.0, 101.0), Verdict::OutOfRange);
+        assert_eq!(p.check(21.2, 45.5), Verdict::Accept);
+    }
+
+    #[test]
+    fn a_lone_spike_is_dropped() {
+        let mut p = am2302();
+        assert_eq!(p.check(21.0, 45.0), Verdict::Accept);
+        assert_eq!(p.check(51.0, 45.0), Verdict::Jump);
+        assert_eq!(p.check(21.1, 45.2), Verdict::Accept);
+        assert_eq!(p.check(21.0, 5.0), Verdict::Jump);
+        assert_eq!(p.check(21.0, 45.0), Verdict::Accept);
+    }
+
+    #[test]
+    fn a_real_change_is_accepted_once_confirmed() {
+        // a door opened onto the balcony in winter
+        let mut p = am2302();
+        assert_eq!(p.check(21.0, 45.0), Verdict::Accept);
+        assert_eq!(p.check(12.0, 60.0), Verdict::Jump);
+        assert_eq!(p.check(11.5, 62.0), Verdict::Accept);
+        assert_eq!(p.check(11.0, 63.0), Verdict::Accept);
+    }
+
+    #[test]
+    fn anything_in_range_after_a_reset() {
+        let mut p = am2302();
+        p.check(21.0, 45.0);
+        p.reset();
+        assert_eq!(p.check(35.0, 20.0), Verdict::Accept);
+    }
+}
@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod tests;
+
+use std::time::SystemTime;
+
+use crate::bus::{Climate, Co2, Co2Sensor, Event};
+
+// CO2 readings
+//
+//  NDIR sensors count CO2 molecules in their cell, so what they report as ppm
+//  is only right at the temperature they were calibrated at, and counts the
+//  water vapour in the air as part of the mix. With [co2] compensate, readings
+//  from sensors without a humidity sensor of their own are brought to 25 degC
+//  and dry air with the latest temperature and humidity reading:
+//
+//    ppm * (T + 273.15) / 298.15 / (1 - e / p)
+//
+//  e the water vapour pressure from the relative humidity (Magnus), p the
+//  standard atmosphere. The SCD30 and SCD4x compensate with their own sensors.
+
+const REFERENCE_KELVIN: f64 = 298.15;
+const STANDARD_PRESSURE_HPA: f64 = 1013.25;
+
+// temperature in degC, relative humidity in %
+pub fn compensate(ppm: f64, temperature: f64, humidity: f64) -> f64 {
+    let saturation = 6.112 * (17.62 * temperature / (243.12 + temperature)).exp();
+    let vapour = humidity.clamp(0.0, 100.0) / 100.0 * saturation / STANDARD_PRESSURE_HPA;
+
+    ppm * (temperature + 273.15) / REFERENCE_KELVIN / (1.0 - vapour)
+}
+
+// for drivers that compensate: keeps the latest temperature and humidity
+//  reading from the bus
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Compensator {
+    enabled: bool,
+    climate: Option<Climate>,
+}
+
+impl Compensator {
+    pub fn new(enabled: bool) -> Self {
+        Compensator { enabled, climate: None }
+    }
+
+    pub fn observe(&mut self, event: &Event) {
+        if let Event::Climate(c) = event {
+            self.climate = Some(*c);
+        }
+    }
+
+    // the reading to publish, and whether it was compensated -- it isn't until
+    //  the first temperature and humidity reading arrives
+    pub fn apply(&self, ppm: f64) -> (f64, bool) {
+        match self.climate.filter(|_| self.enabled) {
+            Some(c) => (compensate(ppm, c.temperature, c.humidity), true),
+            None => (ppm, false),
+        }
+    }
+
+    // a reading from the sensor, ready to publish
+    pub fn event(&self, sensor: Co2Sensor, raw: f64) -> Event {
+        let (ppm, compensated) = self.apply(raw);
+        Event::Co2(Co2 { sensor, ppm, raw, compensated, at: SystemTime::now() })
+    }
+}
+
+// for the LCD, after the German Umweltbundesamt's guidance for indoor air:
+//  below 1000 ppm is fine, above 2000 ppm is unacceptable
+pub fn level(ppm: f64) -> &'static str {
+    match ppm {
+        x if x < 1000.0 => "fine",
+        x if x <= 2000.0 => "elevated",
+        _ => "too high",
+    }
+}
@@ -0,0 +1,51 @@
+#[cfg(test)]
+mod co2_tests {
+    use std::time::SystemTime;
+
+    use crate::bus::{Climate, Event};
+    use crate::co2::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 0.5
+    }
+
+    #[test]
+    fn nothing_to_compensate_at_the_reference() {
+        // at 25 degC in perfectly dry air
+        assert_eq!(compensate(800.0, 25.0, 0.0), 800.0);
+    }
+
+    #[test]
+    fn warm_humid_air_reads_low() {
+        // 3% water vapour at 25 degC, 100 %RH
+        assert!(close(compensate(1000.0, 25.0, 100.0), 1032.3));
+        assert!(close(compensate(1000.0, 35.0, 0.0), 1033.5));
+        assert!(compensate(1000.0, 15.0, 0.0) < 1000.0);
+    }
+
+    #[test]
+    fn compensator_waits_for_a_climate_reading() {
+        let mut c = Compensator::new(true);
+        assert_eq!(c.apply(800.0), (800.0, false));
+
+        c.observe(&Event::Climate(Climate { temperature: 35.0, humidity: 0.0, pressure: None, at: SystemTime::now() }));
+        let (ppm, compensated) = c.apply(1000.0);
+        assert!(close(ppm, 1033.5));
+        assert!(compensated);
+    }
+
+    #[test]
+    fn compensator_off() {
+        let mut c = Compensator::new(false);
+        c.observe(&Event::Climate(Climate { temperature: 35.0, humidity: 50.0, pressure: None, at: SystemTime::now() }));
+        assert_eq!(c.apply(800.0), (800.0, false));
+    }
+
+    #[test]
+    fn levels() {
+        assert_eq!(level(420.0), "fine");
+        assert_eq!(level(1000.0), "elevated");
+        assert_eq!(level(2000.0), "elevated");
+        assert_eq!(level(2400.0), "too high");
+    }
+}
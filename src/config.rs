@@ -1,9 +1,736 @@
 #[cfg(test)]
 mod tests;
 
-pub fn parse_config(args: &[String]) -> &str {
-    let filename = &args.get(1)
-        .expect("no file given");
+use std::{
+    env,
+    fmt,
+    fs,
+    net::{IpAddr, SocketAddr},
+};
 
-    filename
-}
\ No newline at end of file
+use serde::Deserialize;
+
+use crate::aqi::{schemes::{self, IndexScheme}, Breakpoints};
+use crate::correction::Correction;
+use crate::measurements::{Window, STANDARD_WINDOWS};
+use crate::payload::{bme280, bme680, sht3x, sht4x};
+use crate::registers::{Encoding, WordOrder};
+use crate::sensors;
+use crate::serial;
+
+// Runtime configuration
+//
+//  Settings are layered, each one overriding the last:
+//      built-in defaults < TOML file < AIRQ_* environment variables < command line
+//  The file is given with --config or AIRQ_CONFIG. Any section or key may be
+//  left out of the file; see airq.toml in the repo for every key and its default.
+//  For compatibility with the old start.sh, a bare argument is the serial device.
+
+// an hour at one reading a second
+const MAX_ROLLING_READINGS: usize = 3600;
+
+pub const USAGE: &str = "\
+usage: airq [OPTIONS] [DEVICE]
+
+  -c, --config FILE       TOML configuration file             (AIRQ_CONFIG)
+  -d, --device DEVICE     PMS5003 serial device               (AIRQ_DEVICE)
+  -b, --baud RATE         PMS5003 baud rate                   (AIRQ_BAUD)
+  -g, --gpio PIN          AM2302 GPIO (BCM) pin               (AIRQ_GPIO)
+  -a, --address ADDR      Modbus TCP listen address           (AIRQ_ADDRESS)
+  -p, --port PORT         Modbus TCP listen port              (AIRQ_PORT)
+  -m, --mode MODE         PMS5003 active or passive           (AIRQ_MODE)
+      --word-order ORDER  ABCD, CDAB, BADC or DCBA            (AIRQ_WORD_ORDER)
+      --scaled-integers   publish x10 integer temp/humidity   (AIRQ_SCALED_INTEGERS)
+      --no-modbus         don't start the Modbus servers
+      --no-display        don't drive the LCD
+      --no-log            don't log readings to stdout
+  -h, --help              show this message
+";
+
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub sensors: SensorsConfig,
+    pub pms5003: Pms5003Config,
+    pub sds011: Sds011Config,
+    pub sps30: Sps30Config,
+    pub am2302: Am2302Config,
+    pub climate: ClimateConfig,
+    pub co2: Co2Config,
+    pub gas: GasConfig,
+    pub simulated: SimulatedConfig,
+    pub modbus: ModbusConfig,
+    pub aqi: AqiConfig,
+    pub rolling: RollingConfig,
+    pub display: DisplayConfig,
+    pub outputs: OutputsConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SensorsConfig {
+    // driver names -- see sensors.rs; each driver has its own section below
+    pub enabled: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Pms5003Config {
+    pub device: String,
+    pub baud: u32,
+    // longest a read waits for bytes before giving up; 100 ms resolution
+    pub read_timeout_ms: u64,
+    pub mode: Pms5003Mode,
+    // active: every frame is decoded, but readings are published at most this often
+    //  passive: the sensor is asked for a reading this often
+    pub interval_secs: u64,
+    // how often the decoder statistics go to the log
+    pub stats_log_secs: u64,
+    // PM2.5 correction fed to the indices and LCD: none, epa, lrapa or aqandu
+    pub correction: String,
+    // None keeps the fan running all the time
+    pub duty_cycle: Option<DutyCycleConfig>,
+}
+
+// [pms5003.duty_cycle] -- see duty_cycle.rs
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DutyCycleConfig {
+    // fan on, including the stabilize time
+    pub awake_secs: u64,
+    // readings in the first part of the awake time are thrown away
+    pub stabilize_secs: u64,
+    pub sleep_secs: u64,
+}
+
+// active: the sensor streams a frame every second or so
+//  passive: the sensor only sends a frame when polled, once per interval
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Pms5003Mode {
+    #[default]
+    Active,
+    Passive,
+}
+
+impl std::str::FromStr for Pms5003Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "active" => Ok(Pms5003Mode::Active),
+            "passive" => Ok(Pms5003Mode::Passive),
+            _ => Err(format!("unknown mode '{}', expected active or passive", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Sds011Config {
+    pub device: String,
+    pub read_timeout_ms: u64,
+    // readings are published at most this often
+    pub interval_secs: u64,
+    // 0 runs the fan all the time; 1..30 has the sensor wake for 30 s every
+    //  that many minutes, on its own
+    pub work_period_mins: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Sps30Config {
+    pub interface: Sps30Interface,
+    // UART only
+    pub device: String,
+    // I2C only, /dev/i2c-N
+    pub i2c_bus: u8,
+    pub interval_secs: u64,
+    // fan cleaning, at every multiple of this since the epoch; 0 never cleans
+    pub cleaning_interval_hours: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Sps30Interface {
+    #[default]
+    I2c,
+    Uart,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Am2302Config {
+    pub gpio: u32,
+    pub interval_secs: u64,
+    // further tries, 2 s apart, when a read fails
+    pub retries: u32,
+    // temperature and humidity are reported invalid this long after the last
+    //  good reading; 0 never
+    pub stale_secs: u64,
+    // the largest change between consecutive readings taken at face value --
+    //  a bigger jump is only believed once the next reading confirms it
+    pub max_temp_step: f64,
+    pub max_humidity_step: f64,
+}
+
+// shared by the I2C temperature and humidity drivers -- only one runs at a time
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClimateConfig {
+    // /dev/i2c-N
+    pub i2c_bus: u8,
+    // None for the sensor's usual address: 0x44 for the SHT3x and SHT4x, 0x76
+    //  for the BME280
+    pub address: Option<u16>,
+    pub interval_secs: u64,
+}
+
+// shared by the CO2 drivers -- only one runs at a time
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Co2Config {
+    // s8 and mhz19
+    pub device: String,
+    // scd30 and scd4x, /dev/i2c-N
+    pub i2c_bus: u8,
+    pub interval_secs: u64,
+    // with the latest temperature and humidity reading; s8 and mhz19 only
+    pub compensate: bool,
+}
+
+// shared by the gas drivers -- only one runs at a time
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GasConfig {
+    // /dev/i2c-N
+    pub i2c_bus: u8,
+    // 1..10 -- the gas index algorithm is tuned for a reading every second
+    pub interval_secs: u64,
+    // sgp40 and sgp41: with the latest temperature and humidity reading
+    pub compensate: bool,
+    // bme680 only: 0x76 or 0x77
+    pub address: u16,
+    // bme680 only: the hot plate's temperature in degC, 200..400, and how long
+    //  it's heated before each reading
+    pub heater_temp: u16,
+    pub heater_ms: u16,
+}
+
+// the simulated-pm and simulated-climate drivers
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SimulatedConfig {
+    pub interval_secs: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModbusConfig {
+    pub address: IpAddr,
+    pub port: u16,
+    // None keeps each point's declared order (ABCD)
+    pub word_order: Option<String>,
+    pub scaled_integers: bool,
+    // None serves Modbus TCP only
+    pub rtu: Option<RtuConfig>,
+}
+
+// [modbus.rtu] -- the same registers as a slave on an RS-485 bus, alongside TCP
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RtuConfig {
+    pub device: String,
+    pub baud: u32,
+    pub parity: Parity,
+    pub stop_bits: u8,
+    // 1..247
+    pub slave_id: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Parity {
+    None,
+    // the Modbus default
+    #[default]
+    Even,
+    Odd,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AqiConfig {
+    // index shown on the LCD and published at 0x38 -- see aqi/schemes.rs
+    pub scheme: String,
+    // US EPA PM2.5 breakpoint table: epa-2024 or epa-2012
+    pub breakpoints: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RollingConfig {
+    // the first rolling window holds this many of the latest readings instead
+    //  of the last minute; 0 keeps the minute
+    pub readings: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DisplayConfig {
+    pub refresh_secs: u64,
+    // None colours the backlight by the index scheme's category. Otherwise the
+    //  upper index bound of each colour band: light blue, green, yellow, orange,
+    //  red, purple -- anything above the last one is maroon
+    pub aqi_thresholds: Option<[u16; 6]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputsConfig {
+    pub modbus: bool,
+    pub display: bool,
+    pub log: bool,
+}
+
+impl Default for SensorsConfig {
+    fn default() -> Self {
+        SensorsConfig { enabled: vec!["pms5003".to_string(), "am2302".to_string()] }
+    }
+}
+
+impl Default for Pms5003Config {
+    fn default() -> Self {
+        Pms5003Config {
+            device: "/dev/ttyS0".to_string(),
+            baud: 9600,
+            read_timeout_ms: 2000,
+            mode: Pms5003Mode::Active,
+            interval_secs: 1,
+            stats_log_secs: 300,
+            correction: "none".to_string(),
+            duty_cycle: None,
+        }
+    }
+}
+
+impl Default for DutyCycleConfig {
+    // 30 s up, 4.5 min asleep -- one reading every 5 minutes
+    fn default() -> Self {
+        DutyCycleConfig { awake_secs: 30, stabilize_secs: 30, sleep_secs: 270 }
+    }
+}
+
+impl Default for Sds011Config {
+    fn default() -> Self {
+        Sds011Config { device: "/dev/ttyUSB0".to_string(), read_timeout_ms: 2000, interval_secs: 1, work_period_mins: 0 }
+    }
+}
+
+impl Default for Sps30Config {
+    fn default() -> Self {
+        Sps30Config {
+            interface: Sps30Interface::I2c,
+            device: "/dev/ttyUSB0".to_string(),
+            i2c_bus: 1,
+            interval_secs: 1,
+            // the sensor's own default, once a week
+            cleaning_interval_hours: 168,
+        }
+    }
+}
+
+impl Default for Am2302Config {
+    fn default() -> Self {
+        Am2302Config { gpio: 4, interval_secs: 10, retries: 2, stale_secs: 60, max_temp_step: 5.0, max_humidity_step: 20.0 }
+    }
+}
+
+impl Default for ClimateConfig {
+    fn default() -> Self {
+        ClimateConfig { i2c_bus: 1, address: None, interval_secs: 10 }
+    }
+}
+
+impl Default for Co2Config {
+    fn default() -> Self {
+        // the SCD4x has a new reading every 5 s
+        Co2Config { device: "/dev/ttyAMA1".to_string(), i2c_bus: 1, interval_secs: 5, compensate: false }
+    }
+}
+
+impl Default for GasConfig {
+    fn default() -> Self {
+        // Bosch's suggested heater profile for indoor air
+        GasConfig { i2c_bus: 1, interval_secs: 1, compensate: true, address: 0x77, heater_temp: 320, heater_ms: 150 }
+    }
+}
+
+impl Default for SimulatedConfig {
+    fn default() -> Self {
+        SimulatedConfig { interval_secs: 1 }
+    }
+}
+
+impl Default for ModbusConfig {
+    fn default() -> Self {
+        ModbusConfig {
+            address: IpAddr::from([0, 0, 0, 0]),
+            port: 5502,
+            word_order: None,
+            scaled_integers: false,
+            rtu: None,
+        }
+    }
+}
+
+impl Default for RtuConfig {
+    // 19200 baud 8E1, the Modbus over serial line defaults
+    fn default() -> Self {
+        RtuConfig { device: "/dev/ttyUSB1".to_string(), baud: 19200, parity: Parity::Even, stop_bits: 1, slave_id: 1 }
+    }
+}
+
+impl Default for AqiConfig {
+    fn default() -> Self {
+        AqiConfig { scheme: "us-epa".to_string(), breakpoints: "epa-2024".to_string() }
+    }
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        DisplayConfig { refresh_secs: 30, aqi_thresholds: None }
+    }
+}
+
+impl Default for OutputsConfig {
+    fn default() -> Self {
+        OutputsConfig { modbus: true, display: true, log: true }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ConfigError {
+    // --help was given; not really an error, but it stops the program the same way
+    Help,
+    Usage(String),
+    File { path: String, reason: String },
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Help => write!(f, "{}", USAGE),
+            ConfigError::Usage(msg) => write!(f, "{}\n\n{}", msg, USAGE),
+            ConfigError::File { path, reason } => write!(f, "config file {}: {}", path, reason),
+            ConfigError::Invalid(msg) => write!(f, "invalid configuration: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    pub fn from_toml(text: &str) -> Result<Config, String> {
+        toml::from_str(text).map_err(|e| e.message().to_string())
+    }
+
+    pub fn socket_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.modbus.address, self.modbus.port)
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        Encoding {
+            // validate() has already rejected bad names
+            word_order: self.modbus.word_order.as_ref().and_then(|s| s.parse::<WordOrder>().ok()),
+            scaled_integers: self.modbus.scaled_integers,
+        }
+    }
+
+    pub fn breakpoints(&self) -> Breakpoints {
+        // validate() has already rejected bad names
+        self.aqi.breakpoints.parse().unwrap_or_default()
+    }
+
+    pub fn correction(&self) -> Correction {
+        // validate() has already rejected bad names
+        self.pms5003.correction.parse().unwrap_or_default()
+    }
+
+    pub fn index_scheme(&self) -> Box<dyn IndexScheme> {
+        // validate() has already rejected bad names
+        schemes::from_name(&self.aqi.scheme, self.breakpoints())
+            .unwrap_or_else(|_| Box::new(schemes::UsEpa { breakpoints: self.breakpoints() }))
+    }
+
+    // STANDARD_WINDOWS, with the first one counted in readings if configured
+    pub fn rolling_windows(&self) -> [Window; 4] {
+        let mut windows = STANDARD_WINDOWS;
+        if self.rolling.readings > 0 {
+            windows[0] = Window::Count(self.rolling.readings);
+        }
+        windows
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |msg: String| Err(ConfigError::Invalid(msg));
+
+        for (i, name) in self.sensors.enabled.iter().enumerate() {
+            let Some(kind) = sensors::kind_of(name) else {
+                return invalid(format!("sensors.enabled: unknown sensor '{}', expected one of {}",
+                    name, sensors::driver_names().join(", ")));
+            };
+            if let Some(other) = self.sensors.enabled[..i].iter().find(|n| sensors::kind_of(n) == Some(kind)) {
+                return invalid(format!("sensors.enabled: {} and {} measure the same thing, pick one", other, name));
+            }
+        }
+
+        if self.pms5003.device.is_empty() {
+            return invalid("pms5003.device is empty".to_string());
+        }
+        if !serial::is_supported_baud(self.pms5003.baud) {
+            return invalid(format!("pms5003.baud {} is not a standard rate", self.pms5003.baud));
+        }
+        if !(100..=25_500).contains(&self.pms5003.read_timeout_ms) {
+            return invalid("pms5003.read_timeout_ms must be between 100 and 25500".to_string());
+        }
+        if self.sds011.device.is_empty() {
+            return invalid("sds011.device is empty".to_string());
+        }
+        if !(100..=25_500).contains(&self.sds011.read_timeout_ms) {
+            return invalid("sds011.read_timeout_ms must be between 100 and 25500".to_string());
+        }
+        if self.sds011.work_period_mins > 30 {
+            return invalid("sds011.work_period_mins must be between 0 and 30".to_string());
+        }
+        if self.sps30.interface == Sps30Interface::Uart && self.sps30.device.is_empty() {
+            return invalid("sps30.device is empty".to_string());
+        }
+        // BCM numbering -- the 40-pin header exposes GPIO 0..27
+        if self.am2302.gpio > 27 {
+            return invalid(format!("am2302.gpio {} is not a header GPIO (0..27)", self.am2302.gpio));
+        }
+        if self.am2302.retries > 4 {
+            return invalid("am2302.retries must be between 0 and 4".to_string());
+        }
+        if self.am2302.stale_secs != 0 && self.am2302.stale_secs < self.am2302.interval_secs {
+            return invalid("am2302.stale_secs can't be shorter than interval_secs".to_string());
+        }
+        if !(self.am2302.max_temp_step > 0.0 && self.am2302.max_humidity_step > 0.0) {
+            return invalid("am2302.max_temp_step and max_humidity_step must be greater than 0".to_string());
+        }
+        if let Some(address) = self.climate.address {
+            for name in &self.sensors.enabled {
+                let addresses: &[u16] = match name.as_str() {
+                    "sht3x" => &sht3x::I2C_ADDRESSES,
+                    "sht4x" => &sht4x::I2C_ADDRESSES,
+                    "bme280" => &bme280::I2C_ADDRESSES,
+                    _ => continue,
+                };
+                if !addresses.contains(&address) {
+                    return invalid(format!("climate.address 0x{:02X} is not a {} address", address, name));
+                }
+            }
+        }
+        if self.co2.device.is_empty() {
+            return invalid("co2.device is empty".to_string());
+        }
+        if !(1..=10).contains(&self.gas.interval_secs) {
+            return invalid("gas.interval_secs must be between 1 and 10".to_string());
+        }
+        if !bme680::I2C_ADDRESSES.contains(&self.gas.address) {
+            return invalid(format!("gas.address 0x{:02X} is not a BME680 address (0x76 or 0x77)", self.gas.address));
+        }
+        if !(200..=400).contains(&self.gas.heater_temp) {
+            return invalid("gas.heater_temp must be between 200 and 400".to_string());
+        }
+        if !(1..=4032).contains(&self.gas.heater_ms) {
+            return invalid("gas.heater_ms must be between 1 and 4032".to_string());
+        }
+        if self.modbus.port == 0 {
+            return invalid("modbus.port must be greater than 0".to_string());
+        }
+        if let Some(order) = &self.modbus.word_order {
+            if let Err(e) = order.parse::<WordOrder>() {
+                return invalid(format!("modbus.word_order: {}", e));
+            }
+        }
+        if let Some(rtu) = &self.modbus.rtu {
+            if rtu.device.is_empty() {
+                return invalid("modbus.rtu.device is empty".to_string());
+            }
+            if !serial::is_supported_baud(rtu.baud) {
+                return invalid(format!("modbus.rtu.baud {} is not a standard rate", rtu.baud));
+            }
+            if !(1..=2).contains(&rtu.stop_bits) {
+                return invalid("modbus.rtu.stop_bits must be 1 or 2".to_string());
+            }
+            // 0 is broadcast, 248..255 are reserved
+            if !(1..=247).contains(&rtu.slave_id) {
+                return invalid("modbus.rtu.slave_id must be between 1 and 247".to_string());
+            }
+        }
+        if let Err(e) = self.pms5003.correction.parse::<Correction>() {
+            return invalid(format!("pms5003.correction: {}", e));
+        }
+        if let Err(e) = self.aqi.breakpoints.parse::<Breakpoints>() {
+            return invalid(format!("aqi.breakpoints: {}", e));
+        }
+        if let Err(e) = schemes::from_name(&self.aqi.scheme, Breakpoints::default()) {
+            return invalid(format!("aqi.scheme: {}", e));
+        }
+        for (name, secs) in [
+            ("pms5003.interval_secs", self.pms5003.interval_secs),
+            ("pms5003.stats_log_secs", self.pms5003.stats_log_secs),
+            ("sds011.interval_secs", self.sds011.interval_secs),
+            ("sps30.interval_secs", self.sps30.interval_secs),
+            ("am2302.interval_secs", self.am2302.interval_secs),
+            ("climate.interval_secs", self.climate.interval_secs),
+            ("co2.interval_secs", self.co2.interval_secs),
+            ("simulated.interval_secs", self.simulated.interval_secs),
+            ("display.refresh_secs", self.display.refresh_secs),
+        ] {
+            if secs == 0 {
+                return invalid(format!("{} must be greater than 0", name));
+            }
+        }
+        if let Some(c) = &self.pms5003.duty_cycle {
+            if c.sleep_secs == 0 {
+                return invalid("pms5003.duty_cycle.sleep_secs must be greater than 0".to_string());
+            }
+            if c.stabilize_secs > c.awake_secs {
+                return invalid("pms5003.duty_cycle.stabilize_secs can't be longer than awake_secs".to_string());
+            }
+        }
+        // every reading in a count window is kept, for each of four measurements
+        if self.rolling.readings > MAX_ROLLING_READINGS {
+            return invalid(format!("rolling.readings can't be more than {}", MAX_ROLLING_READINGS));
+        }
+        if self.display.aqi_thresholds.is_some_and(|t| t.windows(2).any(|w| w[0] >= w[1])) {
+            return invalid("display.aqi_thresholds must be strictly increasing".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+pub fn parse_config(args: &[String]) -> Result<Config, ConfigError> {
+    load(args, |key| env::var(key).ok())
+}
+
+// same as parse_config, but with the environment passed in
+pub fn load<E>(args: &[String], env: E) -> Result<Config, ConfigError>
+    where E: Fn(&str) -> Option<String>
+{
+    let cli = parse_args(args)?;
+
+    let mut config = match cli.config_file.clone().or_else(|| env("AIRQ_CONFIG")) {
+        Some(path) => {
+            let text = fs::read_to_string(&path)
+                .map_err(|e| ConfigError::File { path: path.clone(), reason: e.to_string() })?;
+            Config::from_toml(&text)
+                .map_err(|reason| ConfigError::File { path, reason })?
+        },
+        None => Config::default(),
+    };
+
+    // environment, then command line -- both are the same list of overrides
+    let from_env: Vec<(&str, String)> = OVERRIDES.iter()
+        .filter_map(|(key, var)| env(var).map(|v| (*key, v)))
+        .collect();
+    for (key, value) in from_env.iter().chain(cli.overrides.iter()) {
+        apply_override(&mut config, key, value)?;
+    }
+
+    config.validate()?;
+
+    Ok(config)
+}
+
+// (override key, environment variable)
+const OVERRIDES: [(&str, &str); 8] = [
+    ("device", "AIRQ_DEVICE"),
+    ("baud", "AIRQ_BAUD"),
+    ("mode", "AIRQ_MODE"),
+    ("gpio", "AIRQ_GPIO"),
+    ("address", "AIRQ_ADDRESS"),
+    ("port", "AIRQ_PORT"),
+    ("word-order", "AIRQ_WORD_ORDER"),
+    ("scaled-integers", "AIRQ_SCALED_INTEGERS"),
+];
+
+#[derive(Debug, Default)]
+struct CommandLine {
+    config_file: Option<String>,
+    overrides: Vec<(&'static str, String)>,
+}
+
+fn parse_args(args: &[String]) -> Result<CommandLine, ConfigError> {
+    let mut cli = CommandLine::default();
+    let mut device_given = false;
+
+    // skip the command name
+    let mut it = args.iter().skip(1);
+    while let Some(arg) = it.next() {
+        let mut value_for = |flag: &str| it.next().cloned()
+            .ok_or_else(|| ConfigError::Usage(format!("{} needs a value", flag)));
+
+        match arg.as_str() {
+            "-h" | "--help" => return Err(ConfigError::Help),
+            "-c" | "--config" => cli.config_file = Some(value_for(arg)?),
+            "-d" | "--device" => cli.overrides.push(("device", value_for(arg)?)),
+            "-b" | "--baud" => cli.overrides.push(("baud", value_for(arg)?)),
+            "-m" | "--mode" => cli.overrides.push(("mode", value_for(arg)?)),
+            "-g" | "--gpio" => cli.overrides.push(("gpio", value_for(arg)?)),
+            "-a" | "--address" => cli.overrides.push(("address", value_for(arg)?)),
+            "-p" | "--port" => cli.overrides.push(("port", value_for(arg)?)),
+            "--word-order" => cli.overrides.push(("word-order", value_for(arg)?)),
+            "--scaled-integers" => cli.overrides.push(("scaled-integers", "true".to_string())),
+            "--no-modbus" => cli.overrides.push(("no-modbus", "true".to_string())),
+            "--no-display" => cli.overrides.push(("no-display", "true".to_string())),
+            "--no-log" => cli.overrides.push(("no-log", "true".to_string())),
+            a if a.starts_with('-') => return Err(ConfigError::Usage(format!("unknown option {}", a))),
+            // the first bare argument is the device, like the old `airq /dev/ttyS0`
+            a if !device_given => {
+                device_given = true;
+                cli.overrides.push(("device", a.to_string()));
+            },
+            a => return Err(ConfigError::Usage(format!("unexpected argument {}", a))),
+        }
+    }
+
+    Ok(cli)
+}
+
+fn apply_override(config: &mut Config, key: &str, value: &str) -> Result<(), ConfigError> {
+    fn number<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, ConfigError> {
+        value.parse::<T>()
+            .map_err(|_| ConfigError::Invalid(format!("{} '{}' is not a valid number", key, value)))
+    }
+    fn flag(key: &str, value: &str) -> Result<bool, ConfigError> {
+        match value {
+            "1" | "true" | "yes" | "on" => Ok(true),
+            "0" | "false" | "no" | "off" => Ok(false),
+            _ => Err(ConfigError::Invalid(format!("{} '{}' is not true or false", key, value))),
+        }
+    }
+
+    match key {
+        "device" => config.pms5003.device = value.to_string(),
+        "baud" => config.pms5003.baud = number(key, value)?,
+        "mode" => config.pms5003.mode = value.parse().map_err(ConfigError::Invalid)?,
+        "gpio" => config.am2302.gpio = number(key, value)?,
+        "address" => config.modbus.address = value.parse()
+            .map_err(|_| ConfigError::Invalid(format!("address '{}' is not an IP address", value)))?,
+        "port" => config.modbus.port = number(key, value)?,
+        "word-order" => config.modbus.word_order = Some(value.to_string()),
+        "scaled-integers" => config.modbus.scaled_integers = flag(key, value)?,
+        "no-modbus" => config.outputs.modbus = !flag(key, value)?,
+        "no-display" => config.outputs.display = !flag(key, value)?,
+        "no-log" => config.outputs.log = !flag(key, value)?,
+        _ => unreachable!("unknown override {}", key),
+    }
+
+    Ok(())
+}
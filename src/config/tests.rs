@@ -2,37 +2,311 @@ use super::*;
 
 
 mod config_tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn args(a: &[&str]) -> Vec<String> {
+        std::iter::once("exec-name").chain(a.iter().copied()).map(String::from).collect()
+    }
+
+    fn no_env(_key: &str) -> Option<String> {
+        None
+    }
+
+    // writes a throwaway config file and returns its path
+    fn config_file(name: &str, text: &str) -> String {
+        let path = std::env::temp_dir().join(format!("airq-{}-{}.toml", name, std::process::id()));
+        std::fs::write(&path, text).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
     #[test]
     fn returns_first_arg() {
         // the 'first' arg is index 1 -- AFTER the command name
-        let args = [
-            String::from("exec-name"),
-            String::from("first-arg")
-         ];
-        let result = super::parse_config(&args);
+        // and is the serial device, as with `airq /dev/ttyS0`
+        let result = load(&args(&["first-arg"]), no_env).unwrap();
+
+        assert_eq!(result.pms5003.device, "first-arg");
+    }
+
+    #[test]
+    fn rejects_extra_positional_args() {
+        let result = load(&args(&["first-arg", "extra-arg"]), no_env);
+
+        assert!(matches!(result, Err(ConfigError::Usage(_))));
+    }
 
-        assert!(args[1] == result);
+    #[test]
+    fn defaults_with_no_args() {
+        let result = load(&args(&[]), no_env).unwrap();
+
+        assert_eq!(result, Config::default());
+        assert_eq!(result.pms5003.device, "/dev/ttyS0");
+        assert_eq!(result.am2302.gpio, 4);
+        assert_eq!(result.socket_addr().to_string(), "0.0.0.0:5502");
     }
 
     #[test]
-    fn returns_only_first_arg() {
-        let args = [
-            String::from("exec-name"),
-            String::from("first-arg"),
-            String::from("extra-arg")
-         ];    
-         
-         let result = super::parse_config(&args);
- 
-         assert!(args[1] == result);
+    fn reads_partial_toml() {
+        let c = Config::from_toml(r#"
+            [pms5003]
+            device = "/dev/ttyAMA0"
+
+            [modbus]
+            port = 502
+            word_order = "CDAB"
+
+            [outputs]
+            display = false
+        "#).unwrap();
+
+        assert_eq!(c.pms5003.device, "/dev/ttyAMA0");
+        assert_eq!(c.pms5003.baud, 9600);
+        assert_eq!(c.modbus.port, 502);
+        assert_eq!(c.encoding().word_order, Some(crate::registers::WordOrder::Cdab));
+        assert!(!c.outputs.display);
+        assert!(c.outputs.modbus);
+    }
+
+    #[test]
+    fn example_file_is_the_defaults() {
+        let c = Config::from_toml(include_str!("../../airq.toml")).unwrap();
+
+        assert_eq!(c, Config::default());
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        assert!(Config::from_toml("[pms5003]\nbaudrate = 9600\n").is_err());
+    }
+
+    #[test]
+    fn env_overrides_file_and_cli_overrides_env() {
+        let path = config_file("layers", "[modbus]\nport = 1502\n[am2302]\ngpio = 17\n");
+        let env: HashMap<&str, String> = HashMap::from([
+            ("AIRQ_CONFIG", path.clone()),
+            ("AIRQ_PORT", "2502".to_string()),
+            ("AIRQ_DEVICE", "/dev/ttyUSB0".to_string()),
+        ]);
+
+        let c = load(&args(&["--port", "3502"]), |k| env.get(k).cloned()).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(c.am2302.gpio, 17);
+        assert_eq!(c.pms5003.device, "/dev/ttyUSB0");
+        assert_eq!(c.modbus.port, 3502);
+    }
+
+    #[test]
+    fn reports_missing_file() {
+        let result = load(&args(&["--config", "/nonexistent/airq.toml"]), no_env);
+
+        assert!(matches!(result, Err(ConfigError::File { .. })));
+    }
+
+    #[test]
+    fn validation_errors_instead_of_panics() {
+        for bad in [
+            &["--gpio", "40"][..],
+            &["--port", "0"],
+            &["--port", "many"],
+            &["--word-order", "ABDC"],
+            &["--address", "localhost"],
+            &["--baud"],
+            &["--bogus"],
+        ] {
+            let result = load(&args(bad), no_env);
+            assert!(result.is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn thresholds_must_increase() {
+        let mut c = Config::default();
+        c.display.aqi_thresholds = Some([15, 50, 50, 150, 200, 300]);
+
+        assert!(matches!(c.validate(), Err(ConfigError::Invalid(_))));
+    }
+
+    #[test]
+    fn mode_from_file_env_or_cli() {
+        let c = Config::from_toml("[pms5003]\nmode = \"passive\"\n").unwrap();
+        assert_eq!(c.pms5003.mode, Pms5003Mode::Passive);
+        assert!(Config::from_toml("[pms5003]\nmode = \"sleepy\"\n").is_err());
+
+        let env = |k: &str| (k == "AIRQ_MODE").then(|| "Passive".to_string());
+        assert_eq!(load(&args(&[]), env).unwrap().pms5003.mode, Pms5003Mode::Passive);
+        assert_eq!(load(&args(&["-m", "active"]), env).unwrap().pms5003.mode, Pms5003Mode::Active);
+        assert!(load(&args(&["--mode", "sleepy"]), no_env).is_err());
+    }
+
+    #[test]
+    fn duty_cycle_is_optional_and_checked() {
+        assert_eq!(Config::default().pms5003.duty_cycle, None);
+
+        let c = Config::from_toml("[pms5003.duty_cycle]\nsleep_secs = 600\n").unwrap();
+        let d = c.pms5003.duty_cycle.as_ref().unwrap();
+        assert_eq!((d.awake_secs, d.stabilize_secs, d.sleep_secs), (30, 30, 600));
+        assert!(c.validate().is_ok());
+
+        let c = Config::from_toml("[pms5003.duty_cycle]\nawake_secs = 10\n").unwrap();
+        assert!(matches!(c.validate(), Err(ConfigError::Invalid(_))));
+    }
+
+    #[test]
+    fn rtu_is_optional_and_checked() {
+        assert_eq!(Config::default().modbus.rtu, None);
+
+        let c = Config::from_toml("[modbus.rtu]\ndevice = \"/dev/ttyAMA2\"\nparity = \"none\"\nstop_bits = 2\n").unwrap();
+        let rtu = c.modbus.rtu.as_ref().unwrap();
+        assert_eq!((rtu.baud, rtu.parity, rtu.stop_bits, rtu.slave_id), (19200, Parity::None, 2, 1));
+        assert!(c.validate().is_ok());
+
+        assert!(Config::from_toml("[modbus.rtu]\nparity = \"mark\"\n").is_err());
+        for toml in [
+            "[modbus.rtu]\nslave_id = 0\n",
+            "[modbus.rtu]\nslave_id = 248\n",
+            "[modbus.rtu]\nstop_bits = 3\n",
+            "[modbus.rtu]\nbaud = 12345\n",
+        ] {
+            let c = Config::from_toml(toml).unwrap();
+            assert!(matches!(c.validate(), Err(ConfigError::Invalid(_))), "{}", toml);
+        }
+    }
+
+    #[test]
+    fn breakpoints_are_checked() {
+        let c = Config::from_toml("[aqi]\nbreakpoints = \"epa-2012\"\n").unwrap();
+        assert_eq!(c.breakpoints(), crate::aqi::Breakpoints::Epa2012);
+
+        let c = Config::from_toml("[aqi]\nbreakpoints = \"epa-1999\"\n").unwrap();
+        assert!(matches!(c.validate(), Err(ConfigError::Invalid(_))));
+    }
+
+    #[test]
+    fn scheme_is_checked() {
+        let c = Config::from_toml("[aqi]\nscheme = \"uk-daqi\"\n").unwrap();
+        assert_eq!(c.index_scheme().label(), "DAQI");
+
+        let c = Config::from_toml("[aqi]\nscheme = \"who\"\n").unwrap();
+        assert!(matches!(c.validate(), Err(ConfigError::Invalid(_))));
+    }
+
+    #[test]
+    fn correction_is_checked() {
+        let c = Config::from_toml("[pms5003]\ncorrection = \"epa\"\n").unwrap();
+        assert_eq!(c.correction(), crate::correction::Correction::Epa);
+
+        let c = Config::from_toml("[pms5003]\ncorrection = \"purpleair\"\n").unwrap();
+        assert!(matches!(c.validate(), Err(ConfigError::Invalid(_))));
+    }
+
+    #[test]
+    fn sensors_are_known_drivers_listed_once() {
+        let c = Config::from_toml("[sensors]\nenabled = [\"pms5003\"]\n").unwrap();
+        assert!(c.validate().is_ok());
+
+        let c = Config::from_toml("[sensors]\nenabled = [\"bme999\"]\n").unwrap();
+        assert!(matches!(c.validate(), Err(ConfigError::Invalid(_))));
+
+        let c = Config::from_toml("[sensors]\nenabled = [\"am2302\", \"am2302\"]\n").unwrap();
+        assert!(matches!(c.validate(), Err(ConfigError::Invalid(_))));
+    }
+
+    #[test]
+    fn one_particulate_sensor_at_a_time() {
+        let c = Config::from_toml("[sensors]\nenabled = [\"sds011\", \"am2302\"]\n").unwrap();
+        assert!(c.validate().is_ok());
+
+        let c = Config::from_toml("[sensors]\nenabled = [\"pms5003\", \"sds011\"]\n").unwrap();
+        assert!(matches!(c.validate(), Err(ConfigError::Invalid(_))));
+
+        let c = Config::from_toml("[sds011]\nwork_period_mins = 31\n").unwrap();
+        assert!(matches!(c.validate(), Err(ConfigError::Invalid(_))));
+    }
+
+    #[test]
+    fn one_co2_sensor_alongside_the_others() {
+        let c = Config::from_toml("[sensors]\nenabled = [\"pms5003\", \"am2302\", \"scd4x\"]\n").unwrap();
+        assert!(c.validate().is_ok());
+
+        let c = Config::from_toml("[sensors]\nenabled = [\"s8\", \"mhz19\"]\n").unwrap();
+        assert!(matches!(c.validate(), Err(ConfigError::Invalid(_))));
+
+        let c = Config::from_toml("[co2]\ninterval_secs = 0\n").unwrap();
+        assert!(matches!(c.validate(), Err(ConfigError::Invalid(_))));
+    }
+
+    #[test]
+    fn gas_section_is_checked() {
+        let c = Config::from_toml("[sensors]\nenabled = [\"pms5003\", \"am2302\", \"sgp41\"]\n").unwrap();
+        assert!(c.validate().is_ok());
+        let c = Config::from_toml("[sensors]\nenabled = [\"sgp40\", \"bme680\"]\n").unwrap();
+        assert!(matches!(c.validate(), Err(ConfigError::Invalid(_))));
+
+        let c = Config::from_toml("[gas]\naddress = 0x76\n").unwrap();
+        assert!(c.validate().is_ok());
+        for bad in ["address = 0x59", "interval_secs = 11", "heater_temp = 450", "heater_ms = 0"] {
+            let c = Config::from_toml(&format!("[gas]\n{}\n", bad)).unwrap();
+            assert!(matches!(c.validate(), Err(ConfigError::Invalid(_))), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn climate_address_matches_the_sensor() {
+        let c = Config::from_toml("[sensors]\nenabled = [\"pms5003\", \"bme280\"]\n[climate]\naddress = 0x77\n").unwrap();
+        assert!(c.validate().is_ok());
+        let c = Config::from_toml("[sensors]\nenabled = [\"pms5003\", \"sht3x\"]\n[climate]\naddress = 0x77\n").unwrap();
+        assert!(matches!(c.validate(), Err(ConfigError::Invalid(_))));
+
+        let c = Config::from_toml("[sensors]\nenabled = [\"am2302\", \"sht4x\"]\n").unwrap();
+        assert!(matches!(c.validate(), Err(ConfigError::Invalid(_))));
+    }
+
+    #[test]
+    fn am2302_section_is_checked() {
+        let c = Config::from_toml("[am2302]\nstale_secs = 0\nretries = 4\n").unwrap();
+        assert!(c.validate().is_ok());
+        for toml in [
+            "[am2302]\nretries = 5\n",
+            "[am2302]\nstale_secs = 5\n",
+            "[am2302]\nmax_temp_step = 0.0\n",
+            "[am2302]\nmax_humidity_step = -1.0\n",
+        ] {
+            let c = Config::from_toml(toml).unwrap();
+            assert!(matches!(c.validate(), Err(ConfigError::Invalid(_))), "{}", toml);
+        }
+    }
+
+    #[test]
+    fn rolling_section_is_checked() {
+        assert_eq!(Config::default().rolling_windows(), STANDARD_WINDOWS);
+        let c = Config::from_toml("[rolling]\nreadings = 100\n").unwrap();
+        assert!(c.validate().is_ok());
+        assert_eq!(c.rolling_windows()[0], Window::Count(100));
+        assert_eq!(c.rolling_windows()[1..], STANDARD_WINDOWS[1..]);
+
+        let c = Config::from_toml("[rolling]\nreadings = 3601\n").unwrap();
+        assert!(matches!(c.validate(), Err(ConfigError::Invalid(_))));
+    }
+
+    #[test]
+    fn sps30_interface() {
+        assert_eq!(Config::default().sps30.interface, Sps30Interface::I2c);
+        let c = Config::from_toml("[sps30]\ninterface = \"uart\"\ndevice = \"/dev/ttyAMA1\"\n").unwrap();
+        assert_eq!(c.sps30.interface, Sps30Interface::Uart);
+        assert!(c.validate().is_ok());
+        assert!(Config::from_toml("[sps30]\ninterface = \"spi\"\n").is_err());
+
+        // the device only matters over UART
+        let c = Config::from_toml("[sps30]\ndevice = \"\"\n").unwrap();
+        assert!(c.validate().is_ok());
+        let c = Config::from_toml("[sps30]\ninterface = \"uart\"\ndevice = \"\"\n").unwrap();
+        assert!(matches!(c.validate(), Err(ConfigError::Invalid(_))));
     }
 
     #[test]
-    fn panics_with_no_args() {
-        let args = [
-            String::from("exec-name")
-        ];
-        let result = std::panic::catch_unwind(|| super::parse_config(&args));
-        assert!(result.is_err());
+    fn help_is_reported() {
+        assert_eq!(load(&args(&["-h"]), no_env), Err(ConfigError::Help));
     }
 }
@@ -0,0 +1,71 @@
+#[cfg(test)]
+mod correction_tests {
+    use crate::correction::*;
+    use crate::payload::Pms5003Reading;
+
+    fn reading(cf1: u16, atm: u16) -> Pms5003Reading {
+        Pms5003Reading { pm2_5_cf1: cf1, pm2_5_atm: atm, ..Pms5003Reading::default() }
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 0.01
+    }
+
+    #[test]
+    fn none_is_the_raw_cf1_value() {
+        assert_eq!(Correction::None.apply(&reading(20, 15), Some(50.0)), 20.0);
+    }
+
+    #[test]
+    fn epa_low_range() {
+        // 0.524 * 20 - 0.0862 * 50 + 5.75
+        assert!(close(Correction::Epa.apply(&reading(20, 15), Some(50.0)), 11.92));
+    }
+
+    #[test]
+    fn epa_reads_lower_in_humid_air() {
+        let dry = Correction::Epa.apply(&reading(40, 30), Some(20.0));
+        let humid = Correction::Epa.apply(&reading(40, 30), Some(90.0));
+
+        assert!(humid < dry);
+    }
+
+    #[test]
+    fn epa_has_no_steps_at_the_band_edges() {
+        for edge in [30.0, 50.0, 210.0, 260.0] {
+            let a = crate::correction::epa(edge - 1e-6, 50.0);
+            let b = crate::correction::epa(edge, 50.0);
+            assert!(close(a, b), "{}: {} vs {}", edge, a, b);
+        }
+    }
+
+    #[test]
+    fn epa_smoke_range() {
+        // 0.69 * 300 + 8.84e-4 * 300^2 + 2.966
+        assert!(close(Correction::Epa.apply(&reading(300, 200), Some(50.0)), 289.53));
+    }
+
+    #[test]
+    fn epa_without_humidity_is_uncorrected() {
+        assert_eq!(Correction::Epa.apply(&reading(20, 15), None), 20.0);
+    }
+
+    #[test]
+    fn lrapa_and_aqandu_use_atmospheric() {
+        assert!(close(Correction::Lrapa.apply(&reading(30, 20), None), 9.34));
+        assert!(close(Correction::AqAndU.apply(&reading(30, 20), None), 18.21));
+    }
+
+    #[test]
+    fn never_negative() {
+        assert_eq!(Correction::Lrapa.apply(&reading(0, 0), None), 0.0);
+        assert_eq!(Correction::Epa.apply(&reading(0, 0), Some(100.0)), 0.0);
+    }
+
+    #[test]
+    fn parses_names() {
+        assert_eq!("EPA".parse::<Correction>().unwrap(), Correction::Epa);
+        assert_eq!("aqandu".parse::<Correction>().unwrap().to_string(), "aqandu");
+        assert!("purpleair".parse::<Correction>().is_err());
+    }
+}
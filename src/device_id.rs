@@ -0,0 +1,80 @@
+#[cfg(test)]
+mod tests;
+
+use tokio_modbus::Exception;
+
+// Read Device Identification -- function 0x2B, MEI type 0x0E
+//
+//  The objects come from the build: the product code and revision are the
+//  crate's name and version, the vendor and serial number whatever
+//  AIRQ_VENDOR and AIRQ_SERIAL were set to when it was compiled. Basic
+//  objects 0x00..0x02 and the serial number as private object 0x80, so
+//  extended conformity, with both stream and individual access.
+
+pub const FUNCTION: u8 = 0x2B;
+const MEI_TYPE: u8 = 0x0E;
+const CONFORMITY: u8 = 0x83;
+
+// the response's own fields leave the rest of a 253-byte PDU for objects
+const MAX_OBJECTS_LEN: usize = 253 - 7;
+
+pub type Objects = Vec<(u8, String)>;
+
+pub fn objects() -> Objects {
+    vec![
+        (0x00, option_env!("AIRQ_VENDOR").unwrap_or("airq").to_string()),
+        (0x01, env!("CARGO_PKG_NAME").to_string()),
+        (0x02, env!("CARGO_PKG_VERSION").to_string()),
+        (0x80, option_env!("AIRQ_SERIAL").unwrap_or("unknown").to_string()),
+    ]
+}
+
+// the response data after the function code, for the request data after it
+pub fn respond(objects: &Objects, request: &[u8]) -> Result<Vec<u8>, Exception> {
+    let [mei, code, object_id] = *request else {
+        return Err(Exception::IllegalDataValue);
+    };
+    if mei != MEI_TYPE {
+        return Err(Exception::IllegalFunction);
+    }
+
+    let selected: Vec<&(u8, String)> = match code {
+        // basic, regular and extended stream access, from object_id on -- or
+        //  from the start if there's no such object in the category
+        1..=3 => {
+            let last = [0x02, 0x7F, 0xFF][code as usize - 1];
+            let category: Vec<_> = objects.iter().filter(|(id, _)| *id <= last).collect();
+            let from = category.iter().position(|(id, _)| *id == object_id).unwrap_or(0);
+            category[from..].to_vec()
+        },
+        // one object
+        4 => match objects.iter().find(|(id, _)| *id == object_id) {
+            Some(o) => vec![o],
+            None => return Err(Exception::IllegalDataAddress),
+        },
+        _ => return Err(Exception::IllegalDataValue),
+    };
+
+    let mut body = Vec::new();
+    let mut count = 0;
+    let mut next = None;
+    for (id, value) in selected {
+        // cut short if it couldn't go in a response on its own
+        let value = &value.as_bytes()[..value.len().min(MAX_OBJECTS_LEN - 2)];
+        if body.len() + 2 + value.len() > MAX_OBJECTS_LEN {
+            next = Some(*id);
+            break;
+        }
+        body.extend([*id, value.len() as u8]);
+        body.extend_from_slice(value);
+        count += 1;
+    }
+
+    let (more, next_id) = match next {
+        Some(id) => (0xFF, id),
+        None => (0x00, 0x00),
+    };
+    let mut response = vec![MEI_TYPE, code, CONFORMITY, more, next_id, count];
+    response.extend(body);
+    Ok(response)
+}
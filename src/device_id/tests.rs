@@ -0,0 +1,71 @@
+#[cfg(test)]
+mod device_id_tests {
+    use tokio_modbus::Exception;
+
+    use crate::device_id::*;
+
+    fn sample() -> Objects {
+        vec![
+            (0x00, "acme".to_string()),
+            (0x01, "airq".to_string()),
+            (0x02, "1.2".to_string()),
+            (0x80, "42".to_string()),
+        ]
+    }
+
+    #[test]
+    fn build_metadata() {
+        let objects = objects();
+        assert_eq!(objects.iter().map(|(id, _)| *id).collect::<Vec<_>>(), vec![0x00, 0x01, 0x02, 0x80]);
+        assert_eq!(objects[1].1, "airq");
+        assert_eq!(objects[2].1, env!("CARGO_PKG_VERSION"));
+    }
+
+    #[test]
+    fn basic_stream() {
+        assert_eq!(respond(&sample(), &[0x0E, 0x01, 0x00]).unwrap(), [
+            &[0x0E, 0x01, 0x83, 0x00, 0x00, 0x03][..],
+            &[0x00, 4], b"acme",
+            &[0x01, 4], b"airq",
+            &[0x02, 3], b"1.2",
+        ].concat());
+    }
+
+    #[test]
+    fn extended_stream_from_an_object() {
+        assert_eq!(respond(&sample(), &[0x0E, 0x03, 0x02]).unwrap(), [
+            &[0x0E, 0x03, 0x83, 0x00, 0x00, 0x02][..],
+            &[0x02, 3], b"1.2",
+            &[0x80, 2], b"42",
+        ].concat());
+        // no object 0x05 -- from the start
+        assert_eq!(respond(&sample(), &[0x0E, 0x03, 0x05]).unwrap()[5], 4);
+    }
+
+    #[test]
+    fn individual_access() {
+        assert_eq!(respond(&sample(), &[0x0E, 0x04, 0x80]).unwrap(), [
+            &[0x0E, 0x04, 0x83, 0x00, 0x00, 0x01][..],
+            &[0x80, 2], b"42",
+        ].concat());
+        assert_eq!(respond(&sample(), &[0x0E, 0x04, 0x03]), Err(Exception::IllegalDataAddress));
+    }
+
+    #[test]
+    fn bad_requests() {
+        assert_eq!(respond(&sample(), &[0x0D, 0x01, 0x00]), Err(Exception::IllegalFunction));
+        assert_eq!(respond(&sample(), &[0x0E, 0x05, 0x00]), Err(Exception::IllegalDataValue));
+        assert_eq!(respond(&sample(), &[0x0E, 0x01]), Err(Exception::IllegalDataValue));
+    }
+
+    #[test]
+    fn long_objects_follow() {
+        let objects = vec![(0x00, "v".repeat(200)), (0x01, "p".repeat(200)), (0x02, "1".to_string())];
+        let first = respond(&objects, &[0x0E, 0x01, 0x00]).unwrap();
+        assert_eq!(first[..6], [0x0E, 0x01, 0x83, 0xFF, 0x01, 0x01]);
+        assert!(first.len() < 253);
+
+        let rest = respond(&objects, &[0x0E, 0x01, 0x01]).unwrap();
+        assert_eq!(rest[..6], [0x0E, 0x01, 0x83, 0x00, 0x00, 0x02]);
+    }
+}
@@ -0,0 +1,71 @@
+#[cfg(test)]
+mod tests;
+
+use std::time::Duration;
+
+use crate::config::DutyCycleConfig;
+
+// Fan duty cycle for the PMS5003
+//
+//  The laser and fan have a rated life of a few years running flat out; air
+//  quality indoors doesn't need a reading every second. Each cycle the sensor
+//  is woken, left alone while the fan settles, read, then put back to sleep:
+//
+//      |-- stabilize --|-- sampling --|------------ sleep ------------|
+//      |------------ awake -----------|
+//
+//  Readings taken while the fan is still settling are thrown away. If awake
+//  is no longer than stabilize there is no sampling window as such -- the
+//  sensor stays up just long enough for one good reading.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Stabilizing,
+    Sampling,
+    Sleeping,
+}
+
+impl Phase {
+    // as published in the PMS_STATE register
+    pub fn code(self) -> u16 {
+        match self {
+            Phase::Sampling => 0,
+            Phase::Stabilizing => 1,
+            Phase::Sleeping => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DutyCycle {
+    pub awake: Duration,
+    pub stabilize: Duration,
+    pub sleep: Duration,
+}
+
+impl From<&DutyCycleConfig> for DutyCycle {
+    fn from(c: &DutyCycleConfig) -> Self {
+        DutyCycle {
+            awake: Duration::from_secs(c.awake_secs),
+            stabilize: Duration::from_secs(c.stabilize_secs),
+            sleep: Duration::from_secs(c.sleep_secs),
+        }
+    }
+}
+
+impl DutyCycle {
+    // where the cycle is, `since_wake` after the sensor was woken and with or
+    //  without a stabilized reading taken since
+    //  Past the awake time the sensor is kept up until it has delivered one
+    //  good reading, but for no more than another stabilize period -- a sensor
+    //  that has stopped answering still gets to sleep.
+    pub fn phase(&self, since_wake: Duration, sampled: bool) -> Phase {
+        if since_wake < self.stabilize {
+            Phase::Stabilizing
+        } else if since_wake < self.awake || (!sampled && since_wake < self.awake + self.stabilize) {
+            Phase::Sampling
+        } else {
+            Phase::Sleeping
+        }
+    }
+}
@@ -0,0 +1,45 @@
+#[cfg(test)]
+mod duty_cycle_tests {
+    use crate::duty_cycle::*;
+    use std::time::Duration;
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    const CYCLE: DutyCycle = DutyCycle {
+        awake: Duration::from_secs(45),
+        stabilize: Duration::from_secs(30),
+        sleep: Duration::from_secs(270),
+    };
+
+    #[test]
+    fn readings_wait_for_the_fan() {
+        assert_eq!(CYCLE.phase(secs(0), false), Phase::Stabilizing);
+        assert_eq!(CYCLE.phase(secs(29), false), Phase::Stabilizing);
+        assert_eq!(CYCLE.phase(secs(30), false), Phase::Sampling);
+        assert_eq!(CYCLE.phase(secs(44), true), Phase::Sampling);
+        assert_eq!(CYCLE.phase(secs(45), true), Phase::Sleeping);
+    }
+
+    #[test]
+    fn stays_up_for_one_stabilized_reading() {
+        let c = DutyCycle { awake: secs(30), ..CYCLE };
+
+        assert_eq!(c.phase(secs(30), false), Phase::Sampling);
+        assert_eq!(c.phase(secs(31), true), Phase::Sleeping);
+    }
+
+    #[test]
+    fn gives_up_on_a_silent_sensor() {
+        assert_eq!(CYCLE.phase(secs(74), false), Phase::Sampling);
+        assert_eq!(CYCLE.phase(secs(75), false), Phase::Sleeping);
+    }
+
+    #[test]
+    fn built_from_config() {
+        let c = crate::config::DutyCycleConfig { awake_secs: 30, stabilize_secs: 30, sleep_secs: 270 };
+
+        assert_eq!(DutyCycle::from(&c), DutyCycle { awake: secs(30), stabilize: secs(30), sleep: secs(270) });
+    }
+}
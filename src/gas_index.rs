@@ -0,0 +1,330 @@
+#[cfg(test)]
+mod tests;
+
+// Sensirion gas index algorithm
+//  CF: https://github.com/Sensirion/gas-index-algorithm (v3.2.0)
+//
+//  Turns the raw SGP40/SGP41 signals into the VOC index (1..500, 100 is the
+//  average of the past 24 h) and the NOx index (1..500, 1 is normal). The
+//  sensor has no absolute scale, so the algorithm keeps learning the signal's
+//  recent mean and spread and reports where each reading falls -- which takes
+//  the first few hours after a start, and 45 s before anything is reported
+//  at all. A straight port of the reference C, f32 and all, so the indices
+//  match what other SGP devices show.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    Voc,
+    Nox,
+}
+
+const INITIAL_BLACKOUT: f32 = 45.0;
+const INDEX_GAIN: f32 = 230.0;
+const SRAW_STD_INITIAL: f32 = 50.0;
+const SRAW_STD_BONUS_VOC: f32 = 220.0;
+const SRAW_STD_NOX: f32 = 2000.0;
+const TAU_MEAN_HOURS: f32 = 12.0;
+const TAU_VARIANCE_HOURS: f32 = 12.0;
+const TAU_INITIAL_MEAN_VOC: f32 = 20.0;
+const TAU_INITIAL_MEAN_NOX: f32 = 1200.0;
+const INIT_DURATION_MEAN_VOC: f32 = 3600.0 * 0.75;
+const INIT_DURATION_MEAN_NOX: f32 = 3600.0 * 4.75;
+const INIT_TRANSITION_MEAN: f32 = 0.01;
+const TAU_INITIAL_VARIANCE: f32 = 2500.0;
+const INIT_DURATION_VARIANCE_VOC: f32 = 3600.0 * 1.45;
+const INIT_DURATION_VARIANCE_NOX: f32 = 3600.0 * 5.70;
+const INIT_TRANSITION_VARIANCE: f32 = 0.01;
+const GATING_THRESHOLD_VOC: f32 = 340.0;
+const GATING_THRESHOLD_NOX: f32 = 30.0;
+const GATING_THRESHOLD_INITIAL: f32 = 510.0;
+const GATING_THRESHOLD_TRANSITION: f32 = 0.09;
+const GATING_VOC_MAX_DURATION_MINUTES: f32 = 60.0 * 3.0;
+const GATING_NOX_MAX_DURATION_MINUTES: f32 = 60.0 * 12.0;
+const GATING_MAX_RATIO: f32 = 0.3;
+const SIGMOID_L: f32 = 500.0;
+const SIGMOID_K_VOC: f32 = -0.0065;
+const SIGMOID_X0_VOC: f32 = 213.0;
+const SIGMOID_K_NOX: f32 = -0.0101;
+const SIGMOID_X0_NOX: f32 = 614.0;
+const VOC_INDEX_OFFSET: f32 = 100.0;
+const NOX_INDEX_OFFSET: f32 = 1.0;
+const LP_TAU_FAST: f32 = 20.0;
+const LP_TAU_SLOW: f32 = 500.0;
+const LP_ALPHA: f32 = -0.2;
+const VOC_SRAW_MINIMUM: i32 = 20000;
+const NOX_SRAW_MINIMUM: i32 = 10000;
+const GAMMA_SCALING: f32 = 64.0;
+const ADDITIONAL_GAMMA_MEAN_SCALING: f32 = 8.0;
+const FIX16_MAX: f32 = 32767.0;
+
+// 1 / (1 + e^(k (x - x0))), flattened to 0 and 1 far from x0
+fn sigmoid(x0: f32, k: f32, sample: f32) -> f32 {
+    let x = k * (sample - x0);
+    if x < -50.0 {
+        1.0
+    } else if x > 50.0 {
+        0.0
+    } else {
+        1.0 / (1.0 + x.exp())
+    }
+}
+
+// the signal's running mean and standard deviation, learnt quickly at first
+//  and slowly later, and not at all while the index is high -- so a long
+//  stretch of bad air isn't learnt as the new normal
+#[derive(Debug, Clone)]
+struct MeanVarianceEstimator {
+    initialized: bool,
+    mean: f32,
+    sraw_offset: f32,
+    std: f32,
+    gamma_mean: f32,
+    gamma_variance: f32,
+    gamma_initial_mean: f32,
+    gamma_initial_variance: f32,
+    current_gamma_mean: f32,
+    current_gamma_variance: f32,
+    uptime_gamma: f32,
+    uptime_gating: f32,
+    gating_duration_minutes: f32,
+}
+
+impl MeanVarianceEstimator {
+    fn new(algorithm: Algorithm, interval: f32) -> Self {
+        let tau_initial_mean = match algorithm {
+            Algorithm::Voc => TAU_INITIAL_MEAN_VOC,
+            Algorithm::Nox => TAU_INITIAL_MEAN_NOX,
+        };
+        let hours = interval / 3600.0;
+
+        MeanVarianceEstimator {
+            initialized: false,
+            mean: 0.0,
+            sraw_offset: 0.0,
+            std: SRAW_STD_INITIAL,
+            gamma_mean: ADDITIONAL_GAMMA_MEAN_SCALING * GAMMA_SCALING * hours / (TAU_MEAN_HOURS + hours),
+            gamma_variance: GAMMA_SCALING * hours / (TAU_VARIANCE_HOURS + hours),
+            gamma_initial_mean: ADDITIONAL_GAMMA_MEAN_SCALING * GAMMA_SCALING * interval / (tau_initial_mean + interval),
+            gamma_initial_variance: GAMMA_SCALING * interval / (TAU_INITIAL_VARIANCE + interval),
+            current_gamma_mean: 0.0,
+            current_gamma_variance: 0.0,
+            uptime_gamma: 0.0,
+            uptime_gating: 0.0,
+            gating_duration_minutes: 0.0,
+        }
+    }
+
+    fn mean(&self) -> f32 {
+        self.mean + self.sraw_offset
+    }
+
+    fn calculate_gamma(&mut self, p: &Params, gas_index: f32) {
+        let uptime_limit = FIX16_MAX - p.interval;
+        if self.uptime_gamma < uptime_limit {
+            self.uptime_gamma += p.interval;
+        }
+        if self.uptime_gating < uptime_limit {
+            self.uptime_gating += p.interval;
+        }
+
+        let sigmoid_gamma_mean = sigmoid(p.init_duration_mean, INIT_TRANSITION_MEAN, self.uptime_gamma);
+        let gamma_mean = self.gamma_mean + (self.gamma_initial_mean - self.gamma_mean) * sigmoid_gamma_mean;
+        let gating_threshold_mean = p.gating_threshold + (GATING_THRESHOLD_INITIAL - p.gating_threshold)
+            * sigmoid(p.init_duration_mean, INIT_TRANSITION_MEAN, self.uptime_gating);
+        let sigmoid_gating_mean = sigmoid(gating_threshold_mean, GATING_THRESHOLD_TRANSITION, gas_index);
+        self.current_gamma_mean = sigmoid_gating_mean * gamma_mean;
+
+        let sigmoid_gamma_variance = sigmoid(p.init_duration_variance, INIT_TRANSITION_VARIANCE, self.uptime_gamma);
+        let gamma_variance = self.gamma_variance
+            + (self.gamma_initial_variance - self.gamma_variance) * (sigmoid_gamma_variance - sigmoid_gamma_mean);
+        let gating_threshold_variance = p.gating_threshold + (GATING_THRESHOLD_INITIAL - p.gating_threshold)
+            * sigmoid(p.init_duration_variance, INIT_TRANSITION_VARIANCE, self.uptime_gating);
+        let sigmoid_gating_variance = sigmoid(gating_threshold_variance, GATING_THRESHOLD_TRANSITION, gas_index);
+        self.current_gamma_variance = sigmoid_gating_variance * gamma_variance;
+
+        self.gating_duration_minutes += p.interval / 60.0
+            * ((1.0 - sigmoid_gating_mean) * (1.0 + GATING_MAX_RATIO) - GATING_MAX_RATIO);
+        if self.gating_duration_minutes < 0.0 {
+            self.gating_duration_minutes = 0.0;
+        }
+        if self.gating_duration_minutes > p.gating_max_duration_minutes {
+            self.uptime_gating = 0.0;
+        }
+    }
+
+    fn process(&mut self, p: &Params, gas_index: f32, sraw: f32) {
+        if !self.initialized {
+            self.initialized = true;
+            self.sraw_offset = sraw;
+            self.mean = 0.0;
+            return;
+        }
+
+        if self.mean >= 100.0 || self.mean <= -100.0 {
+            self.sraw_offset += self.mean;
+            self.mean = 0.0;
+        }
+        let sraw = sraw - self.sraw_offset;
+        self.calculate_gamma(p, gas_index);
+
+        let delta_sgp = (sraw - self.mean) / GAMMA_SCALING;
+        let c = if delta_sgp < 0.0 { self.std - delta_sgp } else { self.std + delta_sgp };
+        let additional_scaling = if c > 1440.0 { (c / 1440.0) * (c / 1440.0) } else { 1.0 };
+
+        self.std = (additional_scaling * (GAMMA_SCALING - self.current_gamma_variance)).sqrt()
+            * (self.std * (self.std / (GAMMA_SCALING * additional_scaling))
+                + self.current_gamma_variance * delta_sgp / additional_scaling * delta_sgp).sqrt();
+        self.mean += self.current_gamma_mean * delta_sgp / ADDITIONAL_GAMMA_MEAN_SCALING;
+    }
+}
+
+// smooths the index, faster when it moves a lot
+#[derive(Debug, Clone)]
+struct AdaptiveLowpass {
+    a1: f32,
+    a2: f32,
+    x: Option<[f32; 3]>,
+}
+
+impl AdaptiveLowpass {
+    fn new(interval: f32) -> Self {
+        AdaptiveLowpass {
+            a1: interval / (LP_TAU_FAST + interval),
+            a2: interval / (LP_TAU_SLOW + interval),
+            x: None,
+        }
+    }
+
+    fn process(&mut self, interval: f32, sample: f32) -> f32 {
+        let [x1, x2, x3] = self.x.get_or_insert([sample; 3]);
+        *x1 = (1.0 - self.a1) * *x1 + self.a1 * sample;
+        *x2 = (1.0 - self.a2) * *x2 + self.a2 * sample;
+
+        let f1 = (LP_ALPHA * (*x1 - *x2).abs()).exp();
+        let tau_a = (LP_TAU_SLOW - LP_TAU_FAST) * f1 + LP_TAU_FAST;
+        let a3 = interval / (interval + tau_a);
+        *x3 = (1.0 - a3) * *x3 + a3 * sample;
+
+        *x3
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Params {
+    algorithm: Algorithm,
+    interval: f32,
+    index_offset: f32,
+    sraw_minimum: i32,
+    gating_max_duration_minutes: f32,
+    init_duration_mean: f32,
+    init_duration_variance: f32,
+    gating_threshold: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct GasIndex {
+    params: Params,
+    uptime: f32,
+    sraw: f32,
+    gas_index: f32,
+    estimator: MeanVarianceEstimator,
+    lowpass: AdaptiveLowpass,
+}
+
+impl GasIndex {
+    // `interval` is the time between readings in seconds -- 1 s, as Sensirion
+    //  recommends, up to 10 s
+    pub fn new(algorithm: Algorithm, interval: f32) -> Self {
+        let params = match algorithm {
+            Algorithm::Voc => Params {
+                algorithm,
+                interval,
+                index_offset: VOC_INDEX_OFFSET,
+                sraw_minimum: VOC_SRAW_MINIMUM,
+                gating_max_duration_minutes: GATING_VOC_MAX_DURATION_MINUTES,
+                init_duration_mean: INIT_DURATION_MEAN_VOC,
+                init_duration_variance: INIT_DURATION_VARIANCE_VOC,
+                gating_threshold: GATING_THRESHOLD_VOC,
+            },
+            Algorithm::Nox => Params {
+                algorithm,
+                interval,
+                index_offset: NOX_INDEX_OFFSET,
+                sraw_minimum: NOX_SRAW_MINIMUM,
+                gating_max_duration_minutes: GATING_NOX_MAX_DURATION_MINUTES,
+                init_duration_mean: INIT_DURATION_MEAN_NOX,
+                init_duration_variance: INIT_DURATION_VARIANCE_NOX,
+                gating_threshold: GATING_THRESHOLD_NOX,
+            },
+        };
+
+        GasIndex {
+            params,
+            uptime: 0.0,
+            sraw: 0.0,
+            gas_index: 0.0,
+            estimator: MeanVarianceEstimator::new(algorithm, interval),
+            lowpass: AdaptiveLowpass::new(interval),
+        }
+    }
+
+    fn mox_model(&self, sraw: f32) -> f32 {
+        let mean = self.estimator.mean();
+        match self.params.algorithm {
+            Algorithm::Voc => (sraw - mean) / -(self.estimator.std + SRAW_STD_BONUS_VOC) * INDEX_GAIN,
+            Algorithm::Nox => (sraw - mean) / SRAW_STD_NOX * INDEX_GAIN,
+        }
+    }
+
+    fn sigmoid_scaled(&self, sample: f32) -> f32 {
+        let (x0, k, offset_default) = match self.params.algorithm {
+            Algorithm::Voc => (SIGMOID_X0_VOC, SIGMOID_K_VOC, VOC_INDEX_OFFSET),
+            Algorithm::Nox => (SIGMOID_X0_NOX, SIGMOID_K_NOX, NOX_INDEX_OFFSET),
+        };
+        let offset = self.params.index_offset;
+
+        let x = k * (sample - x0);
+        if x < -50.0 {
+            SIGMOID_L
+        } else if x > 50.0 {
+            0.0
+        } else if sample >= 0.0 {
+            let shift = if offset_default == 1.0 {
+                (500.0 / 499.0) * (1.0 - offset)
+            } else {
+                (SIGMOID_L - 5.0 * offset) / 4.0
+            };
+            (SIGMOID_L + shift) / (1.0 + x.exp()) - shift
+        } else {
+            offset / offset_default * (SIGMOID_L / (1.0 + x.exp()))
+        }
+    }
+
+    // the index for one raw reading, 0 during the blackout after a start
+    pub fn process(&mut self, sraw: u16) -> u16 {
+        let p = self.params;
+        if self.uptime <= INITIAL_BLACKOUT {
+            self.uptime += p.interval;
+            return (self.gas_index + 0.5) as u16;
+        }
+
+        let sraw = sraw as i32;
+        if sraw > 0 && sraw < 65000 {
+            let sraw = sraw.clamp(p.sraw_minimum + 1, p.sraw_minimum + 32767);
+            self.sraw = (sraw - p.sraw_minimum) as f32;
+        }
+
+        self.gas_index = if p.algorithm == Algorithm::Voc || self.estimator.initialized {
+            self.sigmoid_scaled(self.mox_model(self.sraw))
+        } else {
+            p.index_offset
+        };
+        self.gas_index = self.lowpass.process(p.interval, self.gas_index).max(0.5);
+
+        if self.sraw > 0.0 {
+            self.estimator.process(&p, self.gas_index, self.sraw);
+        }
+
+        (self.gas_index + 0.5) as u16
+    }
+}
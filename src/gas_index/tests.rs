@@ -0,0 +1,56 @@
+#[cfg(test)]
+mod gas_index_tests {
+    use crate::gas_index::*;
+
+    // the index after `secs` one-second readings of `sraw`
+    fn run(index: &mut GasIndex, sraw: u16, secs: u32) -> u16 {
+        (0..secs).map(|_| index.process(sraw)).last().unwrap()
+    }
+
+    #[test]
+    fn nothing_during_the_blackout() {
+        let mut voc = GasIndex::new(Algorithm::Voc, 1.0);
+        assert_eq!(run(&mut voc, 30000, 45), 0);
+        let mut nox = GasIndex::new(Algorithm::Nox, 1.0);
+        assert_eq!(run(&mut nox, 16000, 45), 0);
+    }
+
+    #[test]
+    fn steady_air_is_the_offset() {
+        // 100 is average for VOC, 1 is normal for NOx
+        let mut voc = GasIndex::new(Algorithm::Voc, 1.0);
+        assert_eq!(run(&mut voc, 30000, 600), 100);
+        let mut nox = GasIndex::new(Algorithm::Nox, 1.0);
+        assert_eq!(run(&mut nox, 16000, 600), 1);
+    }
+
+    #[test]
+    fn voc_rise_lowers_the_raw_signal() {
+        // the sensor's resistance drops with more VOCs about
+        let mut voc = GasIndex::new(Algorithm::Voc, 1.0);
+        run(&mut voc, 30000, 600);
+        let high = run(&mut voc, 28000, 60);
+        assert!(high > 200, "{}", high);
+
+        // and the index falls back once the air clears
+        let settled = run(&mut voc, 30000, 1800);
+        assert!(settled < 120, "{}", settled);
+    }
+
+    #[test]
+    fn nox_rise_raises_the_raw_signal() {
+        let mut nox = GasIndex::new(Algorithm::Nox, 1.0);
+        run(&mut nox, 16000, 600);
+        let high = run(&mut nox, 20000, 60);
+        assert!(high > 20, "{}", high);
+    }
+
+    #[test]
+    fn ignores_readings_out_of_range() {
+        // 0 and readings over 65000 are the sensor failing, not air
+        let mut voc = GasIndex::new(Algorithm::Voc, 1.0);
+        run(&mut voc, 30000, 600);
+        assert_eq!(run(&mut voc, 0, 10), 100);
+        assert_eq!(run(&mut voc, 65535, 10), 100);
+    }
+}
@@ -0,0 +1,88 @@
+#[cfg(test)]
+mod tests;
+
+use std::time::SystemTime;
+
+use crate::bus::{Event, IndoorAir, Pollutant};
+
+// Combined indoor air score
+//
+//  One number for how the air is, from whatever the unit measures: each of
+//  PM2.5, CO2, the VOC index and the NOx index is put into one of five bands,
+//  and the score is the worst of them. Averaging would let clean air on one
+//  count hide bad air on another.
+//
+//      score            PM2.5 ug/m^3   CO2 ppm   VOC index   NOx index
+//      1 excellent      <= 10          < 800     <= 150      <= 20
+//      2 good           <= 25          < 1000    <= 250      <= 100
+//      3 moderate       <= 50          < 1500    <= 350      <= 200
+//      4 poor           <= 75          < 2000    <= 450      <= 300
+//      5 unhealthy      above          above     above       above
+//
+//  PM2.5 after any correction, as fed to the particulate indices. The CO2
+//  bands follow the Umweltbundesamt's guidance as co2::level does, and the VOC
+//  and NOx bands Sensirion's.
+
+pub const LABELS: [&str; 5] = ["excellent", "good", "moderate", "poor", "unhealthy"];
+
+// upper bounds of bands 1..4, and whether a bound is in its band
+fn band(value: f64, bounds: [f64; 4], inclusive: bool) -> u8 {
+    let within = |b: &f64| if inclusive { value <= *b } else { value < *b };
+    bounds.iter().position(within).map_or(5, |i| i as u8 + 1)
+}
+
+pub fn sub_score(pollutant: Pollutant, value: f64) -> u8 {
+    match pollutant {
+        Pollutant::Pm2_5 => band(value, [10.0, 25.0, 50.0, 75.0], true),
+        Pollutant::Co2 => band(value, [800.0, 1000.0, 1500.0, 2000.0], false),
+        Pollutant::Voc => band(value, [150.0, 250.0, 350.0, 450.0], true),
+        Pollutant::Nox => band(value, [20.0, 100.0, 200.0, 300.0], true),
+    }
+}
+
+// "excellent" .. "unhealthy"
+pub fn label(score: u8) -> &'static str {
+    LABELS[(score.clamp(1, 5) - 1) as usize]
+}
+
+// the latest of each input, from the bus
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Tracker {
+    pm2_5: Option<f64>,
+    co2: Option<f64>,
+    voc: Option<f64>,
+    nox: Option<f64>,
+}
+
+impl Tracker {
+    // whether the event was one of the inputs
+    pub fn observe(&mut self, event: &Event) -> bool {
+        match event {
+            Event::Particulate(p) => self.pm2_5 = Some(p.pm2_5),
+            Event::Co2(c) => self.co2 = Some(c.ppm),
+            Event::Gas(g) if g.voc_index.is_some() || g.nox_index.is_some() => {
+                self.voc = g.voc_index.map(|i| i as f64).or(self.voc);
+                self.nox = g.nox_index.map(|i| i as f64).or(self.nox);
+            },
+            _ => return false,
+        }
+        true
+    }
+
+    // None before any input -- on a tie the first of PM2.5, CO2, VOC and NOx
+    //  is the dominant one
+    pub fn score(&self) -> Option<IndoorAir> {
+        [
+            (Pollutant::Pm2_5, self.pm2_5),
+            (Pollutant::Co2, self.co2),
+            (Pollutant::Voc, self.voc),
+            (Pollutant::Nox, self.nox),
+        ].into_iter()
+            .filter_map(|(p, v)| v.map(|v| (p, sub_score(p, v))))
+            .fold(None, |worst: Option<(Pollutant, u8)>, (p, s)| match worst {
+                Some((_, w)) if w >= s => worst,
+                _ => Some((p, s)),
+            })
+            .map(|(dominant, score)| IndoorAir { score, dominant, at: SystemTime::now() })
+    }
+}
@@ -0,0 +1,63 @@
+#[cfg(test)]
+mod iaq_tests {
+    use std::time::SystemTime;
+
+    use crate::bus::{Co2, Co2Sensor, Event, Gas, GasReading, Pollutant};
+    use crate::iaq::*;
+
+    fn co2(ppm: f64) -> Event {
+        Event::Co2(Co2 { sensor: Co2Sensor::Scd4x, ppm, raw: ppm, compensated: false, at: SystemTime::now() })
+    }
+
+    fn gas(voc_index: Option<u16>, nox_index: Option<u16>) -> Event {
+        let reading = GasReading::Sgp41 { voc: 30000, nox: 16000 };
+        Event::Gas(Gas { reading, voc_index, nox_index, at: SystemTime::now() })
+    }
+
+    #[test]
+    fn bands() {
+        assert_eq!(sub_score(Pollutant::Pm2_5, 10.0), 1);
+        assert_eq!(sub_score(Pollutant::Pm2_5, 10.1), 2);
+        assert_eq!(sub_score(Pollutant::Pm2_5, 500.0), 5);
+        assert_eq!(sub_score(Pollutant::Co2, 799.0), 1);
+        assert_eq!(sub_score(Pollutant::Co2, 800.0), 2);
+        assert_eq!(sub_score(Pollutant::Co2, 2000.0), 5);
+        assert_eq!(sub_score(Pollutant::Voc, 100.0), 1);
+        assert_eq!(sub_score(Pollutant::Voc, 300.0), 3);
+        assert_eq!(sub_score(Pollutant::Nox, 1.0), 1);
+        assert_eq!(sub_score(Pollutant::Nox, 250.0), 4);
+        assert_eq!(label(1), "excellent");
+        assert_eq!(label(5), "unhealthy");
+    }
+
+    #[test]
+    fn nothing_to_score_without_inputs() {
+        let mut t = Tracker::default();
+        assert!(!t.observe(&gas(None, None)));
+        assert_eq!(t.score(), None);
+    }
+
+    #[test]
+    fn worst_input_wins() {
+        let mut t = Tracker::default();
+        assert!(t.observe(&co2(900.0)));
+        let s = t.score().unwrap();
+        assert_eq!((s.score, s.dominant), (2, Pollutant::Co2));
+
+        assert!(t.observe(&gas(Some(320), Some(1))));
+        let s = t.score().unwrap();
+        assert_eq!((s.score, s.dominant), (3, Pollutant::Voc));
+
+        // a tie goes to the first input
+        t.observe(&co2(1200.0));
+        assert_eq!(t.score().unwrap().dominant, Pollutant::Co2);
+    }
+
+    #[test]
+    fn keeps_the_last_index_while_one_is_missing() {
+        let mut t = Tracker::default();
+        t.observe(&gas(Some(460), Some(1)));
+        t.observe(&gas(None, Some(1)));
+        assert_eq!(t.score().unwrap().score, 5);
+    }
+}
@@ -1,23 +1,45 @@
 use chrono::Local;
-use gpio_am2302_rs::{ try_read };
 use std::{
     collections::HashMap,
     env,
-    fs::{ File },
     future,
-    io::Read,
     net::SocketAddr,
-    sync::{Arc, Mutex},
+    sync::{mpsc, Arc, Mutex},
     thread,
-    time::{Duration, SystemTime},
+    time::{Duration, Instant, SystemTime},
 };
 
-use crate::payload::Payload;
+use crate::payload::{bme680::Bme680Reading, sps30::Sps30Reading, Pms5003Reading};
 
+mod alarms;
+use alarms::Alarms;
+mod aqi;
+use aqi::{schemes::{Category, IndexScheme}, Breakpoints, NowCast};
+mod bus;
+mod climate;
+mod co2;
+use bus::{Bus, ClimateHealth, Event, Gas, GasReading, Link, Observer, Particulate, PmReading, Quality};
 mod config;
+mod correction;
+use correction::Correction;
+use config::{ConfigError, DisplayConfig, Parity, Pms5003Config, RtuConfig};
+mod device_id;
+mod duty_cycle;
+mod gas_index;
+use gas_index::{Algorithm, GasIndex};
 mod grove_rgb_lcd;
+mod iaq;
 use grove_rgb_lcd::GroveRgbLcd;
+mod measurements;
+use measurements::{Rolling, Window};
+mod modbus_crc;
 mod payload;
+mod registers;
+mod rtu;
+mod sensors;
+use sensors::Kind;
+mod serial;
+use registers::{Point, RegisterBank};
 
 
 use tokio::net::TcpListener;
@@ -26,9 +48,17 @@ use tokio_modbus::{
     prelude::*,
     server::tcp::{accept_tcp_connection, Server},
 };
+use tokio_serial::{DataBits, SerialStream, StopBits};
+
+// what the Modbus servers publish, kept up to date by the RegisterSink
+#[derive(Clone)]
+struct Readings {
+    registers: Arc<Mutex<RegisterBank>>,
+    alarms: Arc<Mutex<Alarms>>,
+}
 
 struct ModbusService {
-    input_registers: Arc<Mutex<HashMap<u16, u16>>>,
+    readings: Readings,
     holding_registers: Arc<Mutex<HashMap<u16, u16>>>,
 }
 
@@ -40,7 +70,11 @@ impl tokio_modbus::server::Service for ModbusService {
         match req {
             Request::ReadInputRegisters(addr, cnt) => {
                  future::ready(
-                    register_read(&self.input_registers.lock().unwrap(), addr, cnt)
+                    self.readings.registers.lock().unwrap().read(addr, cnt)
+                        .ok_or_else(|| {
+                            println!("SERVER: Exception::IllegalDataAddress");
+                            Exception::IllegalDataAddress
+                        })
                         .map(Response::ReadInputRegisters),
                 )
             },
@@ -60,6 +94,46 @@ impl tokio_modbus::server::Service for ModbusService {
                 )
                 .map(|_| Response::WriteSingleRegister(addr, value)),
             ),
+            Request::MaskWriteRegister(addr, and_mask, or_mask) => {
+                let mut holding_registers = self.holding_registers.lock().unwrap();
+                future::ready(
+                    register_read(&holding_registers, addr, 1)
+                        .map(|r| (r[0] & and_mask) | (or_mask & !and_mask))
+                        .and_then(|v| register_write(&mut holding_registers, addr, &[v]))
+                        .map(|_| Response::MaskWriteRegister(addr, and_mask, or_mask)),
+                )
+            },
+            // the write goes first, so the read sees it -- but not before both
+            //  ranges are known to be good
+            Request::ReadWriteMultipleRegisters(read_addr, cnt, write_addr, values) => {
+                let mut holding_registers = self.holding_registers.lock().unwrap();
+                future::ready(
+                    register_range(&holding_registers, read_addr, cnt.into())
+                        .and_then(|_| register_write(&mut holding_registers, write_addr, &values))
+                        .and_then(|_| register_read(&holding_registers, read_addr, cnt))
+                        .map(Response::ReadWriteMultipleRegisters),
+                )
+            },
+            Request::ReadDiscreteInputs(addr, cnt) => future::ready(
+                bits_or_exception(self.readings.alarms.lock().unwrap().discrete_inputs(addr, cnt))
+                    .map(Response::ReadDiscreteInputs),
+            ),
+            Request::ReadCoils(addr, cnt) => future::ready(
+                bits_or_exception(self.readings.alarms.lock().unwrap().coils(addr, cnt))
+                    .map(Response::ReadCoils),
+            ),
+            Request::WriteSingleCoil(addr, value) => future::ready(
+                bits_or_exception(self.readings.alarms.lock().unwrap().write_coils(addr, &[value]))
+                    .map(|_| Response::WriteSingleCoil(addr, value)),
+            ),
+            Request::WriteMultipleCoils(addr, values) => future::ready(
+                bits_or_exception(self.readings.alarms.lock().unwrap().write_coils(addr, &values))
+                    .map(|_| Response::WriteMultipleCoils(addr, values.len() as u16)),
+            ),
+            Request::Custom(device_id::FUNCTION, data) => future::ready(
+                device_id::respond(&device_id::objects(), &data)
+                    .map(|r| Response::Custom(device_id::FUNCTION, r.into())),
+            ),
             _ => {
                 println!("SERVER: Exception::IllegalFunction - Unimplemented function code in request: {req:?}");
                 future::ready(Err(Exception::IllegalFunction))
@@ -69,63 +143,73 @@ impl tokio_modbus::server::Service for ModbusService {
 }
 
 impl ModbusService {
-    fn new(readings: Arc<Mutex<HashMap<u16, u16>>>) -> Self {
+    fn new(readings: Readings) -> Self {
         let mut holding_registers = HashMap::with_capacity(16);
         for k in 0..16 {
             holding_registers.insert(k, 0);
         }
 
         Self {
-            input_registers: readings,
+            readings,
             holding_registers: Arc::new(Mutex::new(holding_registers)),
         }
     }
 }
 
+/// Check that every register from addr on exists, without running past
+/// 0xFFFF, so a request is carried out in full or not at all.
+fn register_range(
+    registers: &HashMap<u16, u16>,
+    addr: u16,
+    cnt: usize,
+) -> Result<(), Exception> {
+    let end = addr as usize + cnt;
+    if end > 0x10000 || !(addr as usize..end).all(|a| registers.contains_key(&(a as u16))) {
+        println!("SERVER: Exception::IllegalDataAddress");
+        return Err(Exception::IllegalDataAddress);
+    }
+
+    Ok(())
+}
+
 /// Helper function implementing reading registers from a HashMap.
 fn register_read(
     registers: &HashMap<u16, u16>,
     addr: u16,
     cnt: u16,
 ) -> Result<Vec<u16>, Exception> {
-    let mut response_values = vec![0; cnt.into()];
-    for i in 0..cnt {
-        let reg_addr = addr + i;
-        if let Some(r) = registers.get(&reg_addr) {
-            response_values[i as usize] = *r;
-        } else {
-            println!("SERVER: Exception::IllegalDataAddress");
-            return Err(Exception::IllegalDataAddress);
-        }
-    }
-
-    Ok(response_values)
+    register_range(registers, addr, cnt.into())?;
+    Ok((0..cnt).map(|i| registers[&(addr + i)]).collect())
 }
 
-/// Write a holding register. Used by both the write single register
-/// and write multiple registers requests.
+/// Write holding registers. Used by the write single register, write
+/// multiple registers, mask write and read/write requests.
 fn register_write(
     registers: &mut HashMap<u16, u16>,
     addr: u16,
     values: &[u16],
 ) -> Result<(), Exception> {
+    register_range(registers, addr, values.len())?;
     for (i, value) in values.iter().enumerate() {
-        let reg_addr = addr + i as u16;
-        if let Some(r) = registers.get_mut(&reg_addr) {
-            *r = *value;
-        } else {
-            println!("SERVER: Exception::IllegalDataAddress");
-            return Err(Exception::IllegalDataAddress);
-        }
+        registers.insert(addr + i as u16, *value);
     }
 
     Ok(())
 }
 
-fn write_to_display(disp: &mut GroveRgbLcd, data: &str) -> ()
+// coils and discrete inputs are the alarms -- there are only so many of them
+fn bits_or_exception<T>(bits: Option<T>) -> Result<T, Exception> {
+    bits.ok_or_else(|| {
+        println!("SERVER: Exception::IllegalDataAddress");
+        Exception::IllegalDataAddress
+    })
+}
+
+// the second line is `status` and the time -- "Moderate   14:05"
+fn write_to_display(disp: &mut GroveRgbLcd, data: &str, status: &str) -> ()
 {
     let date = Local::now();
-    let t = format!("{}\n    {}", data, date.format("%d %b %H:%M"));
+    let t = format!("{}\n{:<10.10} {}", data, status, date.format("%H:%M"));
     match disp.set_text(&t)
     {
         Err(err) => { println!("error: {:?}", err);},
@@ -135,57 +219,25 @@ fn write_to_display(disp: &mut GroveRgbLcd, data: &str) -> ()
     ()
 }
 
-// CF: https://forum.airnowtech.org/t/the-aqi-equation/169
-// computes AQI for PM 2.5 concentrations and PM 10, then 
-// averages them for an aggregate number
-fn aqi(conc_2_5: f64, conc_10: f64) -> f64
-{
-    // First use PM 2.5 in ug/m^3
-    let (conc_lo, conc_hi, aqi_lo, aqi_hi) = match conc_2_5
-    {
-        x if x <  12.0 => (  0.0,  12.0,   0.0,  50.0),
-        x if x <  35.4 => ( 12.1,  35.4,  51.0, 100.0),
-        x if x <  55.4 => ( 35.5,  55.4, 101.0, 150.0),
-        x if x < 150.4 => ( 55.5, 150.4, 151.0, 200.0),
-        x if x < 250.4 => (150.5, 250.4, 201.0, 300.0),
-        x if x < 500.4 => (250.5, 500.4, 301.0, 500.0),
-        
-        _ => (500.5, 1000.0, 501.0, 1000.0),
-    };
-    let aqi_2_5 = ((aqi_hi - aqi_lo)/(conc_hi - conc_lo))*
-                    (conc_2_5 - conc_lo) +
-                    aqi_lo;
-
-    // Now use PM 10 in ug/m^3
-    let (conc_lo, conc_hi, aqi_lo, aqi_hi) = match conc_10
-    {
-        x if x <  54.0 => (  0.0,  54.0,   0.0,  50.0),
-        x if x < 154.0 => ( 55.0, 154.0,  51.0, 100.0),
-        x if x < 254.0 => (155.0, 254.0, 101.0, 150.0),
-        x if x < 354.0 => (255.0, 354.0, 151.0, 200.0),
-        x if x < 424.0 => (355.0, 424.0, 201.0, 300.0),
-        x if x < 604.0 => (425.0, 604.0, 301.0, 500.0),
-        
-        _ => (605.0, 1000.0, 501.0, 1000.0),
-    };
-    let aqi_10 = ((aqi_hi - aqi_lo)/(conc_hi - conc_lo))*
-                    (conc_10 - conc_lo) +
-                    aqi_lo;
-
-    (aqi_2_5 + aqi_10)/2.0
-}
+// the backlight with nothing to show a colour for
+const IDLE_RGB: (u8, u8, u8) = (0x10, 0x10, 0x40);
 
+// the category's colour, unless the configuration gives fixed thresholds
 // CF - https://www.epa.gov/sites/default/files/2014-05/documents/zell-aqi.pdf
-fn set_display_color_for_aqi(disp: &mut GroveRgbLcd, aqi_level: u16) -> ()
+fn set_display_color_for_aqi(disp: &mut GroveRgbLcd, aqi_level: u16, category: &Category, thresholds: Option<&[u16; 6]>) -> ()
 {
+    let Some(thresholds) = thresholds else {
+        let _ = disp.set_rgb(category.rgb);
+        return;
+    };
     let (r, g, b) = match aqi_level
     {
-        x if x <=  15 => (0x00, 0x10, 0x40),    // light blue
-        x if x <=  50 => (0x00, 0x80, 0x00),    // green
-        x if x <= 100 => (0x80, 0x80, 0x00),    // yellow
-        x if x <= 150 => (0xF0, 0x40, 0x00),    // orange
-        x if x <= 200 => (0xF0, 0x00, 0x00),    // red
-        x if x <= 300 => (0xA0, 0x00, 0x40),    // purple
+        x if x <= thresholds[0] => (0x00, 0x10, 0x40),    // light blue
+        x if x <= thresholds[1] => (0x00, 0x80, 0x00),    // green
+        x if x <= thresholds[2] => (0x80, 0x80, 0x00),    // yellow
+        x if x <= thresholds[3] => (0xF0, 0x40, 0x00),    // orange
+        x if x <= thresholds[4] => (0xF0, 0x00, 0x00),    // red
+        x if x <= thresholds[5] => (0xA0, 0x00, 0x40),    // purple
         
         _ => (0xFF, 0x00, 0xFF)     // maroon
     };
@@ -197,196 +249,604 @@ fn set_display_color_for_aqi(disp: &mut GroveRgbLcd, aqi_level: u16) -> ()
     ()
 }
 
-fn read_float_register(registers: &HashMap<u16, u16>, addr: u16) -> f32 {
-    let u = (read_register(registers, addr) as u32) << 16 | 
-                    (read_register(registers, addr + 1) as u32);
-    let f = f32::from_bits(u);
-    f
+// write one point, logging rather than failing on values that can't be encoded
+fn publish(registers: &mut RegisterBank, point: &Point, value: f64) {
+    if let Err(e) = registers.set(point, value) {
+        eprintln!("{}", e);
+    }
 }
-fn write_float_register(registers: &mut HashMap<u16, u16>, addr: u16, f: f32) {
-    let u = f.to_bits() as u32;
-    write_register(registers, addr, (u >> 16) as u16);
-    write_register(registers, addr + 1, (u & 0xFFFF) as u16);
-} 
 
-#[allow(dead_code)]
-fn read_long_register(registers: &HashMap<u16, u16>, addr: u16) -> u32 {
-    (read_register(registers, addr) as u32) << 16 | 
-        (read_register(registers, addr + 1) as u32)
+fn publish_pms5003(registers: &mut RegisterBank, r: &Pms5003Reading) {
+    publish(registers, &registers::PMS_PM1_0_CF1, r.pm1_0_cf1 as f64);
+    publish(registers, &registers::PMS_PM2_5_CF1, r.pm2_5_cf1 as f64);
+    publish(registers, &registers::PMS_PM10_CF1, r.pm10_cf1 as f64);
+    publish(registers, &registers::PMS_PM1_0_ATM, r.pm1_0_atm as f64);
+    publish(registers, &registers::PMS_PM2_5_ATM, r.pm2_5_atm as f64);
+    publish(registers, &registers::PMS_PM10_ATM, r.pm10_atm as f64);
+    publish(registers, &registers::PMS_PARTICLES_0_3UM, r.particles_0_3um as f64);
+    publish(registers, &registers::PMS_PARTICLES_0_5UM, r.particles_0_5um as f64);
+    publish(registers, &registers::PMS_PARTICLES_1_0UM, r.particles_1_0um as f64);
+    publish(registers, &registers::PMS_PARTICLES_2_5UM, r.particles_2_5um as f64);
+    publish(registers, &registers::PMS_PARTICLES_5_0UM, r.particles_5_0um as f64);
+    publish(registers, &registers::PMS_PARTICLES_10UM, r.particles_10um as f64);
+    publish(registers, &registers::PMS_VERSION, r.version as f64);
+    publish(registers, &registers::PMS_ERROR_CODE, r.error_code as f64);
 }
-fn write_long_register(registers: &mut HashMap<u16, u16>, addr: u16, u: u32) {
-    let hw = (u >> 16) as u16;
-    let lw = (u & 0xFFFF) as u16;
-    write_register(registers, addr, hw);
-    write_register(registers, addr + 1, lw);
+
+fn publish_sps30(registers: &mut RegisterBank, r: &Sps30Reading) {
+    let values = [r.pm1_0, r.pm2_5, r.pm4_0, r.pm10, r.nc0_5, r.nc1_0, r.nc2_5, r.nc4_0, r.nc10, r.typical_size];
+    for (p, v) in registers::SPS30_VALUES.iter().zip(values) {
+        publish(registers, p, v);
+    }
 }
 
-fn read_register(registers: &HashMap<u16, u16>, addr: u16) -> u16 {
-    match registers.get(&addr) {
-        Some(x) => *x,
-        None => 0,
+fn publish_bme680(registers: &mut RegisterBank, r: &Bme680Reading) {
+    let values = [r.gas_resistance.unwrap_or(f64::NAN), r.temperature, r.humidity, r.pressure];
+    for (p, v) in registers::BME680_VALUES.iter().zip(values) {
+        publish(registers, p, v);
     }
 }
-fn write_register(registers: &mut HashMap<u16, u16>, addr: u16, value: u16) {
-    if !registers.contains_key(&addr) {
-        registers.insert(addr, value);
-    } else {
-        let x = registers.get_mut(&addr).unwrap();
-        *x = value;
+
+fn publish_link(registers: &mut RegisterBank, link: &Link) {
+    publish(registers, &registers::PMS_FRAMES, link.stats.frames as f64);
+    publish(registers, &registers::PMS_CHECKSUM_ERRORS, link.stats.checksum_errors as f64);
+    publish(registers, &registers::PMS_LENGTH_ERRORS, link.stats.length_errors as f64);
+    publish(registers, &registers::PMS_RESYNC_BYTES, link.stats.resync_bytes as f64);
+
+    let age = link.frame_age
+        .map_or(u32::MAX as u64, |d| d.as_secs().min(u32::MAX as u64 - 1));
+    publish(registers, &registers::PMS_FRAME_AGE, age as f64);
+    publish(registers, &registers::PMS_STATE, link.phase.code() as f64);
+}
+
+fn publish_climate_health(registers: &mut RegisterBank, h: &ClimateHealth) {
+    publish(registers, &registers::CLIMATE_READINGS, h.health.readings as u32 as f64);
+    publish(registers, &registers::CLIMATE_ERRORS, h.health.errors as u32 as f64);
+    publish(registers, &registers::CLIMATE_REJECTED, h.rejected as u32 as f64);
+
+    let age = h.health.since_last
+        .map_or(u32::MAX as u64, |d| d.as_secs().min(u32::MAX as u64 - 1));
+    publish(registers, &registers::CLIMATE_AGE, age as f64);
+}
+
+fn quality_point(kind: Kind) -> &'static Point {
+    match kind {
+        Kind::Particulate => &registers::PM_QUALITY,
+        Kind::Climate => &registers::TEMP_HUM_QUALITY,
+        Kind::Co2 => &registers::CO2_QUALITY,
+        Kind::Gas => &registers::GAS_QUALITY,
     }
 }
 
-// indexes into the readings register
-const AQI: u16 = 0;
-const PM_1_0: u16 = 1;
-const PM_2_5: u16 = 2;
-const PM_10: u16 = 3;
-const AQI_TICK_HW: u16 = 4;
-const TEMP_HW: u16 = 6;
-const HUM_HW: u16 = 8;
-const TEMP_HUM_TICK_HW: u16 = 10;
+// temperature and humidity go NaN -- 0x8000 in the scaled integers -- when the
+//  sensor stops delivering; the integer registers keep their last value, and
+//  only the quality word tells
+fn publish_quality(registers: &mut RegisterBank, kind: Kind, quality: Quality) {
+    publish(registers, quality_point(kind), quality.code() as f64);
+    if kind == Kind::Climate && matches!(quality, Quality::Stale | Quality::SensorError) {
+        for p in [&registers::TEMP, &registers::HUM, &registers::TEMP_X10, &registers::HUM_X10] {
+            publish(registers, p, f64::NAN);
+        }
+    }
+}
+
+// each window's mean, median, min, max and standard deviation, NaN while empty
+fn publish_rolling(registers: &mut RegisterBank, points: &[[Point; 5]; 4], rolling: &Rolling, now: Instant) {
+    for (stats, summary) in points.iter().zip(rolling.summaries(now)) {
+        let values = summary.map_or([f64::NAN; 5], |s| [s.mean, s.median, s.min, s.max, s.stddev]);
+        for (p, v) in stats.iter().zip(values) {
+            publish(registers, p, v);
+        }
+    }
+}
+
+fn epoch_secs(at: SystemTime) -> u64 {
+    at.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn epoch_ticks(at: SystemTime) -> f64 {
+    (epoch_secs(at) & 0xFFFFffff) as f64
+}
 
-const CHUNK_SIZE: usize = 64;
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>>  {
-    let socket_addr: SocketAddr = "0.0.0.0:5502".parse().unwrap();
-
-    // use readings to hold the last 3 readings in a register format for the modbus server
-    let mut registers: HashMap<u16, u16> = HashMap::with_capacity(16);
-    // default them to 0
-    write_register(&mut registers, AQI, 0);         // 1 * 16-bit
-    write_register(&mut registers, PM_1_0, 0);      // 1
-    write_register(&mut registers, PM_2_5, 0);      // 1
-    write_register(&mut registers, PM_10, 0);    // 1
-    write_long_register(&mut registers, AQI_TICK_HW, 0); // 2 * 16-bit
-
-    write_float_register(&mut registers, TEMP_HW, -40.0); // 2 
-    write_float_register(&mut registers, HUM_HW, 0.0);  // 2
-    write_long_register(&mut registers, TEMP_HUM_TICK_HW, 0); // 2 * 16-bit
-    // 12 registers * 16-bit = 24 bytes
-    
-    let readings = Arc::new(Mutex::new(registers));
-
-    let r1 = readings.clone();
-    thread::spawn(move || {
-        sampling_context(r1);
-    });
+    let args: Vec<String> = env::args().collect();
+    let config = match config::parse_config(&args) {
+        Ok(config) => config,
+        Err(ConfigError::Help) => {
+            print!("{}", config::USAGE);
+            return Ok(());
+        },
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        },
+    };
 
-    let r2 = readings.clone();
-    thread::spawn(move || {
-        temp_humidity_sampling(r2);
+    // the outputs subscribe before the sensors start, so none of them misses
+    //  a reading
+    let bus = Bus::new();
+
+    let readings = config.outputs.modbus.then(|| {
+        // every input register point starts out as 0 -- except temperature and
+        //  humidity, the rolling statistics and the SPS30 and BME680 values,
+        //  which are NaN until they have a reading
+        let mut registers = RegisterBank::with_encoding(&config.encoding());
+        for p in [&registers::TEMP, &registers::HUM, &registers::TEMP_X10, &registers::HUM_X10] {
+            publish(&mut registers, p, f64::NAN);
+        }
+        for kind in [Kind::Particulate, Kind::Climate, Kind::Co2, Kind::Gas] {
+            publish(&mut registers, quality_point(kind), Quality::NoData.code() as f64);
+        }
+        publish(&mut registers, &registers::CLIMATE_AGE, u32::MAX as f64);
+        publish(&mut registers, &registers::PMS_FRAME_AGE, u32::MAX as f64);
+        publish(&mut registers, &registers::AQI_NOWCAST, u16::MAX as f64);
+        publish(&mut registers, &registers::INDEX_SCHEME, config.index_scheme().code() as f64);
+        publish(&mut registers, &registers::PM_2_5_CORRECTION, config.correction().code() as f64);
+        for p in registers::ROLLING_REGISTERS.iter().chain(&registers::SPS30_VALUES).chain(&registers::BME680_VALUES) {
+            publish(&mut registers, p, f64::NAN);
+        }
+        print!("{}", registers::dump(registers.points()));
+        print!("{}", alarms::dump());
+
+        let readings = Readings {
+            registers: Arc::new(Mutex::new(registers)),
+            alarms: Arc::new(Mutex::new(Alarms::default())),
+        };
+        bus.attach_ticking(RegisterSink::new(readings.clone(), config.rolling_windows()), ROLLING_REFRESH);
+        readings
     });
 
-    // add a display output thread
-    let r3 = readings.clone();
-    thread::spawn(move || {
-        display_registers(r3);
-});
+    if config.outputs.display {
+        let events = bus.subscribe();
+        let (display, scheme) = (config.display.clone(), config.index_scheme());
+        thread::spawn(move || {
+            display_context(events, &display, scheme.as_ref());
+        });
+    }
+
+    if config.outputs.log {
+        bus.attach(Logger);
+    }
+    bus.attach(StatsLog::new(&config.pms5003));
+
+    let indices = Indices { scheme: config.index_scheme(), breakpoints: config.breakpoints(), correction: config.correction() };
+    bus.attach(Indexer::new(bus.clone(), indices, config.gas.interval_secs));
+    bus.attach(Scorer::new(bus.clone()));
+
+    for (kind, sensor) in sensors::from_config(&config) {
+        let b = bus.clone();
+        thread::spawn(move || {
+            sensors::run(sensor, kind, b);
+        });
+    }
+
+    match (readings, &config.modbus.rtu) {
+        (Some(readings), Some(rtu)) => {
+            tokio::try_join!(server_context(config.socket_addr(), readings.clone()), rtu_server_context(rtu, readings))?;
+        },
+        (Some(readings), None) => server_context(config.socket_addr(), readings).await?,
+        // nothing to serve -- the sampling threads keep running
+        (None, _) => future::pending::<()>().await,
+    }
+
+    Ok(())
+}
+
+// how often the rolling statistics are brought up to date without a reading
+const ROLLING_REFRESH: Duration = Duration::from_secs(1);
+
+// the Modbus input registers, kept up to date from the bus
+struct RegisterSink {
+    registers: Arc<Mutex<RegisterBank>>,
+    alarms: Arc<Mutex<Alarms>>,
+    pm2_5: Rolling,
+    pm10: Rolling,
+    temp: Rolling,
+    hum: Rolling,
+}
+
+impl RegisterSink {
+    fn new(readings: Readings, windows: [Window; 4]) -> Self {
+        let Readings { registers, alarms } = readings;
+        let rolling = || Rolling::new(windows);
+        RegisterSink { registers, alarms, pm2_5: rolling(), pm10: rolling(), temp: rolling(), hum: rolling() }
+    }
+}
+
+impl Observer<Event> for RegisterSink {
+    fn update(&mut self, event: &Event) {
+        let now = Instant::now();
+        match event {
+            Event::Particulate(p) => {
+                self.pm2_5.push(now, p.pm2_5);
+                self.pm10.push(now, p.pm10);
+
+                let mut registers = self.registers.lock().unwrap();
+                publish(&mut registers, &registers::AQI, p.aqi.value as f64);
+                publish(&mut registers, &registers::AQI_NOWCAST, p.nowcast.map_or(u16::MAX, |a| a.value) as f64);
+                publish(&mut registers, &registers::AQI_DOMINANT, p.aqi.dominant.code() as f64);
+                publish(&mut registers, &registers::AQI_PM2_5, p.aqi.pm2_5 as f64);
+                publish(&mut registers, &registers::AQI_PM10, p.aqi.pm10 as f64);
+                publish(&mut registers, &registers::INDEX, p.index.value as f64);
+                publish(&mut registers, &registers::INDEX_CATEGORY, p.index.category as f64);
+                publish(&mut registers, &registers::PM_2_5_CORRECTED, p.pm2_5);
+                publish(&mut registers, &registers::PM_SENSOR, p.reading.code() as f64);
+                match &p.reading {
+                    PmReading::Pms5003(r) => {
+                        publish(&mut registers, &registers::PM_1_0, r.pm1_0_cf1 as f64);
+                        publish(&mut registers, &registers::PM_2_5, r.pm2_5_cf1 as f64);
+                        publish(&mut registers, &registers::PM_10, r.pm10_cf1 as f64);
+                        publish_pms5003(&mut registers, r);
+                    },
+                    // no PM1.0, and nothing for the PMS5003 frame block
+                    PmReading::Sds011(r) => {
+                        publish(&mut registers, &registers::PM_2_5, r.pm2_5);
+                        publish(&mut registers, &registers::PM_10, r.pm10);
+                    },
+                    PmReading::Sps30(r) => {
+                        publish(&mut registers, &registers::PM_1_0, r.pm1_0);
+                        publish(&mut registers, &registers::PM_2_5, r.pm2_5);
+                        publish(&mut registers, &registers::PM_10, r.pm10);
+                        publish_sps30(&mut registers, r);
+                    },
+                    PmReading::Simulated(r) => {
+                        publish(&mut registers, &registers::PM_2_5, r.pm2_5);
+                        publish(&mut registers, &registers::PM_10, r.pm10);
+                    },
+                }
+                publish_rolling(&mut registers, &registers::PM_2_5_ROLLING, &self.pm2_5, now);
+                publish_rolling(&mut registers, &registers::PM_10_ROLLING, &self.pm10, now);
+                publish(&mut registers, &registers::AQI_TICK, epoch_ticks(p.at));
+            },
+            Event::Climate(c) => {
+                self.temp.push(now, c.temperature);
+                self.hum.push(now, c.humidity);
+
+                let mut registers = self.registers.lock().unwrap();
+                publish(&mut registers, &registers::TEMP, c.temperature);
+                publish(&mut registers, &registers::HUM, c.humidity);
+                publish(&mut registers, &registers::TEMP_X10, c.temperature);
+                publish(&mut registers, &registers::HUM_X10, c.humidity);
+                if let Some(hpa) = c.pressure {
+                    publish(&mut registers, &registers::PRESSURE, hpa);
+                }
+                publish(&mut registers, &registers::TEMP_HUM_TICK, epoch_ticks(c.at));
+                publish_rolling(&mut registers, &registers::TEMP_ROLLING, &self.temp, now);
+                publish_rolling(&mut registers, &registers::HUM_ROLLING, &self.hum, now);
+            },
+            Event::Co2(c) => {
+                let mut registers = self.registers.lock().unwrap();
+                publish(&mut registers, &registers::CO2, c.ppm);
+                publish(&mut registers, &registers::CO2_RAW, c.raw);
+                publish(&mut registers, &registers::CO2_SENSOR, c.sensor.code() as f64);
+                publish(&mut registers, &registers::CO2_COMPENSATED, c.compensated as u16 as f64);
+                publish(&mut registers, &registers::CO2_TICK, epoch_ticks(c.at));
+            },
+            Event::Gas(g) => {
+                let mut registers = self.registers.lock().unwrap();
+                match &g.reading {
+                    GasReading::Sgp40 { voc } => publish(&mut registers, &registers::VOC_RAW, *voc as f64),
+                    GasReading::Sgp41 { voc, nox } => {
+                        publish(&mut registers, &registers::VOC_RAW, *voc as f64);
+                        publish(&mut registers, &registers::NOX_RAW, *nox as f64);
+                    },
+                    GasReading::Bme680(r) => publish_bme680(&mut registers, r),
+                }
+                publish(&mut registers, &registers::VOC_INDEX, g.voc_index.unwrap_or(0) as f64);
+                publish(&mut registers, &registers::NOX_INDEX, g.nox_index.unwrap_or(0) as f64);
+                publish(&mut registers, &registers::GAS_SENSOR, g.reading.code() as f64);
+                publish(&mut registers, &registers::GAS_TICK, epoch_ticks(g.at));
+            },
+            Event::IndoorAir(a) => {
+                let mut registers = self.registers.lock().unwrap();
+                publish(&mut registers, &registers::IAQ_SCORE, a.score as f64);
+                publish(&mut registers, &registers::IAQ_DOMINANT, a.dominant.code() as f64);
+            },
+            Event::Link(link) => publish_link(&mut self.registers.lock().unwrap(), link),
+            Event::ClimateHealth(h) => publish_climate_health(&mut self.registers.lock().unwrap(), h),
+            Event::Quality(kind, q) => publish_quality(&mut self.registers.lock().unwrap(), *kind, *q),
+            // published once the Indexer has made a Particulate or Gas of it
+            Event::Pm(_) | Event::Voc(_) => {},
+        }
+
+        self.alarms.lock().unwrap().update(&self.registers.lock().unwrap());
+    }
+
+    // the windows age between readings too -- a sensor that has gone quiet
+    //  has its windows expire to NaN
+    fn tick(&mut self) {
+        let now = Instant::now();
+        let mut registers = self.registers.lock().unwrap();
+        publish_rolling(&mut registers, &registers::PM_2_5_ROLLING, &self.pm2_5, now);
+        publish_rolling(&mut registers, &registers::PM_10_ROLLING, &self.pm10, now);
+        publish_rolling(&mut registers, &registers::TEMP_ROLLING, &self.temp, now);
+        publish_rolling(&mut registers, &registers::HUM_ROLLING, &self.hum, now);
+    }
+}
 
-    tokio::select! {
-        _ = server_context(socket_addr, readings.clone()) => unreachable!(),
+// readings to stdout as they arrive
+struct Logger;
+
+impl Observer<Event> for Logger {
+    fn update(&mut self, event: &Event) {
+        match event {
+            Event::Particulate(p) => match &p.reading {
+                PmReading::Pms5003(r) => println!("{},{},{}", r.pm1_0_cf1, r.pm2_5_cf1, r.pm10_cf1),
+                // no PM1.0 -- the column is left empty
+                PmReading::Sds011(r) => println!(",{:.1},{:.1}", r.pm2_5, r.pm10),
+                PmReading::Sps30(r) => println!("{:.1},{:.1},{:.1}", r.pm1_0, r.pm2_5, r.pm10),
+                PmReading::Simulated(r) => println!(",{:.1},{:.1}", r.pm2_5, r.pm10),
+            },
+            Event::Climate(c) => match c.pressure {
+                Some(hpa) => println!("{:.1}°C,{:.1}%,{:.1}hPa", c.temperature, c.humidity, hpa),
+                None => println!("{:.1}°C,{:.1}%", c.temperature, c.humidity),
+            },
+            Event::Co2(c) => println!("{:.0}ppm", c.ppm),
+            Event::Gas(g) => match &g.reading {
+                GasReading::Sgp40 { .. } | GasReading::Sgp41 { .. } => {
+                    let index = |i: Option<u16>| i.map_or(String::new(), |i| i.to_string());
+                    println!("VOC {},NOx {}", index(g.voc_index), index(g.nox_index));
+                },
+                GasReading::Bme680(r) => match r.gas_resistance {
+                    Some(ohms) => println!("{:.0}Ω,{:.1}hPa", ohms, r.pressure),
+                    None => println!(",{:.1}hPa", r.pressure),
+                },
+            },
+            Event::Pm(_) | Event::Voc(_) | Event::IndoorAir(_) => {},
+            Event::Link(_) | Event::ClimateHealth(_) | Event::Quality(..) => {},
+        }
     }
 }
 
-fn display_registers(readings: Arc<Mutex<HashMap<u16, u16>>>) {
+fn display_context(events: mpsc::Receiver<Event>, config: &DisplayConfig, scheme: &dyn IndexScheme) {
     let mut display = grove_rgb_lcd::connect().unwrap();
-    let _ = display.set_rgb((0x10, 0x10, 0x40));
+    let _ = display.set_rgb(IDLE_RGB);
+
+    write_to_display(&mut display, &"", scheme.name());
+
+    // what's on screen is redrawn every refresh_secs from the latest of each
+    //  reading -- shown as "--" while its quality says it isn't valid, which
+    //  it isn't until the sensor reports
+    let refresh = Duration::from_secs(config.refresh_secs);
+    let mut next = Instant::now() + refresh;     // wait for the first reading to come in
+    let (mut aqi, mut category) = (0, 0);
+    let (mut temp, mut hum) = (0.0, 0.0);
+    // with a CO2 or gas sensor, the refreshes take turns showing those too
+    let mut co2 = None;
+    let mut gas: Option<Gas> = None;
+    let mut air = None;
+    let mut screen = 0;
+    let (mut pm_quality, mut climate_quality) = (Quality::NoData, Quality::NoData);
+    let (mut co2_quality, mut gas_quality) = (Quality::NoData, Quality::NoData);
 
-    write_to_display(&mut display, &"");
-    
     loop {
-        thread::sleep(Duration::from_secs(30));     // wait for the first reading to come in
+        match events.recv_timeout(next.saturating_duration_since(Instant::now())) {
+            Ok(Event::Particulate(p)) => (aqi, category) = (p.index.value, p.index.category),
+            Ok(Event::Climate(c)) => (temp, hum) = (c.temperature, c.humidity),
+            Ok(Event::Co2(c)) => co2 = Some(c.ppm),
+            Ok(Event::Gas(g)) => gas = Some(g),
+            Ok(Event::IndoorAir(a)) => air = Some(a),
+            Ok(Event::Quality(kind, q)) => match kind {
+                Kind::Particulate => pm_quality = q,
+                Kind::Climate => climate_quality = q,
+                Kind::Co2 => co2_quality = q,
+                Kind::Gas => gas_quality = q,
+            },
+            Ok(_) | Err(mpsc::RecvTimeoutError::Timeout) => {},
+            Err(mpsc::RecvTimeoutError::Disconnected) => return,
+        }
+        if Instant::now() < next {
+            continue;
+        }
+        next = Instant::now() + refresh;
+
+        // the AQI screen, then whichever of the others there's a reading for
+        let screens = [true, co2.is_some(), gas.is_some()];
+        screen = (screen + 1..).map(|s| s % screens.len()).find(|&s| screens[s]).unwrap_or(0);
+        match (screen, co2, gas) {
+            (1, Some(ppm), _) => {
+                // "CO2 xxxx ppm"
+                if co2_quality.is_valid() {
+                    write_to_display(&mut display, &format!("CO2 {:.0} ppm", ppm), co2::level(ppm));
+                } else {
+                    write_to_display(&mut display, "CO2 -- ppm", co2_quality.label());
+                }
+                continue;
+            },
+            (2, _, Some(g)) => {
+                // "VOC xxx NOx xxx", or the BME680's "Gas xxxx kOhm"
+                let valid = gas_quality.is_valid();
+                let index = |i: Option<u16>| i.filter(|_| valid).map_or("--".to_string(), |i| i.to_string());
+                let line1 = match g.reading {
+                    GasReading::Sgp40 { .. } => format!("VOC {}", index(g.voc_index)),
+                    GasReading::Sgp41 { .. } => format!("VOC {} NOx {}", index(g.voc_index), index(g.nox_index)),
+                    GasReading::Bme680(r) => r.gas_resistance.filter(|_| valid)
+                        .map_or("Gas --".to_string(), |ohms| format!("Gas {:.0} kOhm", ohms / 1000.0)),
+                };
+                // the combined score, once there is one
+                let status = match air {
+                    _ if !valid => gas_quality.label(),
+                    Some(a) => iaq::label(a.score),
+                    None => "",
+                };
+                write_to_display(&mut display, &line1, status);
+                continue;
+            },
+            _ => {},
+        }
 
         // lines are 16 chars long
         // "AQI xx xx.x° xx%"
-        let registers = readings.lock().unwrap();
-        let aqi = read_register(&*registers, AQI);
         // let deg = 0xDF as char;
         let deg = 'F';  // for now just use F -- the char isn't showing up as per datasheet
-        let t = read_float_register(&*registers, TEMP_HW) * 9.0/5.0 + 32.0;
-        let h = read_float_register(&*registers, HUM_HW) as u16;
-        drop(registers);
+        let climate = if climate_quality.is_valid() {
+            format!("{:.1}{} {}%", temp * 9.0/5.0 + 32.0, deg, hum as u16)
+        } else {
+            format!("--{} --%", deg)
+        };
 
-        let line1 = format!("AQI {} {:.1}{} {}%", aqi, t, deg, h);
+        if !pm_quality.is_valid() {
+            let line1 = format!("{} -- {}", scheme.label(), climate);
+            write_to_display(&mut display, &line1, pm_quality.label());
+            let _ = display.set_rgb(IDLE_RGB);
+            continue;
+        }
 
-        write_to_display(&mut display, &line1);
-        set_display_color_for_aqi(&mut display, aqi);
+        let category = scheme.category(category);
+        let line1 = format!("{} {} {}", scheme.label(), aqi, climate);
+
+        write_to_display(&mut display, &line1, category.short);
+        set_display_color_for_aqi(&mut display, aqi, category, config.aqi_thresholds.as_ref());
     }
 }
 
-// temp and humidity sampling
-const GPIO_NUMBER: u32 = 4;
-fn temp_humidity_sampling(readings: Arc<Mutex<HashMap<u16, u16>>>) {
-    loop {
-        match try_read(GPIO_NUMBER) {
-            Ok(reading) => {
-                println!("{:.1}°C,{:.1}%", 
-                    reading.temperature, reading.humidity);
-
-                let mut registers = readings.lock().unwrap();
-                write_float_register(&mut *registers, TEMP_HW, reading.temperature);
-                write_float_register(&mut *registers, HUM_HW, reading.humidity);
-
-                let ticks: u64 = SystemTime::now()
-                    .duration_since(SystemTime::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs();
-                write_long_register(&mut *registers, TEMP_HUM_TICK_HW, (ticks & 0xFFFFffff) as u32);
-                drop(registers);
-            },
-            _ => { },
-        }
-        thread::sleep(Duration::from_secs(10));
+// how PM readings become index values
+struct Indices {
+    scheme: Box<dyn IndexScheme>,
+    breakpoints: Breakpoints,
+    correction: Correction,
+}
+
+// a reading with its corrected PM2.5 and every index computed from it, with
+//  `rh` the latest relative humidity
+fn particulate(r: &PmReading, rh: Option<f64>, nowcast: &mut NowCast, indices: &Indices) -> Particulate {
+    let at = SystemTime::now();
+    // the corrections are fits for Plantower sensors
+    let pm2_5 = match r {
+        PmReading::Pms5003(pms) => indices.correction.apply(pms, rh),
+        PmReading::Sds011(_) | PmReading::Sps30(_) | PmReading::Simulated(_) => r.pm2_5(),
+    };
+    let pm10 = r.pm10();
+
+    nowcast.push(epoch_secs(at), pm2_5, pm10);
+
+    Particulate {
+        reading: *r,
+        pm2_5,
+        pm10,
+        aqi: aqi::aqi(pm2_5, pm10, indices.breakpoints),
+        nowcast: nowcast.aqi(epoch_secs(at), indices.breakpoints),
+        index: indices.scheme.index(pm2_5, pm10),
+        at,
     }
 }
 
-fn sampling_context(readings: Arc<Mutex<HashMap<u16, u16>>>) {  
-    let args: Vec<String> = env::args().collect();
+// a gas reading with the Sensirion indices computed from it -- 0 from the
+//  algorithm is its blackout after a start
+fn gas(r: &GasReading, voc: &mut GasIndex, nox: &mut GasIndex) -> Gas {
+    let ready = |i: u16| (i > 0).then_some(i);
+    let (voc_index, nox_index) = match r {
+        GasReading::Sgp40 { voc: raw } => (ready(voc.process(*raw)), None),
+        GasReading::Sgp41 { voc: raw_voc, nox: raw_nox } => (ready(voc.process(*raw_voc)), ready(nox.process(*raw_nox))),
+        // Bosch's index is in their closed-source library
+        GasReading::Bme680(_) => (None, None),
+    };
 
-    let mut f = File::open(config::parse_config(&args)).unwrap();
-    let mut d = [0; 2*CHUNK_SIZE];
+    Gas { reading: *r, voc_index, nox_index, at: SystemTime::now() }
+}
 
-    let mut total_read = 0;
+// turns raw PM and gas readings into Particulate and Gas events, whichever
+//  sensor they came from
+struct Indexer {
+    bus: Bus<Event>,
+    indices: Indices,
+    nowcast: NowCast,
+    // the latest humidity, once there is one -- forgotten when the climate
+    //  sensor goes stale or fails, so the EPA fit isn't run on an old reading
+    rh: Option<f64>,
+    voc: GasIndex,
+    nox: GasIndex,
+}
 
-    loop {
-        total_read += f.read(&mut d[total_read..]).unwrap_or(0);
-        if total_read < CHUNK_SIZE {
-            continue;
+impl Indexer {
+    // `gas_interval_secs` apart, as the gas sensors sample
+    fn new(bus: Bus<Event>, indices: Indices, gas_interval_secs: u64) -> Self {
+        let interval = gas_interval_secs as f32;
+        Indexer {
+            bus,
+            indices,
+            nowcast: NowCast::new(),
+            rh: None,
+            voc: GasIndex::new(Algorithm::Voc, interval),
+            nox: GasIndex::new(Algorithm::Nox, interval),
         }
+    }
+}
 
-        let p;
-        let found;
-        (_, p, found) = match payload::parse_stream_to_payload(&d) {
-            Ok((i, p)) => (i, p, true),
-            Err(_e) => (&d[..], Payload::default(), false),
-        };
+impl Observer<Event> for Indexer {
+    fn update(&mut self, event: &Event) {
+        match event {
+            Event::Climate(c) => self.rh = Some(c.humidity),
+            Event::Quality(Kind::Climate, Quality::Stale | Quality::SensorError) => self.rh = None,
+            Event::Pm(r) => {
+                let p = particulate(r, self.rh, &mut self.nowcast, &self.indices);
+                self.bus.publish(Event::Particulate(p));
+            },
+            Event::Voc(r) => {
+                let g = gas(r, &mut self.voc, &mut self.nox);
+                self.bus.publish(Event::Gas(g));
+            },
+            _ => {},
+        }
+    }
+}
+
+// the combined indoor air score, again after every reading that goes into it
+struct Scorer {
+    bus: Bus<Event>,
+    tracker: iaq::Tracker,
+}
 
-        if found {
-            let aqi_avg = aqi(p.data[1] as f64, p.data[2] as f64) as u16;
-            println!("{},{},{}", p.data[0], p.data[1], p.data[2]);
-            // update the readings registers
-            let mut registers = readings.lock().unwrap();
-            write_register(&mut *registers, AQI, aqi_avg);
-            write_register(&mut *registers, PM_1_0, p.data[0] as u16);
-            write_register(&mut *registers, PM_2_5, p.data[1] as u16);
-            write_register(&mut *registers, PM_10, p.data[2] as u16);
-
-            let ticks: u64 = SystemTime::now()
-                .duration_since(SystemTime::UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
-            write_long_register(&mut *registers, AQI_TICK_HW, (ticks & 0xFFFFffff) as u32);
-            drop(registers);
-
-            total_read = 0;
-            d = [0; 2*CHUNK_SIZE];
+impl Scorer {
+    fn new(bus: Bus<Event>) -> Self {
+        Scorer { bus, tracker: iaq::Tracker::default() }
+    }
+}
+
+impl Observer<Event> for Scorer {
+    fn update(&mut self, event: &Event) {
+        if !self.tracker.observe(event) {
+            return;
         }
+        if let Some(a) = self.tracker.score() {
+            self.bus.publish(Event::IndoorAir(a));
+        }
+    }
+}
+
+// decoder statistics to the log every stats_log_secs
+struct StatsLog {
+    device: String,
+    every: Duration,
+    last: Instant,
+}
 
-        thread::sleep(Duration::from_secs(1));
+impl StatsLog {
+    fn new(config: &Pms5003Config) -> Self {
+        StatsLog { device: config.device.clone(), every: Duration::from_secs(config.stats_log_secs), last: Instant::now() }
     }
 }
 
-async fn server_context(socket_addr: SocketAddr, readings: Arc<Mutex<HashMap<u16, u16>>>) -> anyhow::Result<()> {
+impl Observer<Event> for StatsLog {
+    fn update(&mut self, event: &Event) {
+        let Event::Link(link) = event else {
+            return;
+        };
+        if self.last.elapsed() < self.every {
+            return;
+        }
+        self.last = Instant::now();
+        match link.frame_age {
+            Some(age) => eprintln!("{}: {}, last frame {}s ago", self.device, link.stats, age.as_secs()),
+            None => eprintln!("{}: {}, no frame yet", self.device, link.stats),
+        }
+    }
+}
+
+async fn server_context(socket_addr: SocketAddr, readings: Readings) -> anyhow::Result<()> {
     println!("Starting up Modbus server on {socket_addr}");
     let listener = TcpListener::bind(socket_addr).await?;
 
@@ -404,3 +864,24 @@ async fn server_context(socket_addr: SocketAddr, readings: Arc<Mutex<HashMap<u16
 
     Ok(())
 }
+
+// the same registers on an RS-485 bus, 8 data bits
+async fn rtu_server_context(config: &RtuConfig, readings: Readings) -> anyhow::Result<()> {
+    let parity = match config.parity {
+        Parity::None => tokio_serial::Parity::None,
+        Parity::Even => tokio_serial::Parity::Even,
+        Parity::Odd => tokio_serial::Parity::Odd,
+    };
+    let stop_bits = if config.stop_bits == 2 { StopBits::Two } else { StopBits::One };
+    let builder = tokio_serial::new(&config.device, config.baud)
+        .data_bits(DataBits::Eight)
+        .parity(parity)
+        .stop_bits(stop_bits);
+    let port = SerialStream::open(&builder)?;
+    println!("Starting up Modbus RTU server on {} at {} baud, slave {}", config.device, config.baud, config.slave_id);
+
+    rtu::serve(port, config.slave_id, rtu::frame_gap(config.baud), ModbusService::new(readings)).await?;
+    println!("RTU server done");
+
+    Ok(())
+}
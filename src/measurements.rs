@@ -1,17 +1,178 @@
 #[cfg(test)]
+mod tests;
 
-mod observer;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
 
 /***
  * Measurements
- * 
- *  The measurements object is an Observable. As such, it
- *      - has an interface to add/remove observers
- *      - notifies observers when the underlying data store changes/is ready
- *  This particular implementation has some additional interfaces/behaviors
- *      - has an interface to receive an updated set of measurements
- *  When a configurable number of readings have been accumulated, each of the 
- * measures is averaged and observers notified.
- * 
+ *
+ *  A windowed aggregator: readings go in one at a time with the time they were
+ *  taken, and the mean, median, min, max and standard deviation of those still
+ *  in the window come out. The window is either the last N readings or
+ *  everything younger than some duration.
+ *
+ *  A day of readings once a second is a lot to keep on a Pi Zero, so time
+ *  windows may merge readings into buckets of a fixed resolution. Count, mean,
+ *  min, max and standard deviation stay exact; the median becomes the median
+ *  of the bucket means.
+ *
  *  Configuration is passed into the constructor and is immutable.
- */
\ No newline at end of file
+ */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Window {
+    // the most recent readings, however old
+    Count(usize),
+    // readings younger than `span`, merged into buckets `resolution` long;
+    //  a zero resolution keeps every reading
+    Time { span: Duration, resolution: Duration },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Summary {
+    pub count: u32,
+    pub mean: f64,
+    pub median: f64,
+    pub min: f64,
+    pub max: f64,
+    // population standard deviation
+    pub stddev: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    start: Instant,
+    count: u32,
+    sum: f64,
+    sum_sq: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Bucket {
+    fn new(at: Instant, value: f64) -> Self {
+        Bucket { start: at, count: 1, sum: value, sum_sq: value * value, min: value, max: value }
+    }
+
+    fn add(&mut self, value: f64) {
+        self.count += 1;
+        self.sum += value;
+        self.sum_sq += value * value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    fn mean(&self) -> f64 {
+        self.sum / self.count as f64
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Aggregator {
+    window: Window,
+    buckets: VecDeque<Bucket>,
+}
+
+impl Aggregator {
+    pub fn new(window: Window) -> Self {
+        Aggregator { window, buckets: VecDeque::new() }
+    }
+
+    // readings that aren't numbers (a failed conversion upstream) are ignored
+    pub fn push(&mut self, at: Instant, value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+
+        match self.window {
+            Window::Count(n) => {
+                self.buckets.push_back(Bucket::new(at, value));
+                while self.buckets.len() > n {
+                    self.buckets.pop_front();
+                }
+            },
+            Window::Time { span, resolution } => {
+                match self.buckets.back_mut() {
+                    Some(b) if at >= b.start && at.duration_since(b.start) < resolution => b.add(value),
+                    _ => self.buckets.push_back(Bucket::new(at, value)),
+                }
+                while self.buckets.front().is_some_and(|b| at.saturating_duration_since(b.start) >= span) {
+                    self.buckets.pop_front();
+                }
+            },
+        }
+    }
+
+    // None once the window is empty -- no readings yet, or all of them too old
+    pub fn summary(&self, now: Instant) -> Option<Summary> {
+        let live: Vec<&Bucket> = self.buckets.iter()
+            .filter(|b| match self.window {
+                Window::Count(_) => true,
+                Window::Time { span, .. } => now.saturating_duration_since(b.start) < span,
+            })
+            .collect();
+        if live.is_empty() {
+            return None;
+        }
+
+        let count: u32 = live.iter().map(|b| b.count).sum();
+        let sum: f64 = live.iter().map(|b| b.sum).sum();
+        let sum_sq: f64 = live.iter().map(|b| b.sum_sq).sum();
+        let mean = sum / count as f64;
+
+        let mut means: Vec<f64> = live.iter().map(|b| b.mean()).collect();
+        means.sort_by(f64::total_cmp);
+        let mid = means.len() / 2;
+        let median = if means.len().is_multiple_of(2) { (means[mid - 1] + means[mid]) / 2.0 } else { means[mid] };
+
+        Some(Summary {
+            count,
+            mean,
+            median,
+            min: live.iter().map(|b| b.min).fold(f64::INFINITY, f64::min),
+            max: live.iter().map(|b| b.max).fold(f64::NEG_INFINITY, f64::max),
+            // rounding can leave the variance a hair below zero
+            stddev: (sum_sq / count as f64 - mean * mean).max(0.0).sqrt(),
+        })
+    }
+}
+
+// the windows published over Modbus: 1 minute, 10 minutes, 1 hour and 24 hours
+//  at a resolution that keeps each to a few hundred buckets at most
+pub const STANDARD_WINDOWS: [Window; 4] = [
+    Window::Time { span: Duration::from_secs(60), resolution: Duration::ZERO },
+    Window::Time { span: Duration::from_secs(600), resolution: Duration::from_secs(1) },
+    Window::Time { span: Duration::from_secs(3600), resolution: Duration::from_secs(10) },
+    Window::Time { span: Duration::from_secs(86400), resolution: Duration::from_secs(240) },
+];
+
+// one measurement aggregated over four windows -- the standard ones unless
+//  configured otherwise
+#[derive(Debug, Clone)]
+pub struct Rolling {
+    windows: [Aggregator; 4],
+}
+
+impl Default for Rolling {
+    fn default() -> Self {
+        Rolling::new(STANDARD_WINDOWS)
+    }
+}
+
+impl Rolling {
+    pub fn new(windows: [Window; 4]) -> Self {
+        Rolling { windows: windows.map(Aggregator::new) }
+    }
+
+    pub fn push(&mut self, at: Instant, value: f64) {
+        for w in self.windows.iter_mut() {
+            w.push(at, value);
+        }
+    }
+
+    // in the order the windows were given
+    pub fn summaries(&self, now: Instant) -> [Option<Summary>; 4] {
+        [0, 1, 2, 3].map(|i| self.windows[i].summary(now))
+    }
+}
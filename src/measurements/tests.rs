@@ -1,22 +1,103 @@
-mod tests;
-
+#[cfg(test)]
 mod measurements_tests {
-    use create::measurements::*;
-
-    /**
-     * Tests the Measurements Observable struct/impl
-     * backlog:
-     * 
-     *  [ ] has an interface to add/remove observers
-     *  [ ] notifies observers when the underlying data store changes/is ready
-     *  [ ] has an interface to receive a single set of measurements to add to accumulator
-     *  [ ] is configured on construction with a number of slots to average
-     *  [ ] observers are only notified when the configured number of slots has been accumulated and averaged
-     *  [ ] when the accumulator is averaged (and observers notifiied), the accumulator is emptied/fully consumed
-     * 
-     */
-    
-    fn create_measurements() {
-        let m = Measurements();
-    }
-}
\ No newline at end of file
+    use crate::measurements::*;
+    use std::time::{Duration, Instant};
+
+    fn secs(n: u64) -> Duration {
+        Duration::from_secs(n)
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn empty_window_has_no_summary() {
+        assert_eq!(Aggregator::new(Window::Count(3)).summary(Instant::now()), None);
+    }
+
+    #[test]
+    fn summarizes_the_readings() {
+        let t = Instant::now();
+        let mut a = Aggregator::new(Window::Count(10));
+        for v in [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0] {
+            a.push(t, v);
+        }
+
+        let s = a.summary(t).unwrap();
+        assert_eq!(s.count, 8);
+        assert!(close(s.mean, 5.0));
+        assert!(close(s.median, 4.5));
+        assert_eq!((s.min, s.max), (2.0, 9.0));
+        assert!(close(s.stddev, 2.0));
+    }
+
+    #[test]
+    fn count_window_keeps_the_latest() {
+        let t = Instant::now();
+        let mut a = Aggregator::new(Window::Count(3));
+        for v in [100.0, 1.0, 2.0, 3.0] {
+            a.push(t, v);
+        }
+
+        let s = a.summary(t).unwrap();
+        assert_eq!((s.count, s.max, s.median), (3, 3.0, 2.0));
+    }
+
+    #[test]
+    fn time_window_drops_old_readings() {
+        let t = Instant::now();
+        let mut a = Aggregator::new(Window::Time { span: secs(60), resolution: Duration::ZERO });
+        a.push(t, 100.0);
+        a.push(t + secs(30), 10.0);
+        a.push(t + secs(60), 20.0);
+
+        let s = a.summary(t + secs(60)).unwrap();
+        assert_eq!((s.count, s.min, s.max), (2, 10.0, 20.0));
+
+        // nothing new for a while -- the window empties on its own
+        assert_eq!(a.summary(t + secs(90)).unwrap().count, 1);
+        assert_eq!(a.summary(t + secs(120)), None);
+    }
+
+    #[test]
+    fn buckets_keep_exact_statistics() {
+        let t = Instant::now();
+        let mut a = Aggregator::new(Window::Time { span: secs(3600), resolution: secs(10) });
+        for (i, v) in [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0].into_iter().enumerate() {
+            a.push(t + secs(i as u64 * 3), v);
+        }
+
+        let s = a.summary(t + secs(30)).unwrap();
+        assert_eq!(s.count, 8);
+        assert!(close(s.mean, 5.0));
+        assert_eq!((s.min, s.max), (2.0, 9.0));
+        assert!(close(s.stddev, 2.0));
+        // buckets at 0s (2, 4, 4, 4), 12s (5, 5, 7, 9)
+        assert!(close(s.median, (3.5 + 6.5) / 2.0));
+    }
+
+    #[test]
+    fn ignores_readings_that_are_not_numbers() {
+        let t = Instant::now();
+        let mut a = Aggregator::new(Window::Count(3));
+        a.push(t, f64::NAN);
+        a.push(t, f64::INFINITY);
+        assert_eq!(a.summary(t), None);
+    }
+
+    #[test]
+    fn rolling_feeds_every_standard_window() {
+        let t = Instant::now();
+        let mut r = Rolling::new(STANDARD_WINDOWS);
+        r.push(t, 12.0);
+        r.push(t + secs(120), 8.0);
+
+        let s = r.summaries(t + secs(120));
+        assert_eq!(s[0].unwrap().count, 1);
+        for w in &s[1..] {
+            assert_eq!(w.unwrap().count, 2);
+            assert!(close(w.unwrap().mean, 10.0));
+        }
+    }
+}
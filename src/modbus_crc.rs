@@ -0,0 +1,13 @@
+#[cfg(test)]
+mod tests;
+
+// The Modbus RTU CRC-16
+//
+//  Polynomial 0xA001 (0x8005 reflected), init 0xFFFF, sent low byte first.
+//  Shared by the RTU slave and the Senseair S8, which is read over Modbus RTU.
+
+pub fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0xFFFF, |crc, &b| {
+        (0..8).fold(crc ^ b as u16, |c, _| if c & 1 != 0 { (c >> 1) ^ 0xA001 } else { c >> 1 })
+    })
+}
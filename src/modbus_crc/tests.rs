@@ -0,0 +1,15 @@
+#[cfg(test)]
+mod modbus_crc_tests {
+    use crate::modbus_crc::*;
+
+    #[test]
+    fn check_value() {
+        assert_eq!(crc16(b"123456789"), 0x4B37);
+        assert_eq!(crc16(&[]), 0xFFFF);
+    }
+
+    #[test]
+    fn request_from_the_s8_manual() {
+        assert_eq!(crc16(&[0xFE, 0x04, 0x00, 0x03, 0x00, 0x01]).to_le_bytes(), [0xD5, 0xC5]);
+    }
+}
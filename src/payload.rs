@@ -3,6 +3,26 @@ extern crate nom;
 
 mod tests;
 
+pub mod bme280;
+pub mod bme680;
+pub mod mhz19;
+pub mod scd30;
+pub mod scd4x;
+pub mod sds011;
+pub mod senseair_s8;
+pub mod sensirion;
+pub mod sgp4x;
+pub mod sht3x;
+pub mod sht4x;
+pub mod sps30;
+
+use std::{
+    collections::VecDeque,
+    fmt,
+    marker::PhantomData,
+    time::{Duration, Instant},
+};
+
 use nom::{
     Err,
     IResult,
@@ -20,16 +40,73 @@ pub struct Payload {
     start: u16,     // is always 'BM'
     len: u16,           // length of payload -- constant -- 0x1C
 
-    pub data: [u16; 12],    // 12 16-bit readings for various PM concentrations
-    reserved_data: u16, // seems to always be 0x9700 -- but no doc
+    data: [u16; 12],    // 12 16-bit readings for various PM concentrations
+    reserved_data: u16, // high byte is the version, low byte the error code -- usually 0x9700
 
     check: u16
 }
 
+// typed view of the 12 data words and the version/error word of a Payload
+//  CF=1 ("standard particle") concentrations are what the datasheet calls for
+//  in a factory environment; the atmospheric ones are corrected for ambient air.
+//  Keep them apart -- they differ by up to 50% at higher concentrations.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pms5003Reading {
+    // concentrations in ug/m^3, CF=1 standard particle
+    pub pm1_0_cf1: u16,
+    pub pm2_5_cf1: u16,
+    pub pm10_cf1: u16,
+
+    // concentrations in ug/m^3, atmospheric environment
+    pub pm1_0_atm: u16,
+    pub pm2_5_atm: u16,
+    pub pm10_atm: u16,
+
+    // number of particles beyond the given diameter in 0.1 L of air
+    pub particles_0_3um: u16,
+    pub particles_0_5um: u16,
+    pub particles_1_0um: u16,
+    pub particles_2_5um: u16,
+    pub particles_5_0um: u16,
+    pub particles_10um: u16,
+
+    pub version: u8,
+    pub error_code: u8,
+}
+
+impl From<&Payload> for Pms5003Reading {
+    fn from(p: &Payload) -> Self {
+        let [version, error_code] = p.reserved_data.to_be_bytes();
+
+        Pms5003Reading {
+            pm1_0_cf1: p.data[0],
+            pm2_5_cf1: p.data[1],
+            pm10_cf1: p.data[2],
+            pm1_0_atm: p.data[3],
+            pm2_5_atm: p.data[4],
+            pm10_atm: p.data[5],
+            particles_0_3um: p.data[6],
+            particles_0_5um: p.data[7],
+            particles_1_0um: p.data[8],
+            particles_2_5um: p.data[9],
+            particles_5_0um: p.data[10],
+            particles_10um: p.data[11],
+            version,
+            error_code,
+        }
+    }
+}
+
+impl Payload {
+    pub fn reading(&self) -> Pms5003Reading {
+        Pms5003Reading::from(self)
+    }
+}
+
 pub const FRAME_START: u16 = 0x424D;        // 'BM'
 
-fn find_possible_start(s: &[u8]) -> IResult<&[u8], &[u8]> {
-    take_till(|w| w == FRAME_START.to_be_bytes()[0])(s)
+fn find_possible_start(s: &[u8], head: u8) -> IResult<&[u8], &[u8]> {
+    take_till(|b| b == head)(s)
 }
 
 fn start_tag_parser(s: &[u8]) -> IResult<&[u8], &[u8]> {
@@ -41,65 +118,255 @@ fn u16_parser(s: &[u8]) -> IResult<&[u8], u16> {
 fn take_n_bytes(s: &[u8], n: usize) -> IResult<&[u8], &[u8]> {
     take(n)(s)
 }
-pub fn parse_stream_to_payload(input: &[u8]) -> IResult<&[u8], Payload> {
-    let mut p = Payload::default();
 
-    let mut body;
-    let mut found;
-    loop {
-        ((body, _)) = match find_possible_start(input) {
-            Err(e) => return Err(e),
-            Ok((b,s)) => (b,s),
-        };
+pub const FRAME_LEN: u16 = 0x1C;          // bytes following the length word
+pub const FRAME_SIZE: usize = 4 + FRAME_LEN as usize;
 
-        (body, found) = match start_tag_parser(body) {
-            Err(_e) => (&body[2..], false),
-            Ok((body, _s)) => (body, true),
-        };
-        
-        if found {
-            p.start = FRAME_START;
-            break;
-        }
+// a command acknowledgement: 'BM', length 4, the command and data bytes echoed
+//  back, then the checksum. The sensor sends one after a mode change or sleep.
+const ACK_LEN: u16 = 0x04;
+
+fn ack_parser(input: &[u8]) -> IResult<&[u8], ()> {
+    let (body, _) = start_tag_parser(input)?;
+    let (body, len) = u16_parser(body)?;
+    if len != ACK_LEN {
+        return Err(Err::Error(nom::error::Error { input: body, code: nom::error::ErrorKind::LengthValue }));
     }
+    let (body, echo) = u16_parser(body)?;
+    let (body, check) = u16_parser(body)?;
+    if add_hi_lo_bytes(FRAME_START) + add_hi_lo_bytes(len) + add_hi_lo_bytes(echo) != check {
+        return Err(Err::Error(nom::error::Error { input: body, code: nom::error::ErrorKind::Fail }));
+    }
+
+    Ok((body, ()))
+}
+
+// parses exactly one frame that starts at the beginning of the input
+//  streaming: a truncated frame is Err::Incomplete, never a panic
+fn frame_parser(input: &[u8]) -> IResult<&[u8], Payload> {
+    let mut p = Payload::default();
 
-    let (body, len) = u16_parser(body)
-        .expect("can't read frame length");
-    if len != 0x1C {
-        eprintln!("bad length");
+    let (body, _) = start_tag_parser(input)?;
+    p.start = FRAME_START;
+
+    let (body, len) = u16_parser(body)?;
+    if len != FRAME_LEN {
         return Err(Err::Error(nom::error::Error { input: body, code: nom::error::ErrorKind::LengthValue }));
     }
     p.len = len;
+
     // len is number of bytes *remaining* in body to read
     let d_size = std::mem::size_of_val(&p.data);
-    let (body, d) = take_n_bytes(body, d_size)
-        .expect("can't read data");
-
-    let vd: Vec<u16> = d
-        .chunks_exact(std::mem::size_of::<u16>())
-        .into_iter()
-        .map(|w| u16::from_be_bytes([w[0],w[1]]))
-        .collect();
-    p.data = vd.try_into()
-        .expect("error reading data");
-    
+    let (body, d) = take_n_bytes(body, d_size)?;
+    for (w, b) in p.data.iter_mut().zip(d.chunks_exact(std::mem::size_of::<u16>())) {
+        *w = u16::from_be_bytes([b[0], b[1]]);
+    }
+
     // copy the reserved field
-    let (body, reserved) = u16_parser(body)
-        .expect("can't read reserved word");
+    let (body, reserved) = u16_parser(body)?;
     p.reserved_data = reserved;
 
     // compute checksum
     p.check = checksum(&p);
-    let (body, check) = u16_parser(body)
-        .expect("can't read checksum");
+    let (body, check) = u16_parser(body)?;
     if p.check != check {
-        // eprintln!("checksums don't match!");
         return Err(Err::Error(nom::error::Error { input: body, code: nom::error::ErrorKind::Fail }));
     }
 
     Ok((body, p))
 }
 
+// Incremental decoder for the serial byte stream
+//
+//  Bytes go in as they are read, in whatever chunks the port hands back;
+//  complete frames come out in order. Partial frames wait in the buffer for
+//  the rest of their bytes, and after a corrupt frame the decoder skips ahead
+//  to the next frame start rather than dropping everything it holds. What a
+//  frame looks like is up to the FrameFormat -- the PMS5003's here, the
+//  SDS011's in sds011.rs.
+#[derive(Debug)]
+pub struct FrameDecoder<F: FrameFormat> {
+    buf: VecDeque<u8>,
+    stats: FrameStats,
+    last_frame: Option<Instant>,
+    format: PhantomData<F>,
+}
+
+pub trait FrameFormat {
+    type Frame;
+
+    // the first byte of every frame
+    const HEAD: u8;
+    // the longest frame
+    const SIZE: usize;
+
+    // exactly one frame that starts at the beginning of the input; None for a
+    //  well-formed frame that isn't a reading, like a command acknowledgement.
+    //  Streaming: a truncated frame is Err::Incomplete. A bad length is
+    //  ErrorKind::LengthValue and a bad checksum ErrorKind::Fail, for the stats.
+    fn parse(input: &[u8]) -> IResult<&[u8], Option<Self::Frame>>;
+}
+
+#[derive(Debug)]
+pub struct Pms5003Format;
+
+impl FrameFormat for Pms5003Format {
+    type Frame = Payload;
+
+    const HEAD: u8 = FRAME_START.to_be_bytes()[0];
+    const SIZE: usize = FRAME_SIZE;
+
+    fn parse(input: &[u8]) -> IResult<&[u8], Option<Payload>> {
+        match frame_parser(input) {
+            Ok((rest, p)) => Ok((rest, Some(p))),
+            // acknowledgements to our own commands aren't errors -- drop them quietly
+            Err(Err::Error(e)) if e.code == nom::error::ErrorKind::LengthValue => match ack_parser(input) {
+                Ok((rest, _)) => Ok((rest, None)),
+                Err(Err::Incomplete(n)) => Err(Err::Incomplete(n)),
+                Err(_) => Err(Err::Error(e)),
+            },
+            Err(e) => Err(e),
+        }
+    }
+}
+
+// running totals since the decoder was created; all wrap at u32
+//  a loose RX wire shows up as resync bytes and length errors, a failing
+//  sensor as checksum errors or simply no frames at all
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FrameStats {
+    pub frames: u32,
+    pub checksum_errors: u32,
+    pub length_errors: u32,
+    // bytes thrown away while looking for the next frame start
+    pub resync_bytes: u32,
+}
+
+impl fmt::Display for FrameStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "frames {}, checksum errors {}, length errors {}, resync bytes {}",
+            self.frames, self.checksum_errors, self.length_errors, self.resync_bytes)
+    }
+}
+
+// Host commands
+//
+//  'BM', a command byte, two data bytes and a 16-bit sum of all the bytes
+//  before it. In passive mode the sensor only sends a frame when asked with
+//  ReadPassive; Sleep stops the fan and laser until Wake.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    ActiveMode,
+    PassiveMode,
+    ReadPassive,
+    Sleep,
+    Wake,
+}
+
+pub const COMMAND_SIZE: usize = 7;
+
+impl Command {
+    pub fn encode(self) -> [u8; COMMAND_SIZE] {
+        let (command, data): (u8, u16) = match self {
+            Command::PassiveMode => (0xE1, 0x0000),
+            Command::ActiveMode => (0xE1, 0x0001),
+            Command::ReadPassive => (0xE2, 0x0000),
+            Command::Sleep => (0xE4, 0x0000),
+            Command::Wake => (0xE4, 0x0001),
+        };
+        let [b, m] = FRAME_START.to_be_bytes();
+        let [data_hi, data_lo] = data.to_be_bytes();
+
+        let mut bytes = [b, m, command, data_hi, data_lo, 0, 0];
+        let sum: u16 = bytes[..5].iter().map(|&x| x as u16).sum();
+        bytes[5..].copy_from_slice(&sum.to_be_bytes());
+
+        bytes
+    }
+}
+
+impl<F: FrameFormat> Default for FrameDecoder<F> {
+    fn default() -> Self {
+        FrameDecoder::new()
+    }
+}
+
+impl<F: FrameFormat> FrameDecoder<F> {
+    // more than enough for the few frames that arrive between reads; if the
+    //  consumer falls behind, the oldest bytes are dropped
+    const CAPACITY: usize = 8 * F::SIZE;
+
+    pub fn new() -> Self {
+        FrameDecoder {
+            buf: VecDeque::with_capacity(Self::CAPACITY),
+            stats: FrameStats::default(),
+            last_frame: None,
+            format: PhantomData,
+        }
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buf.extend(bytes);
+        let excess = self.buf.len().saturating_sub(Self::CAPACITY);
+        self.skip(excess);
+    }
+
+    pub fn stats(&self) -> FrameStats {
+        self.stats
+    }
+
+    // None until the first good frame
+    pub fn since_last_frame(&self) -> Option<Duration> {
+        self.last_frame.map(|t| t.elapsed())
+    }
+
+    fn skip(&mut self, n: usize) {
+        self.buf.drain(..n);
+        self.stats.resync_bytes = self.stats.resync_bytes.wrapping_add(n as u32);
+    }
+
+    // the next complete frame, or None once more bytes are needed
+    pub fn next_frame(&mut self) -> Option<F::Frame> {
+        loop {
+            let data = self.buf.make_contiguous();
+
+            // no frame start anywhere -- nothing worth keeping
+            let Ok((candidate, _)) = find_possible_start(data, F::HEAD) else {
+                let n = data.len();
+                self.skip(n);
+                return None;
+            };
+            let start = data.len() - candidate.len();
+
+            match F::parse(&data[start..]) {
+                Ok((rest, frame)) => {
+                    let used = data.len() - rest.len();
+                    self.skip(start);
+                    self.buf.drain(..used - start);
+                    if let Some(frame) = frame {
+                        self.stats.frames = self.stats.frames.wrapping_add(1);
+                        self.last_frame = Some(Instant::now());
+                        return Some(frame);
+                    }
+                },
+                Err(Err::Incomplete(_)) => {
+                    self.skip(start);
+                    return None;
+                },
+                // not a frame after all, or a corrupt one -- resync past this start
+                Err(Err::Error(e)) | Err(Err::Failure(e)) => {
+                    match e.code {
+                        nom::error::ErrorKind::Fail => self.stats.checksum_errors = self.stats.checksum_errors.wrapping_add(1),
+                        nom::error::ErrorKind::LengthValue => self.stats.length_errors = self.stats.length_errors.wrapping_add(1),
+                        _ => {},
+                    }
+                    self.skip(start + 1);
+                },
+            }
+        }
+    }
+}
+
 // checksum is a byte-wise checksum... need to split words
 fn checksum(p: &Payload) -> u16 {
     let mut sum = 0;
@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod tests;
+
+// reading from the Bosch BME280 pressure, temperature and humidity sensor
+//  see datasheet:  https://www.bosch-sensortec.com/media/boschsensortec/downloads/datasheets/bst-bme280-ds002.pdf
+//
+//  Plain I2C registers at 0x76 or 0x77. Like the BME680 every value is an ADC
+//  count to be compensated with the sensor's own calibration; this is the
+//  datasheet's floating point version. The pressure and temperature formulas
+//  are the BMP280's.
+
+pub const I2C_ADDRESSES: [u16; 2] = [0x76, 0x77];
+
+pub const CHIP_ID: u8 = 0x60;
+
+pub const REG_CHIP_ID: u8 = 0xD0;
+pub const REG_RESET: u8 = 0xE0;
+pub const REG_CTRL_HUM: u8 = 0xF2;
+pub const REG_STATUS: u8 = 0xF3;
+pub const REG_CTRL_MEAS: u8 = 0xF4;
+pub const REG_DATA: u8 = 0xF7;
+
+pub const RESET: u8 = 0xB6;
+// the status register's "conversion running" bit
+pub const MEASURING: u8 = 0x08;
+
+// the calibration, in two blocks
+pub const CALIB_1: (u8, usize) = (0x88, 26);
+pub const CALIB_2: (u8, usize) = (0xE1, 7);
+
+// pressure, temperature, humidity
+pub const DATA_SIZE: usize = 8;
+
+// oversampling x1 for everything, one forced measurement; ctrl_hum only takes
+//  effect on the following write to ctrl_meas
+pub const CTRL_HUM: u8 = 0b001;
+pub const CTRL_MEAS_FORCED: u8 = 0b001 << 5 | 0b001 << 2 | 0b01;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct Calibration {
+    pub t1: u16,
+    pub t2: i16,
+    pub t3: i16,
+    pub p1: u16,
+    pub p2: i16,
+    pub p3: i16,
+    pub p4: i16,
+    pub p5: i16,
+    pub p6: i16,
+    pub p7: i16,
+    pub p8: i16,
+    pub p9: i16,
+    pub h1: u8,
+    pub h2: i16,
+    pub h3: u8,
+    pub h4: i16,
+    pub h5: i16,
+    pub h6: i8,
+}
+
+impl Calibration {
+    // from the CALIB_1 and CALIB_2 blocks
+    pub fn parse(c1: &[u8; CALIB_1.1], c2: &[u8; CALIB_2.1]) -> Self {
+        let u16_at = |i: usize| u16::from_le_bytes([c1[i], c1[i + 1]]);
+        let i16_at = |i: usize| i16::from_le_bytes([c1[i], c1[i + 1]]);
+
+        Calibration {
+            t1: u16_at(0),
+            t2: i16_at(2),
+            t3: i16_at(4),
+            p1: u16_at(6),
+            p2: i16_at(8),
+            p3: i16_at(10),
+            p4: i16_at(12),
+            p5: i16_at(14),
+            p6: i16_at(16),
+            p7: i16_at(18),
+            p8: i16_at(20),
+            p9: i16_at(22),
+            // 0xA0 is unused
+            h1: c1[25],
+            h2: i16::from_le_bytes([c2[0], c2[1]]),
+            h3: c2[2],
+            // signed 12-bit values sharing 0xE5
+            h4: (c2[3] as i8 as i16) << 4 | (c2[4] & 0x0F) as i16,
+            h5: (c2[5] as i8 as i16) << 4 | (c2[4] >> 4) as i16,
+            h6: c2[6] as i8,
+        }
+    }
+
+    // degC, and the fine temperature the other values are compensated with
+    pub fn temperature(&self, adc: u32) -> (f64, f64) {
+        let (adc, t1) = (adc as f64, self.t1 as f64);
+        let var1 = (adc / 16384.0 - t1 / 1024.0) * self.t2 as f64;
+        let var2 = (adc / 131072.0 - t1 / 8192.0).powi(2) * self.t3 as f64;
+        let t_fine = var1 + var2;
+
+        (t_fine / 5120.0, t_fine)
+    }
+
+    // hPa
+    pub fn pressure(&self, adc: u32, t_fine: f64) -> f64 {
+        let mut var1 = t_fine / 2.0 - 64000.0;
+        let mut var2 = var1 * var1 * self.p6 as f64 / 32768.0;
+        var2 += var1 * self.p5 as f64 * 2.0;
+        var2 = var2 / 4.0 + self.p4 as f64 * 65536.0;
+        var1 = (self.p3 as f64 * var1 * var1 / 524288.0 + self.p2 as f64 * var1) / 524288.0;
+        var1 = (1.0 + var1 / 32768.0) * self.p1 as f64;
+        if var1 == 0.0 {
+            return 0.0;
+        }
+
+        let mut p = 1048576.0 - adc as f64;
+        p = (p - var2 / 4096.0) * 6250.0 / var1;
+        let var1 = self.p9 as f64 * p * p / 2147483648.0;
+        let var2 = p * self.p8 as f64 / 32768.0;
+        p += (var1 + var2 + self.p7 as f64) / 16.0;
+
+        p / 100.0
+    }
+
+    // %
+    pub fn humidity(&self, adc: u16, t_fine: f64) -> f64 {
+        let h = t_fine - 76800.0;
+        let h = (adc as f64 - (self.h4 as f64 * 64.0 + self.h5 as f64 / 16384.0 * h))
+            * (self.h2 as f64 / 65536.0 * (1.0 + self.h6 as f64 / 67108864.0 * h * (1.0 + self.h3 as f64 / 67108864.0 * h)));
+        let h = h * (1.0 - self.h1 as f64 * h / 524288.0);
+
+        h.clamp(0.0, 100.0)
+    }
+
+    // degC, %, hPa from DATA_SIZE bytes at REG_DATA
+    pub fn reading(&self, b: &[u8; DATA_SIZE]) -> (f64, f64, f64) {
+        let adc20 = |i: usize| (b[i] as u32) << 12 | (b[i + 1] as u32) << 4 | (b[i + 2] as u32) >> 4;
+        let (temperature, t_fine) = self.temperature(adc20(3));
+
+        (temperature, self.humidity(u16::from_be_bytes([b[6], b[7]]), t_fine), self.pressure(adc20(0), t_fine))
+    }
+}
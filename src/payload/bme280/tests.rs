@@ -0,0 +1,64 @@
+#[cfg(test)]
+mod bme280_tests {
+    use crate::payload::bme280::*;
+
+    // the worked example from the BMP280 datasheet, which has the same
+    //  temperature and pressure compensation
+    fn datasheet() -> Calibration {
+        Calibration {
+            t1: 27504, t2: 26435, t3: -1000,
+            p1: 36477, p2: -10685, p3: 3024, p4: 2855, p5: 140, p6: -7, p7: 15500, p8: -14600, p9: 6000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn temperature_and_pressure() {
+        let c = datasheet();
+        let (t, t_fine) = c.temperature(519888);
+        assert!((t - 25.08).abs() < 0.01, "{}", t);
+        assert!((t_fine - 128422.0).abs() < 1.0, "{}", t_fine);
+        let p = c.pressure(415148, t_fine);
+        assert!((p - 1006.53).abs() < 0.01, "{}", p);
+    }
+
+    #[test]
+    fn humidity_is_clamped() {
+        let c = Calibration { h2: 360, h4: 320, h5: 50, h6: 30, h1: 75, h3: 0, ..datasheet() };
+        let (_, t_fine) = c.temperature(519888);
+        let rh = c.humidity(30000, t_fine);
+        assert!(rh > 0.0 && rh < 100.0, "{}", rh);
+        assert_eq!(c.humidity(0, t_fine), 0.0);
+        assert_eq!(c.humidity(u16::MAX, t_fine), 100.0);
+    }
+
+    #[test]
+    fn calibration_layout() {
+        let mut c1 = [0u8; CALIB_1.1];
+        let c2 = [0x6A, 0x01, 0x00, 0x13, 0x2E, 0x03, 0x1E];
+        c1[0..2].copy_from_slice(&27504u16.to_le_bytes());
+        c1[4..6].copy_from_slice(&(-1000i16).to_le_bytes());
+        c1[25] = 75;
+
+        let c = Calibration::parse(&c1, &c2);
+        assert_eq!((c.t1, c.t3, c.h1), (27504, -1000, 75));
+        assert_eq!(c.h2, 362);
+        assert_eq!(c.h4, 0x13E);
+        assert_eq!(c.h5, 0x032);
+        assert_eq!(c.h6, 30);
+
+        // H4 and H5 are signed
+        let c = Calibration::parse(&c1, &[0, 0, 0, 0xFF, 0x0F, 0xFF, 0]);
+        assert_eq!((c.h4, c.h5), (-1, -16));
+    }
+
+    #[test]
+    fn data_layout() {
+        let c = datasheet();
+        // pressure 415148 and temperature 519888, 20 bits each
+        let b = [0x65, 0x5A, 0xC0, 0x7E, 0xED, 0x00, 0x80, 0x00];
+        let (t, _, p) = c.reading(&b);
+        assert!((t - 25.08).abs() < 0.01);
+        assert!((p - 1006.53).abs() < 0.01);
+    }
+}
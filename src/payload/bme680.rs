@@ -0,0 +1,282 @@
+#[cfg(test)]
+mod tests;
+
+// reading from the Bosch BME680 / BME688 gas, pressure, temperature and humidity
+//  sensors
+//  see datasheets:  https://www.bosch-sensortec.com/media/boschsensortec/downloads/datasheets/bst-bme680-ds001.pdf
+//                   https://www.bosch-sensortec.com/media/boschsensortec/downloads/datasheets/bst-bme688-ds000.pdf
+//
+//  Plain I2C registers at 0x76 or 0x77. Every value comes out as an ADC count
+//  that only means something with the sensor's own calibration, read once from
+//  its memory; the compensation below is the floating point version from
+//  Bosch's BME68x driver. The gas sensor is a hot plate whose resistance drops
+//  with VOCs about -- it's reported as measured, in ohms. Turning it into an
+//  air quality index takes Bosch's closed-source BSEC library, which airq
+//  doesn't use.
+
+pub const I2C_ADDRESSES: [u16; 2] = [0x76, 0x77];
+
+pub const CHIP_ID: u8 = 0x61;
+
+pub const REG_CHIP_ID: u8 = 0xD0;
+pub const REG_RESET: u8 = 0xE0;
+pub const REG_VARIANT_ID: u8 = 0xF0;
+pub const REG_CTRL_GAS_1: u8 = 0x71;
+pub const REG_CTRL_HUM: u8 = 0x72;
+pub const REG_CTRL_MEAS: u8 = 0x74;
+pub const REG_RES_HEAT_0: u8 = 0x5A;
+pub const REG_GAS_WAIT_0: u8 = 0x64;
+pub const REG_FIELD_0: u8 = 0x1D;
+
+pub const RESET: u8 = 0xB6;
+
+// the calibration, in three blocks
+pub const COEFF_1: (u8, usize) = (0x8A, 23);
+pub const COEFF_2: (u8, usize) = (0xE1, 14);
+pub const COEFF_3: (u8, usize) = (0x00, 5);
+
+// status through the gas resistance of the BME688, which is two bytes further
+//  on than the BME680's
+pub const FIELD_SIZE: usize = 17;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    // the BME680's gas sensor, tuned for low resistances
+    Bme680,
+    Bme688,
+}
+
+impl Variant {
+    pub fn from_id(id: u8) -> Self {
+        if id == 0 { Variant::Bme680 } else { Variant::Bme688 }
+    }
+
+    // heater set point 0, on
+    pub fn run_gas(self) -> u8 {
+        match self {
+            Variant::Bme680 => 0x10,
+            Variant::Bme688 => 0x20,
+        }
+    }
+}
+
+// oversampling x1 for temperature, pressure and humidity
+pub const CTRL_HUM: u8 = 0b001;
+pub const CTRL_MEAS_FORCED: u8 = 0b001 << 5 | 0b001 << 2 | 0b01;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct Calibration {
+    pub t1: u16,
+    pub t2: i16,
+    pub t3: i8,
+    pub p1: u16,
+    pub p2: i16,
+    pub p3: i8,
+    pub p4: i16,
+    pub p5: i16,
+    pub p6: i8,
+    pub p7: i8,
+    pub p8: i16,
+    pub p9: i16,
+    pub p10: u8,
+    pub h1: u16,
+    pub h2: u16,
+    pub h3: i8,
+    pub h4: i8,
+    pub h5: i8,
+    pub h6: u8,
+    pub h7: i8,
+    pub gh1: i8,
+    pub gh2: i16,
+    pub gh3: i8,
+    pub res_heat_range: u8,
+    pub res_heat_val: i8,
+    pub range_sw_err: i8,
+}
+
+impl Calibration {
+    // from the COEFF_1, COEFF_2 and COEFF_3 blocks
+    pub fn parse(c1: &[u8; COEFF_1.1], c2: &[u8; COEFF_2.1], c3: &[u8; COEFF_3.1]) -> Self {
+        let u16_at = |b: &[u8], i: usize| u16::from_le_bytes([b[i], b[i + 1]]);
+        let i16_at = |b: &[u8], i: usize| i16::from_le_bytes([b[i], b[i + 1]]);
+
+        Calibration {
+            t1: u16_at(c2, 8),
+            t2: i16_at(c1, 0),
+            t3: c1[2] as i8,
+            p1: u16_at(c1, 4),
+            p2: i16_at(c1, 6),
+            p3: c1[8] as i8,
+            p4: i16_at(c1, 10),
+            p5: i16_at(c1, 12),
+            p7: c1[14] as i8,
+            p6: c1[15] as i8,
+            p8: i16_at(c1, 18),
+            p9: i16_at(c1, 20),
+            p10: c1[22],
+            // 12-bit values sharing a byte
+            h1: (c2[2] as u16) << 4 | (c2[1] & 0x0F) as u16,
+            h2: (c2[0] as u16) << 4 | (c2[1] >> 4) as u16,
+            h3: c2[3] as i8,
+            h4: c2[4] as i8,
+            h5: c2[5] as i8,
+            h6: c2[6],
+            h7: c2[7] as i8,
+            gh2: i16_at(c2, 10),
+            gh1: c2[12] as i8,
+            gh3: c2[13] as i8,
+            res_heat_val: c3[0] as i8,
+            res_heat_range: (c3[2] & 0x30) >> 4,
+            range_sw_err: ((c3[4] & 0xF0) as i8) / 16,
+        }
+    }
+
+    // degC, and the fine temperature the other values are compensated with
+    pub fn temperature(&self, adc: u32) -> (f64, f64) {
+        let (adc, t1) = (adc as f64, self.t1 as f64);
+        let var1 = (adc / 16384.0 - t1 / 1024.0) * self.t2 as f64;
+        let var2 = (adc / 131072.0 - t1 / 8192.0).powi(2) * (self.t3 as f64 * 16.0);
+        let t_fine = var1 + var2;
+
+        (t_fine / 5120.0, t_fine)
+    }
+
+    // hPa
+    pub fn pressure(&self, adc: u32, t_fine: f64) -> f64 {
+        let mut var1 = t_fine / 2.0 - 64000.0;
+        let mut var2 = var1 * var1 * (self.p6 as f64 / 131072.0);
+        var2 += var1 * self.p5 as f64 * 2.0;
+        var2 = var2 / 4.0 + self.p4 as f64 * 65536.0;
+        var1 = (self.p3 as f64 * var1 * var1 / 16384.0 + self.p2 as f64 * var1) / 524288.0;
+        var1 = (1.0 + var1 / 32768.0) * self.p1 as f64;
+        if var1 as i64 == 0 {
+            return 0.0;
+        }
+
+        let mut p = 1048576.0 - adc as f64;
+        p = (p - var2 / 4096.0) * 6250.0 / var1;
+        let var1 = self.p9 as f64 * p * p / 2147483648.0;
+        let var2 = p * (self.p8 as f64 / 32768.0);
+        let var3 = (p / 256.0).powi(3) * (self.p10 as f64 / 131072.0);
+        p += (var1 + var2 + var3 + self.p7 as f64 * 128.0) / 16.0;
+
+        p / 100.0
+    }
+
+    // %
+    pub fn humidity(&self, adc: u16, t_fine: f64) -> f64 {
+        let t = t_fine / 5120.0;
+        let var1 = adc as f64 - (self.h1 as f64 * 16.0 + self.h3 as f64 / 2.0 * t);
+        let var2 = var1 * (self.h2 as f64 / 262144.0
+            * (1.0 + self.h4 as f64 / 16384.0 * t + self.h5 as f64 / 1048576.0 * t * t));
+        let var3 = self.h6 as f64 / 16384.0;
+        let var4 = self.h7 as f64 / 2097152.0;
+
+        (var2 + (var3 + var4 * t) * var2 * var2).clamp(0.0, 100.0)
+    }
+
+    // ohms
+    pub fn gas_resistance(&self, variant: Variant, adc: u16, range: u8) -> f64 {
+        match variant {
+            Variant::Bme680 => {
+                const K1: [f64; 16] = [0.0, 0.0, 0.0, 0.0, 0.0, -1.0, 0.0, -0.8, 0.0, 0.0, -0.2, -0.5, 0.0, -1.0, 0.0, 0.0];
+                const K2: [f64; 16] = [0.0, 0.0, 0.0, 0.0, 0.1, 0.7, 0.0, -0.8, -0.1, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+                let range = range as usize & 0x0F;
+                let var1 = 1340.0 + 5.0 * self.range_sw_err as f64;
+                let var2 = var1 * (1.0 + K1[range] / 100.0);
+                let var3 = 1.0 + K2[range] / 100.0;
+                1.0 / (var3 * 0.000000125 * (1u32 << range) as f64 * ((adc as f64 - 512.0) / var2 + 1.0))
+            },
+            Variant::Bme688 => {
+                let var1 = (262144u32 >> (range & 0x0F)) as f64;
+                let var2 = 4096.0 + (adc as f64 - 512.0) * 3.0;
+                1000000.0 * var1 / var2
+            },
+        }
+    }
+
+    // the RES_HEAT_0 setting to heat the plate to `target` degC with the air
+    //  at `ambient` degC
+    pub fn heater_resistance(&self, target: u16, ambient: f64) -> u8 {
+        let target = target.min(400) as f64;
+        let var1 = self.gh1 as f64 / 16.0 + 49.0;
+        let var2 = self.gh2 as f64 / 32768.0 * 0.0005 + 0.00235;
+        let var3 = self.gh3 as f64 / 1024.0;
+        let var4 = var1 * (1.0 + var2 * target);
+        let var5 = var4 + var3 * ambient;
+
+        (3.4 * (var5 * (4.0 / (4.0 + self.res_heat_range as f64)) * (1.0 / (1.0 + self.res_heat_val as f64 * 0.002)) - 25.0)) as u8
+    }
+}
+
+// the GAS_WAIT_0 setting for heating `ms` milliseconds: six bits of time and a
+//  multiplier of 1, 4, 16 or 64
+pub fn gas_wait(ms: u16) -> u8 {
+    if ms >= 0xFC0 {
+        return 0xFF;
+    }
+
+    let (mut ms, mut factor) = (ms, 0);
+    while ms > 0x3F {
+        ms /= 4;
+        factor += 1;
+    }
+    ms as u8 + factor * 64
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct Bme680Reading {
+    // degC
+    pub temperature: f64,
+    // %
+    pub humidity: f64,
+    // hPa
+    pub pressure: f64,
+    // ohms; None if the plate didn't reach its temperature in time
+    pub gas_resistance: Option<f64>,
+}
+
+// the raw counts of one measurement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Field {
+    pub new_data: bool,
+    pub pressure: u32,
+    pub temperature: u32,
+    pub humidity: u16,
+    pub gas: u16,
+    pub gas_range: u8,
+    // the measurement ran and the heater was at its temperature
+    pub gas_valid: bool,
+}
+
+impl Field {
+    // FIELD_SIZE bytes from REG_FIELD_0
+    pub fn parse(b: &[u8; FIELD_SIZE], variant: Variant) -> Self {
+        let adc20 = |i: usize| (b[i] as u32) << 12 | (b[i + 1] as u32) << 4 | (b[i + 2] as u32) >> 4;
+        let g = match variant {
+            Variant::Bme680 => 13,
+            Variant::Bme688 => 15,
+        };
+
+        Field {
+            new_data: b[0] & 0x80 != 0,
+            pressure: adc20(2),
+            temperature: adc20(5),
+            humidity: u16::from_be_bytes([b[8], b[9]]),
+            gas: (b[g] as u16) << 2 | (b[g + 1] >> 6) as u16,
+            gas_range: b[g + 1] & 0x0F,
+            gas_valid: b[g + 1] & 0x30 == 0x30,
+        }
+    }
+
+    pub fn reading(&self, calibration: &Calibration, variant: Variant) -> Bme680Reading {
+        let (temperature, t_fine) = calibration.temperature(self.temperature);
+
+        Bme680Reading {
+            temperature,
+            humidity: calibration.humidity(self.humidity, t_fine),
+            pressure: calibration.pressure(self.pressure, t_fine),
+            gas_resistance: self.gas_valid.then(|| calibration.gas_resistance(variant, self.gas, self.gas_range)),
+        }
+    }
+}
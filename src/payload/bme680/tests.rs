@@ -0,0 +1,93 @@
+#[cfg(test)]
+mod bme680_tests {
+    use crate::payload::bme680::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6 * b.abs().max(1.0)
+    }
+
+    #[test]
+    fn calibration_layout() {
+        let mut c1 = [0u8; COEFF_1.1];
+        let mut c2 = [0u8; COEFF_2.1];
+        let mut c3 = [0u8; COEFF_3.1];
+        // T2 at 0x8A, little endian and signed
+        c1[0..2].copy_from_slice(&(-300i16).to_le_bytes());
+        c1[22] = 30;
+        // H1 and H2 share 0xE2
+        c2[0] = 0x3F;
+        c2[1] = 0x2A;
+        c2[2] = 0x2C;
+        // T1 at 0xE9
+        c2[8..10].copy_from_slice(&26000u16.to_le_bytes());
+        c3[2] = 0x10;
+        c3[4] = 0xF0;
+
+        let c = Calibration::parse(&c1, &c2, &c3);
+        assert_eq!(c.t2, -300);
+        assert_eq!(c.p10, 30);
+        assert_eq!(c.t1, 26000);
+        assert_eq!(c.h1, 0x2CA);
+        assert_eq!(c.h2, 0x3F2);
+        assert_eq!(c.res_heat_range, 1);
+        assert_eq!(c.range_sw_err, -1);
+    }
+
+    #[test]
+    fn temperature() {
+        // linear with T2 alone
+        let c = Calibration { t2: 5120, ..Default::default() };
+        let (t, t_fine) = c.temperature(16384 * 25);
+        assert!(close(t, 25.0));
+        assert!(close(t_fine, 25.0 * 5120.0));
+
+        // and 0 degC where the ADC count matches T1
+        let c = Calibration { t1: 25000, t2: 26000, t3: 3, ..Default::default() };
+        assert!(close(c.temperature(25000 * 16).0, 0.0));
+    }
+
+    #[test]
+    fn gas_resistance() {
+        let c = Calibration::default();
+        assert!(close(c.gas_resistance(Variant::Bme688, 512, 0), 64e6));
+        assert!(close(c.gas_resistance(Variant::Bme688, 512, 4), 4e6));
+        assert!(close(c.gas_resistance(Variant::Bme680, 512, 0), 8e6));
+        // higher counts are lower resistances
+        assert!(c.gas_resistance(Variant::Bme680, 800, 5) < c.gas_resistance(Variant::Bme680, 600, 5));
+    }
+
+    #[test]
+    fn heater_settings() {
+        assert_eq!(gas_wait(63), 63);
+        assert_eq!(gas_wait(100), 0x59);
+        assert_eq!(gas_wait(150), 0x65);
+        assert_eq!(gas_wait(5000), 0xFF);
+
+        let c = Calibration { gh1: -30, gh2: -5000, gh3: 18, res_heat_range: 1, res_heat_val: 40, ..Default::default() };
+        assert!(c.heater_resistance(320, 25.0) > c.heater_resistance(200, 25.0));
+        assert_eq!(c.heater_resistance(500, 25.0), c.heater_resistance(400, 25.0));
+    }
+
+    #[test]
+    fn field() {
+        let mut b = [0u8; FIELD_SIZE];
+        b[0] = 0x80;
+        b[2..5].copy_from_slice(&[0x12, 0x34, 0x50]);
+        b[5..8].copy_from_slice(&[0x65, 0x43, 0x20]);
+        b[8..10].copy_from_slice(&[0x5A, 0x5B]);
+        // BME680 gas: 0x2A and 0x2B, valid and stable in range 3
+        b[13..15].copy_from_slice(&[0x81, 0x73]);
+
+        let f = Field::parse(&b, Variant::Bme680);
+        assert!(f.new_data);
+        assert_eq!(f.pressure, 0x12345);
+        assert_eq!(f.temperature, 0x65432);
+        assert_eq!(f.humidity, 0x5A5B);
+        assert_eq!((f.gas, f.gas_range, f.gas_valid), (0x205, 3, true));
+
+        // the BME688's gas registers are still zero
+        let f = Field::parse(&b, Variant::Bme688);
+        assert_eq!((f.gas, f.gas_valid), (0, false));
+        assert_eq!(f.reading(&Calibration::default(), Variant::Bme688).gas_resistance, None);
+    }
+}
@@ -0,0 +1,58 @@
+#[cfg(test)]
+mod tests;
+
+use std::fmt;
+
+// reading CO2 from the Winsen MH-Z19 (B and C)
+//  see datasheet:  https://www.winsen-sensor.com/d/files/infrared-gas-sensor/mh-z19b-co2-ver1_0.pdf
+//
+//  9600 baud 8N1, 9 byte frames: 0xFF, then for a command the sensor number
+//  (always 1) and the command, for a response the command; data; and a
+//  checksum -- the negated sum of bytes 1..7. Command 0x86 reads CO2 in ppm
+//  from the first two data bytes of the response.
+
+pub const BAUD: u32 = 9600;
+pub const FRAME_SIZE: usize = 9;
+
+const START: u8 = 0xFF;
+const READ_CO2: u8 = 0x86;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mhz19Error {
+    Checksum,
+    // not the start byte, or a response to some other command
+    Unexpected,
+}
+
+impl fmt::Display for Mhz19Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mhz19Error::Checksum => write!(f, "MH-Z19 checksum mismatch"),
+            Mhz19Error::Unexpected => write!(f, "unexpected MH-Z19 response"),
+        }
+    }
+}
+
+impl std::error::Error for Mhz19Error {}
+
+fn checksum(frame: &[u8; FRAME_SIZE]) -> u8 {
+    frame[1..8].iter().fold(0u8, |sum, &b| sum.wrapping_add(b)).wrapping_neg()
+}
+
+pub fn co2_request() -> [u8; FRAME_SIZE] {
+    let mut frame = [START, 0x01, READ_CO2, 0, 0, 0, 0, 0, 0];
+    frame[8] = checksum(&frame);
+    frame
+}
+
+// ppm
+pub fn co2_response(frame: &[u8; FRAME_SIZE]) -> Result<u16, Mhz19Error> {
+    if frame[0] != START || frame[1] != READ_CO2 {
+        return Err(Mhz19Error::Unexpected);
+    }
+    if checksum(frame) != frame[8] {
+        return Err(Mhz19Error::Checksum);
+    }
+
+    Ok(u16::from_be_bytes([frame[2], frame[3]]))
+}
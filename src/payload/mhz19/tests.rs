@@ -0,0 +1,25 @@
+extern crate hex_literal;
+
+#[cfg(test)]
+mod mhz19_tests {
+    use crate::payload::mhz19::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn request_from_the_datasheet() {
+        assert_eq!(co2_request(), hex!("ff 01 86 00 00 00 00 00 79"));
+    }
+
+    #[test]
+    fn reads_co2() {
+        // 0x0320 = 800 ppm, then the temperature and status bytes
+        assert_eq!(co2_response(&hex!("ff 86 03 20 47 00 00 00 10")), Ok(800));
+    }
+
+    #[test]
+    fn rejects_bad_responses() {
+        assert_eq!(co2_response(&hex!("ff 86 03 20 47 00 00 00 11")), Err(Mhz19Error::Checksum));
+        assert_eq!(co2_response(&co2_request()), Err(Mhz19Error::Unexpected));
+        assert_eq!(co2_response(&hex!("00 ff 86 03 20 47 00 00 00")), Err(Mhz19Error::Unexpected));
+    }
+}
@@ -0,0 +1,69 @@
+#[cfg(test)]
+mod tests;
+
+use crate::payload::sensirion;
+
+// reading from the Sensirion SCD30 CO2, temperature and humidity sensor
+//  see:  https://sensirion.com/media/documents/D7CEEF4A/6165372F/Sensirion_CO2_Sensors_SCD30_Interface_Description.pdf
+//
+//  I2C at 0x61 in the common Sensirion framing. A reading is three big endian
+//  floats: CO2 in ppm, temperature in degC and relative humidity in %. The
+//  sensor needs a few ms between a command and reading its response, and
+//  stretches the clock while it's busy.
+
+pub const I2C_ADDRESS: u16 = 0x61;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct Scd30Reading {
+    pub co2: f64,
+    pub temperature: f64,
+    pub humidity: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    // with the ambient pressure in mbar, or 0 to leave it uncompensated
+    StartContinuous(u16),
+    // 2..1800 s
+    MeasurementInterval(u16),
+    DataReady,
+    ReadMeasurement,
+}
+
+impl Command {
+    pub fn encode(self) -> Vec<u8> {
+        match self {
+            Command::StartContinuous(mbar) => sensirion::command(0x0010, &[mbar]),
+            Command::MeasurementInterval(secs) => sensirion::command(0x4600, &[secs]),
+            Command::DataReady => sensirion::command(0x0202, &[]),
+            Command::ReadMeasurement => sensirion::command(0x0300, &[]),
+        }
+    }
+
+    // words in the response
+    pub fn response_words(self) -> usize {
+        match self {
+            Command::DataReady => 1,
+            Command::ReadMeasurement => 6,
+            _ => 0,
+        }
+    }
+}
+
+pub fn data_ready(words: &[u16]) -> bool {
+    words == [1]
+}
+
+// None unless given all six words
+pub fn reading(words: &[u16]) -> Option<Scd30Reading> {
+    let [co2_hi, co2_lo, t_hi, t_lo, rh_hi, rh_lo] = *words else {
+        return None;
+    };
+    let float = |hi: u16, lo: u16| f32::from_bits((hi as u32) << 16 | lo as u32) as f64;
+
+    Some(Scd30Reading {
+        co2: float(co2_hi, co2_lo),
+        temperature: float(t_hi, t_lo),
+        humidity: float(rh_hi, rh_lo),
+    })
+}
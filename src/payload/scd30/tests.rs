@@ -0,0 +1,30 @@
+#[cfg(test)]
+mod scd30_tests {
+    use crate::payload::scd30::*;
+
+    fn words(values: [f32; 3]) -> Vec<u16> {
+        values.iter().flat_map(|v| [(v.to_bits() >> 16) as u16, v.to_bits() as u16]).collect()
+    }
+
+    #[test]
+    fn commands() {
+        assert_eq!(Command::ReadMeasurement.encode(), [0x03, 0x00]);
+        // the example from the interface description: start with no pressure compensation
+        assert_eq!(Command::StartContinuous(0).encode(), [0x00, 0x10, 0x00, 0x00, 0x81]);
+        assert_eq!(Command::MeasurementInterval(2).encode(), [0x46, 0x00, 0x00, 0x02, 0xE3]);
+    }
+
+    #[test]
+    fn reads_floats() {
+        let r = reading(&words([439.0, 27.25, 48.5])).unwrap();
+        assert_eq!(r, Scd30Reading { co2: 439.0, temperature: 27.25, humidity: 48.5 });
+        assert!(reading(&words([439.0, 27.25, 48.5])[..4]).is_none());
+    }
+
+    #[test]
+    fn data_ready_flag() {
+        assert!(data_ready(&[1]));
+        assert!(!data_ready(&[0]));
+        assert!(!data_ready(&[]));
+    }
+}
@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod tests;
+
+use crate::payload::sensirion;
+
+// reading from the Sensirion SCD40 / SCD41 CO2, temperature and humidity sensors
+//  see datasheet:  https://sensirion.com/media/documents/48C4B7FB/64C134E7/Sensirion_SCD4x_Datasheet.pdf
+//
+//  I2C at 0x62 in the common Sensirion framing. In periodic mode there is a
+//  new reading every 5 s: CO2 in ppm as is, temperature and relative humidity
+//  scaled to the full 16 bits.
+
+pub const I2C_ADDRESS: u16 = 0x62;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct Scd4xReading {
+    pub co2: f64,
+    pub temperature: f64,
+    pub humidity: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    StartPeriodic,
+    // takes 500 ms, and everything but starting again is refused until then
+    StopPeriodic,
+    DataReady,
+    ReadMeasurement,
+}
+
+impl Command {
+    pub fn encode(self) -> Vec<u8> {
+        let code = match self {
+            Command::StartPeriodic => 0x21B1,
+            Command::StopPeriodic => 0x3F86,
+            Command::DataReady => 0xE4B8,
+            Command::ReadMeasurement => 0xEC05,
+        };
+        sensirion::command(code, &[])
+    }
+
+    pub fn response_words(self) -> usize {
+        match self {
+            Command::DataReady => 1,
+            Command::ReadMeasurement => 3,
+            _ => 0,
+        }
+    }
+}
+
+// any of the low 11 bits set
+pub fn data_ready(words: &[u16]) -> bool {
+    words.first().is_some_and(|w| w & 0x07FF != 0)
+}
+
+// None unless given all three words
+pub fn reading(words: &[u16]) -> Option<Scd4xReading> {
+    let [co2, t, rh] = *words else {
+        return None;
+    };
+
+    Some(Scd4xReading {
+        co2: co2 as f64,
+        temperature: -45.0 + 175.0 * t as f64 / 65535.0,
+        humidity: 100.0 * rh as f64 / 65535.0,
+    })
+}
@@ -0,0 +1,28 @@
+#[cfg(test)]
+mod scd4x_tests {
+    use crate::payload::scd4x::*;
+
+    #[test]
+    fn commands() {
+        assert_eq!(Command::StartPeriodic.encode(), [0x21, 0xB1]);
+        assert_eq!(Command::ReadMeasurement.encode(), [0xEC, 0x05]);
+        assert_eq!(Command::ReadMeasurement.response_words(), 3);
+    }
+
+    // the example from the datasheet: 500 ppm, 25 degC, 37 %RH
+    #[test]
+    fn scales_the_datasheet_example() {
+        let r = reading(&[0x01F4, 0x6667, 0x5EB9]).unwrap();
+        assert_eq!(r.co2, 500.0);
+        assert!((r.temperature - 25.0).abs() < 0.01);
+        assert!((r.humidity - 37.0).abs() < 0.01);
+        assert!(reading(&[0x01F4]).is_none());
+    }
+
+    #[test]
+    fn data_ready_flag() {
+        assert!(data_ready(&[0x8006]));
+        assert!(!data_ready(&[0x8000]));
+        assert!(!data_ready(&[]));
+    }
+}
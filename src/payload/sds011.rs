@@ -0,0 +1,130 @@
+#[cfg(test)]
+mod tests;
+
+use nom::{
+    Err,
+    IResult,
+    bytes::streaming::{tag, take},
+    number::streaming::{le_u16, u8 as byte},
+};
+
+use crate::payload::FrameFormat;
+
+// reading from the Nova Fitness SDS011 (and SDS021) particle sensor
+//  see datasheet:  https://cdn-reichelt.de/documents/datenblatt/X200/SDS011-DATASHEET.pdf
+//  and the laser dust sensor control protocol V1.3
+//
+//  10 byte frames: 0xAA, a command byte, 6 data bytes, the low byte of the sum
+//  of the data bytes, 0xAB. Readings are command 0xC0, with PM2.5 and PM10 in
+//  tenths of ug/m^3, little endian, then the device id. Replies to our own
+//  commands are command 0xC5.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct Sds011Reading {
+    // ug/m^3
+    pub pm2_5: f64,
+    pub pm10: f64,
+    pub device_id: u16,
+}
+
+pub const FRAME_HEAD: u8 = 0xAA;
+pub const FRAME_TAIL: u8 = 0xAB;
+pub const FRAME_SIZE: usize = 10;
+
+const DATA: u8 = 0xC0;
+const REPLY: u8 = 0xC5;
+
+// what a well-formed frame turned out to be
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Frame {
+    Reading(Sds011Reading),
+    Reply,
+}
+
+// parses exactly one frame that starts at the beginning of the input
+//  streaming: a truncated frame is Err::Incomplete, never a panic
+fn frame_parser(input: &[u8]) -> IResult<&[u8], Frame> {
+    let (body, _) = tag([FRAME_HEAD])(input)?;
+    let (body, command) = byte(body)?;
+    if command != DATA && command != REPLY {
+        return Err(Err::Error(nom::error::Error { input: body, code: nom::error::ErrorKind::Tag }));
+    }
+
+    let (body, data) = take(6usize)(body)?;
+    let (body, check) = byte(body)?;
+    let (body, _) = tag([FRAME_TAIL])(body)?;
+    if checksum(data) != check {
+        return Err(Err::Error(nom::error::Error { input: body, code: nom::error::ErrorKind::Fail }));
+    }
+    if command == REPLY {
+        return Ok((body, Frame::Reply));
+    }
+
+    let (data, pm2_5) = le_u16(data)?;
+    let (data, pm10) = le_u16(data)?;
+    let (_, device_id) = le_u16(data)?;
+
+    Ok((body, Frame::Reading(Sds011Reading {
+        pm2_5: pm2_5 as f64 / 10.0,
+        pm10: pm10 as f64 / 10.0,
+        device_id,
+    })))
+}
+
+fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, &b| sum.wrapping_add(b))
+}
+
+// for payload::FrameDecoder: readings come out, replies are dropped, and
+//  every frame is the same size, so there are never any length errors
+#[derive(Debug)]
+pub struct Sds011Format;
+
+impl FrameFormat for Sds011Format {
+    type Frame = Sds011Reading;
+
+    const HEAD: u8 = FRAME_HEAD;
+    const SIZE: usize = FRAME_SIZE;
+
+    fn parse(input: &[u8]) -> IResult<&[u8], Option<Sds011Reading>> {
+        frame_parser(input).map(|(rest, frame)| match frame {
+            Frame::Reading(r) => (rest, Some(r)),
+            Frame::Reply => (rest, None),
+        })
+    }
+}
+
+// Host commands
+//
+//  19 bytes: 0xAA 0xB4, the command and 12 data bytes, the device id (0xFFFF
+//  for whichever sensor is listening), the low byte of the sum of everything
+//  after 0xB4, 0xAB. All of these set rather than query, and the sensor
+//  replies with a 0xC5 frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    // readings every second, or every working period
+    ActiveMode,
+    // wake from a sleep a previous run may have left it in
+    Work,
+    // 0 reports continuously; 1..30 wakes the sensor for 30 s every n minutes
+    WorkingPeriod(u8),
+}
+
+pub const COMMAND_SIZE: usize = 19;
+
+impl Command {
+    pub fn encode(self) -> [u8; COMMAND_SIZE] {
+        let (command, data): (u8, [u8; 2]) = match self {
+            Command::ActiveMode => (0x02, [1, 0]),
+            Command::Work => (0x06, [1, 1]),
+            Command::WorkingPeriod(minutes) => (0x08, [1, minutes]),
+        };
+
+        let mut bytes = [0; COMMAND_SIZE];
+        bytes[..5].copy_from_slice(&[FRAME_HEAD, 0xB4, command, data[0], data[1]]);
+        bytes[15..17].copy_from_slice(&[0xFF, 0xFF]);
+        bytes[17] = checksum(&bytes[2..17]);
+        bytes[18] = FRAME_TAIL;
+
+        bytes
+    }
+}
@@ -0,0 +1,70 @@
+extern crate hex_literal;
+
+#[cfg(test)]
+mod sds011_tests {
+    use crate::payload::sds011::*;
+    use crate::payload::FrameDecoder;
+    use hex_literal::hex;
+
+    // the example from the datasheet: PM2.5 123.6, PM10 261.8
+    const ONE_GOOD_FRAME: [u8; FRAME_SIZE] = hex!("aa c0 d4 04 3a 0a a1 60 1d ab");
+
+    // the sensor's reply to a sleep command
+    const REPLY: [u8; FRAME_SIZE] = hex!("aa c5 06 01 00 00 a1 60 08 ab");
+
+    #[test]
+    fn decodes_the_datasheet_frame() {
+        let mut d = FrameDecoder::<Sds011Format>::new();
+        d.push(&ONE_GOOD_FRAME);
+
+        let r = d.next_frame().unwrap();
+        assert!((r.pm2_5 - 123.6).abs() < 1e-9);
+        assert!((r.pm10 - 261.8).abs() < 1e-9);
+        assert_eq!(r.device_id, 0x60a1);
+        assert!(d.next_frame().is_none());
+    }
+
+    #[test]
+    fn drops_replies_quietly() {
+        let mut d = FrameDecoder::<Sds011Format>::new();
+        d.push(&REPLY);
+        d.push(&ONE_GOOD_FRAME);
+
+        assert!(d.next_frame().is_some());
+        assert_eq!(d.stats().frames, 1);
+        assert_eq!(d.stats().resync_bytes, 0);
+    }
+
+    #[test]
+    fn reassembles_frames_split_across_reads() {
+        let mut d = FrameDecoder::<Sds011Format>::new();
+        d.push(&ONE_GOOD_FRAME[..4]);
+        assert!(d.next_frame().is_none());
+
+        d.push(&ONE_GOOD_FRAME[4..]);
+        assert!(d.next_frame().is_some());
+    }
+
+    #[test]
+    fn resyncs_and_counts_bad_frames() {
+        let mut bad = ONE_GOOD_FRAME;
+        bad[3] ^= 0x55;
+
+        let mut d = FrameDecoder::<Sds011Format>::new();
+        d.push(b"noise");
+        d.push(&bad);
+        d.push(&ONE_GOOD_FRAME);
+
+        assert!(d.next_frame().is_some());
+        assert!(d.next_frame().is_none());
+        assert_eq!(d.stats().checksum_errors, 1);
+        assert_eq!(d.stats().resync_bytes, 5 + FRAME_SIZE as u32);
+    }
+
+    #[test]
+    fn commands_match_the_protocol() {
+        assert_eq!(Command::Work.encode(), hex!("aa b4 06 01 01 00 00 00 00 00 00 00 00 00 00 ff ff 06 ab"));
+        assert_eq!(Command::ActiveMode.encode(), hex!("aa b4 02 01 00 00 00 00 00 00 00 00 00 00 00 ff ff 01 ab"));
+        assert_eq!(Command::WorkingPeriod(1).encode(), hex!("aa b4 08 01 01 00 00 00 00 00 00 00 00 00 00 ff ff 08 ab"));
+    }
+}
@@ -0,0 +1,80 @@
+#[cfg(test)]
+mod tests;
+
+use std::fmt;
+
+use crate::modbus_crc::crc16;
+
+// reading CO2 from the Senseair S8 over Modbus-RTU
+//  see:  https://rmtplusstoragesenseair.blob.core.windows.net/docs/Dev/publicerat/TDE2067.pdf
+//
+//  9600 baud 8N1. The sensor answers to address 0xFE whatever its own address
+//  is; CO2 in ppm is input register IR4, at 0x0003. Frames end in the usual
+//  Modbus CRC-16, low byte first.
+
+pub const BAUD: u32 = 9600;
+pub const ADDRESS: u8 = 0xFE;
+
+const READ_INPUT_REGISTERS: u8 = 0x04;
+const CO2_REGISTER: u16 = 0x0003;
+
+pub const REQUEST_SIZE: usize = 8;
+// address, function, byte count, one register, CRC
+pub const RESPONSE_SIZE: usize = 7;
+// address, function | 0x80, exception code, CRC
+const EXCEPTION_SIZE: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum S8Error {
+    Crc,
+    // a Modbus exception code
+    Exception(u8),
+    // some other address, function or byte count
+    Unexpected,
+}
+
+impl fmt::Display for S8Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            S8Error::Crc => write!(f, "Modbus CRC mismatch"),
+            S8Error::Exception(e) => write!(f, "Modbus exception {}", e),
+            S8Error::Unexpected => write!(f, "unexpected Modbus response"),
+        }
+    }
+}
+
+impl std::error::Error for S8Error {}
+
+pub fn co2_request() -> [u8; REQUEST_SIZE] {
+    let mut bytes = [0; REQUEST_SIZE];
+    bytes[..2].copy_from_slice(&[ADDRESS, READ_INPUT_REGISTERS]);
+    bytes[2..4].copy_from_slice(&CO2_REGISTER.to_be_bytes());
+    bytes[4..6].copy_from_slice(&1u16.to_be_bytes());
+    let crc = crc16(&bytes[..6]);
+    bytes[6..].copy_from_slice(&crc.to_le_bytes());
+
+    bytes
+}
+
+// ppm, from a whole response -- or the first EXCEPTION_SIZE bytes of one, for
+//  an exception
+pub fn co2_response(frame: &[u8]) -> Result<u16, S8Error> {
+    let size = match frame {
+        [ADDRESS, f, ..] if *f == READ_INPUT_REGISTERS | 0x80 => EXCEPTION_SIZE,
+        _ => RESPONSE_SIZE,
+    };
+    if frame.len() < size {
+        return Err(S8Error::Unexpected);
+    }
+
+    let (body, crc) = frame[..size].split_at(size - 2);
+    if crc16(body).to_le_bytes() != crc {
+        return Err(S8Error::Crc);
+    }
+
+    match body {
+        [ADDRESS, f, code] if *f == READ_INPUT_REGISTERS | 0x80 => Err(S8Error::Exception(*code)),
+        [ADDRESS, READ_INPUT_REGISTERS, 2, hi, lo] => Ok(u16::from_be_bytes([*hi, *lo])),
+        _ => Err(S8Error::Unexpected),
+    }
+}
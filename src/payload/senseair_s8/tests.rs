@@ -0,0 +1,37 @@
+extern crate hex_literal;
+
+#[cfg(test)]
+mod senseair_s8_tests {
+    use crate::payload::senseair_s8::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn request_from_the_manual() {
+        assert_eq!(co2_request(), hex!("fe 04 00 03 00 01 d5 c5"));
+    }
+
+    #[test]
+    fn reads_co2() {
+        let mut frame = hex!("fe 04 02 01 90 00 00").to_vec();
+        let crc = crc16(&frame[..5]).to_le_bytes();
+        frame[5..].copy_from_slice(&crc);
+
+        assert_eq!(co2_response(&frame), Ok(400));
+    }
+
+    #[test]
+    fn rejects_bad_responses() {
+        let mut frame = co2_request().to_vec();
+        assert_eq!(co2_response(&frame[..RESPONSE_SIZE]), Err(S8Error::Crc));
+
+        frame = hex!("fe 04 02 01 90 00 00").to_vec();
+        assert_eq!(co2_response(&frame), Err(S8Error::Crc));
+        assert_eq!(co2_response(&frame[..4]), Err(S8Error::Unexpected));
+
+        // illegal data address
+        let mut exception = hex!("fe 84 02 00 00").to_vec();
+        let crc = crc16(&exception[..3]).to_le_bytes();
+        exception[3..].copy_from_slice(&crc);
+        assert_eq!(co2_response(&exception), Err(S8Error::Exception(2)));
+    }
+}
@@ -0,0 +1,72 @@
+#[cfg(test)]
+mod tests;
+
+use std::fmt;
+
+// Sensirion I2C framing, shared by the SPS30, SCD30, SCD4x and SGP4x
+//
+//  A 16-bit command, then any arguments as 16-bit words each followed by a
+//  CRC-8 (polynomial 0x31, init 0xFF). Responses are read separately, in the
+//  same word-and-CRC layout.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum I2cError {
+    Crc,
+    // not a whole number of words
+    Length(usize),
+}
+
+impl fmt::Display for I2cError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            I2cError::Crc => write!(f, "I2C CRC mismatch"),
+            I2cError::Length(n) => write!(f, "unexpected I2C response length {}", n),
+        }
+    }
+}
+
+impl std::error::Error for I2cError {}
+
+pub fn crc8(data: &[u8]) -> u8 {
+    data.iter().fold(0xFF, |crc, &b| {
+        (0..8).fold(crc ^ b, |c, _| if c & 0x80 != 0 { (c << 1) ^ 0x31 } else { c << 1 })
+    })
+}
+
+pub fn command(code: u16, args: &[u16]) -> Vec<u8> {
+    let mut bytes = code.to_be_bytes().to_vec();
+    for arg in args {
+        let word = arg.to_be_bytes();
+        bytes.extend_from_slice(&word);
+        bytes.push(crc8(&word));
+    }
+
+    bytes
+}
+
+// bytes to read back for a response of n words
+pub const fn response_size(words: usize) -> usize {
+    words * 3
+}
+
+// the data bytes of a response, with every word's CRC checked
+pub fn data(raw: &[u8]) -> Result<Vec<u8>, I2cError> {
+    if !raw.len().is_multiple_of(3) {
+        return Err(I2cError::Length(raw.len()));
+    }
+
+    let mut data = Vec::with_capacity(raw.len() / 3 * 2);
+    for chunk in raw.chunks(3) {
+        if crc8(&chunk[..2]) != chunk[2] {
+            return Err(I2cError::Crc);
+        }
+        data.extend_from_slice(&chunk[..2]);
+    }
+
+    Ok(data)
+}
+
+// the same, as words
+pub fn words(raw: &[u8]) -> Result<Vec<u16>, I2cError> {
+    Ok(data(raw)?.chunks(2).map(|w| u16::from_be_bytes([w[0], w[1]])).collect())
+}
@@ -0,0 +1,38 @@
+#[cfg(test)]
+mod sensirion_tests {
+    use crate::payload::sensirion::*;
+
+    fn with_crcs(data: &[u8]) -> Vec<u8> {
+        data.chunks(2).flat_map(|w| [w[0], w[1], crc8(w)]).collect()
+    }
+
+    #[test]
+    fn crc() {
+        // the example from the datasheets
+        assert_eq!(crc8(&[0xBE, 0xEF]), 0x92);
+    }
+
+    #[test]
+    fn commands() {
+        assert_eq!(command(0x0300, &[]), [0x03, 0x00]);
+        assert_eq!(command(0x0010, &[0xBEEF]), [0x00, 0x10, 0xBE, 0xEF, 0x92]);
+        assert_eq!(command(0x8004, &[0x0009, 0x3A80]).len(), 2 + 2 * 3);
+    }
+
+    #[test]
+    fn responses() {
+        let raw = with_crcs(&[0x3F, 0x80, 0x00, 0x00, 0xBE, 0xEF]);
+        assert_eq!(raw.len(), response_size(3));
+        assert_eq!(data(&raw), Ok(vec![0x3F, 0x80, 0x00, 0x00, 0xBE, 0xEF]));
+        assert_eq!(words(&raw), Ok(vec![0x3F80, 0x0000, 0xBEEF]));
+        assert_eq!(data(&[]), Ok(vec![]));
+    }
+
+    #[test]
+    fn rejects_bad_responses() {
+        let mut raw = with_crcs(&[0xBE, 0xEF, 0x00, 0x01]);
+        assert_eq!(data(&raw[..4]), Err(I2cError::Length(4)));
+        raw[1] ^= 0x01;
+        assert_eq!(data(&raw), Err(I2cError::Crc));
+    }
+}
@@ -0,0 +1,65 @@
+#[cfg(test)]
+mod tests;
+
+use crate::payload::sensirion;
+
+// reading from the Sensirion SGP40 VOC and SGP41 VOC/NOx sensors
+//  see datasheets:  https://sensirion.com/media/documents/296373BB/6203C5DF/Sensirion_Gas_Sensors_Datasheet_SGP40.pdf
+//                   https://sensirion.com/media/documents/5FE8673C/61E96F50/Sensirion_Gas_Sensors_Datasheet_SGP41.pdf
+//
+//  I2C at 0x59 in the common Sensirion framing. The sensors report raw signals
+//  in ticks, with no absolute scale -- the VOC and NOx indices come from the
+//  gas index algorithm run over them. Every measurement is given the relative
+//  humidity and temperature of the air, also in ticks, which the sensor
+//  compensates for; 50 %RH and 25 degC if there is nothing better.
+
+pub const I2C_ADDRESS: u16 = 0x59;
+
+pub const DEFAULT_HUMIDITY: u16 = 0x8000;
+pub const DEFAULT_TEMPERATURE: u16 = 0x6666;
+
+// relative humidity in %, as the sensor takes it
+pub fn humidity_ticks(rh: f64) -> u16 {
+    (rh.clamp(0.0, 100.0) * 65535.0 / 100.0).round() as u16
+}
+
+// degC, as the sensor takes it
+pub fn temperature_ticks(t: f64) -> u16 {
+    ((t.clamp(-45.0, 130.0) + 45.0) * 65535.0 / 175.0).round() as u16
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    // SGP40: the VOC signal, with humidity and temperature ticks
+    MeasureRaw(u16, u16),
+    // SGP41: the VOC signal only, while the NOx pixel warms up -- the first 10 s
+    //  after power up, and no longer or it wears out
+    Conditioning(u16, u16),
+    // SGP41: VOC and NOx signals
+    MeasureRawSignals(u16, u16),
+}
+
+impl Command {
+    pub fn encode(self) -> Vec<u8> {
+        match self {
+            Command::MeasureRaw(rh, t) => sensirion::command(0x260F, &[rh, t]),
+            Command::Conditioning(rh, t) => sensirion::command(0x2612, &[rh, t]),
+            Command::MeasureRawSignals(rh, t) => sensirion::command(0x2619, &[rh, t]),
+        }
+    }
+
+    pub fn response_words(self) -> usize {
+        match self {
+            Command::MeasureRaw(..) | Command::Conditioning(..) => 1,
+            Command::MeasureRawSignals(..) => 2,
+        }
+    }
+
+    // ms before the response can be read
+    pub fn execution_ms(self) -> u64 {
+        match self {
+            Command::MeasureRaw(..) => 30,
+            Command::Conditioning(..) | Command::MeasureRawSignals(..) => 50,
+        }
+    }
+}
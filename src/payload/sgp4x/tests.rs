@@ -0,0 +1,24 @@
+#[cfg(test)]
+mod sgp4x_tests {
+    use crate::payload::sgp4x::*;
+
+    // the examples from the datasheets, with the default humidity and
+    //  temperature
+    #[test]
+    fn commands() {
+        let (rh, t) = (DEFAULT_HUMIDITY, DEFAULT_TEMPERATURE);
+        assert_eq!(Command::MeasureRaw(rh, t).encode(), [0x26, 0x0F, 0x80, 0x00, 0xA2, 0x66, 0x66, 0x93]);
+        assert_eq!(Command::Conditioning(rh, t).encode(), [0x26, 0x12, 0x80, 0x00, 0xA2, 0x66, 0x66, 0x93]);
+        assert_eq!(Command::MeasureRawSignals(rh, t).encode(), [0x26, 0x19, 0x80, 0x00, 0xA2, 0x66, 0x66, 0x93]);
+        assert_eq!(Command::MeasureRawSignals(rh, t).response_words(), 2);
+    }
+
+    #[test]
+    fn ticks() {
+        assert_eq!(humidity_ticks(50.0), 0x8000);
+        assert_eq!(temperature_ticks(25.0), 0x6666);
+        assert_eq!(humidity_ticks(0.0), 0);
+        assert_eq!(humidity_ticks(120.0), 0xFFFF);
+        assert_eq!(temperature_ticks(-60.0), 0);
+    }
+}
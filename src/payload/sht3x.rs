@@ -0,0 +1,33 @@
+#[cfg(test)]
+mod tests;
+
+use crate::payload::sensirion;
+
+// reading from the Sensirion SHT30 / SHT31 / SHT35 temperature and humidity
+//  sensors
+//  see datasheet:  https://sensirion.com/media/documents/213E6A3B/63A5A569/Datasheet_SHT3x_DIS.pdf
+//
+//  I2C at 0x44, or 0x45 with ADDR pulled high, in the common Sensirion framing.
+//  A single shot measurement is one command; the response, read once it's
+//  done, is temperature then relative humidity, both scaled to the full 16
+//  bits.
+
+pub const I2C_ADDRESSES: [u16; 2] = [0x44, 0x45];
+
+// single shot, high repeatability, without clock stretching
+pub const MEASURE: u16 = 0x2400;
+// high repeatability takes up to 15 ms
+pub const MEASURE_MS: u64 = 15;
+
+pub fn command() -> Vec<u8> {
+    sensirion::command(MEASURE, &[])
+}
+
+// degC and %, None unless given both words
+pub fn reading(words: &[u16]) -> Option<(f64, f64)> {
+    let [t, rh] = *words else {
+        return None;
+    };
+
+    Some((-45.0 + 175.0 * t as f64 / 65535.0, 100.0 * rh as f64 / 65535.0))
+}
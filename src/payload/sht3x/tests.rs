@@ -0,0 +1,18 @@
+#[cfg(test)]
+mod sht3x_tests {
+    use crate::payload::sht3x::*;
+
+    #[test]
+    fn single_shot_command() {
+        assert_eq!(command(), [0x24, 0x00]);
+    }
+
+    #[test]
+    fn scales_to_the_full_range() {
+        let (t, rh) = reading(&[0x6666, 0x8000]).unwrap();
+        assert!((t - 25.0).abs() < 0.01);
+        assert!((rh - 50.0).abs() < 0.01);
+        assert_eq!(reading(&[0, 0xFFFF]), Some((-45.0, 100.0)));
+        assert_eq!(reading(&[0x6666]), None);
+    }
+}
@@ -0,0 +1,32 @@
+#[cfg(test)]
+mod tests;
+
+// reading from the Sensirion SHT40 / SHT41 / SHT45 temperature and humidity
+//  sensors
+//  see datasheet:  https://sensirion.com/media/documents/33FD6951/662A593A/HT_DS_Datasheet_SHT4x.pdf
+//
+//  I2C at 0x44 (0x45 and 0x46 for some parts), with single byte commands but
+//  the usual Sensirion word-and-CRC responses: temperature then relative
+//  humidity, both scaled to the full 16 bits. The humidity scale runs past 0
+//  and 100 %, so it's clamped.
+
+pub const I2C_ADDRESSES: [u16; 3] = [0x44, 0x45, 0x46];
+
+// high precision, no heater
+pub const MEASURE: u8 = 0xFD;
+// takes up to 8.3 ms
+pub const MEASURE_MS: u64 = 10;
+
+pub fn command() -> Vec<u8> {
+    vec![MEASURE]
+}
+
+// degC and %, None unless given both words
+pub fn reading(words: &[u16]) -> Option<(f64, f64)> {
+    let [t, rh] = *words else {
+        return None;
+    };
+
+    let rh = -6.0 + 125.0 * rh as f64 / 65535.0;
+    Some((-45.0 + 175.0 * t as f64 / 65535.0, rh.clamp(0.0, 100.0)))
+}
@@ -0,0 +1,17 @@
+#[cfg(test)]
+mod sht4x_tests {
+    use crate::payload::sht4x::*;
+
+    #[test]
+    fn scales_and_clamps() {
+        assert_eq!(command(), [0xFD]);
+
+        let (t, rh) = reading(&[0x6666, 0x8000]).unwrap();
+        assert!((t - 25.0).abs() < 0.01);
+        assert!((rh - 56.5).abs() < 0.01);
+        // the humidity scale runs from -6 to 119 %
+        assert_eq!(reading(&[0x6666, 0]).unwrap().1, 0.0);
+        assert_eq!(reading(&[0x6666, 0xFFFF]).unwrap().1, 100.0);
+        assert_eq!(reading(&[]), None);
+    }
+}
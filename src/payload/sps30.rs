@@ -0,0 +1,211 @@
+#[cfg(test)]
+mod tests;
+
+use std::fmt;
+
+use crate::payload::sensirion;
+
+// reading from the Sensirion SPS30 particulate matter sensor
+//  see datasheet:  https://sensirion.com/media/documents/8600FF88/616542B5/Sensirion_PM_Sensors_Datasheet_SPS30.pdf
+//
+//  Over either interface a reading is ten big endian IEEE 754 floats: mass
+//  concentrations PM1.0, PM2.5, PM4.0 and PM10 in ug/m^3, number concentrations
+//  PM0.5, PM1.0, PM2.5, PM4.0 and PM10 in particles/cm^3, and the typical
+//  particle size in um. Unlike the PMS5003 and SDS011 the sensor never speaks
+//  unasked -- every exchange is a command and its response.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct Sps30Reading {
+    // ug/m^3
+    pub pm1_0: f64,
+    pub pm2_5: f64,
+    pub pm4_0: f64,
+    pub pm10: f64,
+    // particles/cm^3
+    pub nc0_5: f64,
+    pub nc1_0: f64,
+    pub nc2_5: f64,
+    pub nc4_0: f64,
+    pub nc10: f64,
+    // um
+    pub typical_size: f64,
+}
+
+pub const VALUES_SIZE: usize = 40;
+
+impl Sps30Reading {
+    // the measured values with any framing or CRCs already stripped
+    pub fn from_be_bytes(b: &[u8; VALUES_SIZE]) -> Self {
+        let f = |i: usize| f32::from_be_bytes([b[4 * i], b[4 * i + 1], b[4 * i + 2], b[4 * i + 3]]) as f64;
+
+        Sps30Reading {
+            pm1_0: f(0),
+            pm2_5: f(1),
+            pm4_0: f(2),
+            pm10: f(3),
+            nc0_5: f(4),
+            nc1_0: f(5),
+            nc2_5: f(6),
+            nc4_0: f(7),
+            nc10: f(8),
+            typical_size: f(9),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sps30Error {
+    // SHDLC frame without its delimiters, or cut short
+    Framing,
+    Checksum,
+    // the response is for some other command
+    Command(u8),
+    // the sensor refused the command -- the SHDLC state byte
+    State(u8),
+    Length(usize),
+}
+
+impl fmt::Display for Sps30Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Sps30Error::Framing => write!(f, "malformed SHDLC frame"),
+            Sps30Error::Checksum => write!(f, "SHDLC checksum mismatch"),
+            Sps30Error::Command(c) => write!(f, "response to command 0x{:02X}", c),
+            Sps30Error::State(s) => write!(f, "sensor error state 0x{:02X}", s),
+            Sps30Error::Length(n) => write!(f, "unexpected response length {}", n),
+        }
+    }
+}
+
+impl std::error::Error for Sps30Error {}
+
+// Commands, the same set over both interfaces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    // measure continuously, floats
+    Start,
+    Stop,
+    // over I2C only -- over UART ReadValues answers with no data instead
+    DataReady,
+    ReadValues,
+    // runs the fan at full speed for 10 s; readings meanwhile are meaningless
+    Clean,
+    // the sensor's own cleaning schedule, in seconds; 0 turns it off
+    CleaningInterval(u32),
+}
+
+// UART: SHDLC at 115200 baud
+//
+//  0x7E, address 0, command, length, data, checksum, 0x7E -- and the response
+//  has a state byte after the command. The checksum is the inverted low byte of
+//  the sum of everything between the delimiters. 0x7E, 0x7D, 0x11 and 0x13 are
+//  escaped as 0x7D and the byte XOR 0x20.
+
+pub const SHDLC_BAUD: u32 = 115_200;
+pub const SHDLC_DELIMITER: u8 = 0x7E;
+const SHDLC_ESCAPE: u8 = 0x7D;
+
+impl Command {
+    fn shdlc_code(self) -> (u8, Vec<u8>) {
+        match self {
+            Command::Start => (0x00, vec![0x01, 0x03]),
+            Command::Stop => (0x01, vec![]),
+            Command::DataReady | Command::ReadValues => (0x03, vec![]),
+            Command::Clean => (0x56, vec![]),
+            Command::CleaningInterval(secs) => {
+                let mut data = vec![0x00];
+                data.extend_from_slice(&secs.to_be_bytes());
+                (0x80, data)
+            },
+        }
+    }
+
+    pub fn shdlc(self) -> Vec<u8> {
+        let (command, data) = self.shdlc_code();
+        let mut body = vec![0x00, command, data.len() as u8];
+        body.extend_from_slice(&data);
+        body.push(shdlc_checksum(&body));
+
+        let mut frame = vec![SHDLC_DELIMITER];
+        for b in body {
+            match b {
+                0x7E | 0x7D | 0x11 | 0x13 => frame.extend_from_slice(&[SHDLC_ESCAPE, b ^ 0x20]),
+                _ => frame.push(b),
+            }
+        }
+        frame.push(SHDLC_DELIMITER);
+
+        frame
+    }
+
+    // the data of a response to this command, from one whole frame including
+    //  both delimiters
+    pub fn shdlc_response(self, frame: &[u8]) -> Result<Vec<u8>, Sps30Error> {
+        let inner = frame.strip_prefix(&[SHDLC_DELIMITER])
+            .and_then(|f| f.strip_suffix(&[SHDLC_DELIMITER]))
+            .ok_or(Sps30Error::Framing)?;
+
+        let mut body = Vec::with_capacity(inner.len());
+        let mut bytes = inner.iter();
+        while let Some(&b) = bytes.next() {
+            match b {
+                SHDLC_ESCAPE => body.push(bytes.next().ok_or(Sps30Error::Framing)? ^ 0x20),
+                _ => body.push(b),
+            }
+        }
+
+        // address, command, state, length, checksum
+        let [head @ .., check] = body.as_slice() else {
+            return Err(Sps30Error::Framing);
+        };
+        if head.len() < 4 {
+            return Err(Sps30Error::Framing);
+        }
+        if shdlc_checksum(head) != *check {
+            return Err(Sps30Error::Checksum);
+        }
+
+        let (command, state, length, data) = (head[1], head[2], head[3] as usize, &head[4..]);
+        if command != self.shdlc_code().0 {
+            return Err(Sps30Error::Command(command));
+        }
+        // bit 7 only flags a device error the status register explains
+        if state & 0x7F != 0 {
+            return Err(Sps30Error::State(state));
+        }
+        if data.len() != length {
+            return Err(Sps30Error::Length(data.len()));
+        }
+
+        Ok(data.to_vec())
+    }
+}
+
+fn shdlc_checksum(body: &[u8]) -> u8 {
+    !body.iter().fold(0u8, |sum, &b| sum.wrapping_add(b))
+}
+
+// I2C at address 0x69, in the common Sensirion framing
+
+pub const I2C_ADDRESS: u16 = 0x69;
+
+impl Command {
+    pub fn i2c(self) -> Vec<u8> {
+        match self {
+            Command::Start => sensirion::command(0x0010, &[0x0300]),
+            Command::Stop => sensirion::command(0x0104, &[]),
+            Command::DataReady => sensirion::command(0x0202, &[]),
+            Command::ReadValues => sensirion::command(0x0300, &[]),
+            Command::Clean => sensirion::command(0x5607, &[]),
+            Command::CleaningInterval(secs) => sensirion::command(0x8004, &[(secs >> 16) as u16, secs as u16]),
+        }
+    }
+
+    // bytes to read back after the command, with their CRCs
+    pub fn i2c_response_size(self) -> usize {
+        match self {
+            Command::DataReady => sensirion::response_size(1),
+            Command::ReadValues => sensirion::response_size(VALUES_SIZE / 2),
+            _ => 0,
+        }
+    }
+}
@@ -0,0 +1,104 @@
+extern crate hex_literal;
+
+#[cfg(test)]
+mod sps30_tests {
+    use crate::payload::sensirion;
+    use crate::payload::sps30::*;
+    use hex_literal::hex;
+
+    // 1.0, 2.5, 4.0 ... as floats, then framed the way the sensor sends them
+    fn values() -> [u8; VALUES_SIZE] {
+        let mut b = [0; VALUES_SIZE];
+        for (i, v) in [1.0f32, 2.5, 4.0, 10.0, 5.0, 6.0, 7.0, 8.0, 9.0, 0.5].iter().enumerate() {
+            b[4 * i..4 * i + 4].copy_from_slice(&v.to_be_bytes());
+        }
+        b
+    }
+
+    fn response(command: u8, state: u8, data: &[u8]) -> Vec<u8> {
+        let mut body = vec![0x00, command, state, data.len() as u8];
+        body.extend_from_slice(data);
+        body.push(!body.iter().fold(0u8, |s, &b| s.wrapping_add(b)));
+
+        let mut frame = vec![0x7E];
+        for b in body {
+            match b {
+                0x7E | 0x7D | 0x11 | 0x13 => frame.extend_from_slice(&[0x7D, b ^ 0x20]),
+                _ => frame.push(b),
+            }
+        }
+        frame.push(0x7E);
+        frame
+    }
+
+    #[test]
+    fn reading_from_floats() {
+        let r = Sps30Reading::from_be_bytes(&values());
+        assert_eq!(r.pm1_0, 1.0);
+        assert_eq!(r.pm2_5, 2.5);
+        assert_eq!(r.pm10, 10.0);
+        assert_eq!(r.nc0_5, 5.0);
+        assert_eq!(r.nc10, 9.0);
+        assert_eq!(r.typical_size, 0.5);
+    }
+
+    // the examples from the datasheet
+    #[test]
+    fn shdlc_commands() {
+        assert_eq!(Command::Start.shdlc(), hex!("7e 00 00 02 01 03 f9 7e"));
+        assert_eq!(Command::ReadValues.shdlc(), hex!("7e 00 03 00 fc 7e"));
+        assert_eq!(Command::Clean.shdlc(), hex!("7e 00 56 00 a9 7e"));
+        assert_eq!(Command::CleaningInterval(0).shdlc(), hex!("7e 00 80 05 00 00 00 00 00 7a 7e"));
+    }
+
+    #[test]
+    fn shdlc_escapes_reserved_bytes() {
+        // 0x11 in the data and a checksum of 0x7D
+        let frame = Command::CleaningInterval(0x11EC).shdlc();
+        assert_eq!(frame, hex!("7e 00 80 05 00 00 00 7d 31 ec 7d 5d 7e"));
+        assert_eq!(frame.iter().filter(|&&b| b == 0x7E).count(), 2);
+    }
+
+    #[test]
+    fn shdlc_responses() {
+        assert_eq!(Command::Start.shdlc_response(&hex!("7e 00 00 00 00 ff 7e")), Ok(vec![]));
+
+        let values = values();
+        let frame = response(0x03, 0x00, &values);
+        assert_eq!(Command::ReadValues.shdlc_response(&frame), Ok(values.to_vec()));
+        // no new reading yet
+        assert_eq!(Command::ReadValues.shdlc_response(&response(0x03, 0x00, &[])), Ok(vec![]));
+    }
+
+    #[test]
+    fn shdlc_unescapes_responses() {
+        let data = [0x7E, 0x7D, 0x11, 0x13];
+        let frame = response(0x03, 0x00, &data);
+        assert_eq!(frame.len(), 6 + 2 * data.len() + 1);
+        assert_eq!(Command::ReadValues.shdlc_response(&frame), Ok(data.to_vec()));
+    }
+
+    #[test]
+    fn shdlc_rejects_bad_responses() {
+        let mut frame = response(0x03, 0x00, &values());
+        frame[10] ^= 0x01;
+        assert_eq!(Command::ReadValues.shdlc_response(&frame), Err(Sps30Error::Checksum));
+
+        assert_eq!(Command::Start.shdlc_response(&response(0x00, 0x43, &[])), Err(Sps30Error::State(0x43)));
+        assert_eq!(Command::Start.shdlc_response(&response(0x56, 0x00, &[])), Err(Sps30Error::Command(0x56)));
+        assert_eq!(Command::Start.shdlc_response(&hex!("7e 00 00 00 00 ff")), Err(Sps30Error::Framing));
+        assert_eq!(Command::Start.shdlc_response(&hex!("7e 00 ff 7e")), Err(Sps30Error::Framing));
+        assert_eq!(Command::Start.shdlc_response(&hex!("7e 00 00 00 02 fd 7e")), Err(Sps30Error::Length(0)));
+    }
+
+    #[test]
+    fn i2c_commands() {
+        assert_eq!(Command::ReadValues.i2c(), hex!("03 00"));
+        assert_eq!(Command::Clean.i2c(), hex!("56 07"));
+        assert_eq!(Command::Start.i2c(), sensirion::command(0x0010, &[0x0300]));
+        let interval = Command::CleaningInterval(604_800).i2c();
+        assert_eq!(interval[..2], hex!("80 04"));
+        assert_eq!(sensirion::data(&interval[2..]), Ok(604_800u32.to_be_bytes().to_vec()));
+        assert_eq!(Command::ReadValues.i2c_response_size(), 60);
+    }
+}
@@ -2,11 +2,10 @@ extern crate hex_literal;
 
 #[cfg(test)]
 mod payload_tests {
-    use crate::payload::{FRAME_START, Payload, parse_stream_to_payload};
+    use crate::payload::{Command, FRAME_SIZE, FRAME_START, FrameDecoder, Payload, Pms5003Format, Pms5003Reading};
     use hex_literal::hex;
 
 
-    const FRAME_SIZE: usize = 32;
     // test data
     const ONE_GOOD_FRAME: [u8; FRAME_SIZE] = hex!(
         "424d 001c 0004 0006 0008 0004 0006 0008" 
@@ -19,13 +18,13 @@ mod payload_tests {
     // );
 
     // contains a complete frame starting at $18, and another partial frame following
-    // const OFFSET_START: [u8; 80] = hex!(
-    //     "6d6d 616e 6473 0d0a 5465 726d 696e 616c"
-    //     "2072 6561 6479 0d0a 424d 001c 0002 0004"
-    //     "0005 0002 0004 0005 01fe 0094 001a 0004"
-    //     "0002 0000 9700 030b 424d 001c 0002 0004"
-    //     "0005 0002 0004 0005 01fe 0094 001a 0004"
-    // );
+    const OFFSET_START: [u8; 80] = hex!(
+        "6d6d 616e 6473 0d0a 5465 726d 696e 616c"
+        "2072 6561 6479 0d0a 424d 001c 0002 0004"
+        "0005 0002 0004 0005 01fe 0094 001a 0004"
+        "0002 0000 9700 030b 424d 001c 0002 0004"
+        "0005 0002 0004 0005 01fe 0094 001a 0004"
+    );
 
     // fn parser_finds_start() {
 
@@ -33,8 +32,7 @@ mod payload_tests {
 
     #[test]
     fn parser_returns_payload() {
-        // let p = parse_stream_to_Payload(&ONE_GOOD_FRAME);
-        let (_, p) = parse_stream_to_payload(&ONE_GOOD_FRAME).unwrap();
+        let p = decode(&ONE_GOOD_FRAME).unwrap();
         assert!(std::mem::size_of_val(&p) == std::mem::size_of::<Payload>());
 
         // check payload has a start sequence
@@ -61,24 +59,72 @@ mod payload_tests {
         assert_eq!(p.check, u16::from_be_bytes([ONE_GOOD_FRAME[30], ONE_GOOD_FRAME[31]]));
     }
 
+    #[test]
+    fn reading_names_the_data_words() {
+        let r = decode(&ONE_GOOD_FRAME).unwrap().reading();
+
+        assert_eq!(r, Pms5003Reading {
+            pm1_0_cf1: 0x0004,
+            pm2_5_cf1: 0x0006,
+            pm10_cf1: 0x0008,
+            pm1_0_atm: 0x0004,
+            pm2_5_atm: 0x0006,
+            pm10_atm: 0x0008,
+            particles_0_3um: 0x0324,
+            particles_0_5um: 0x00ea,
+            particles_1_0um: 0x0036,
+            particles_2_5um: 0x0008,
+            particles_5_0um: 0x0002,
+            particles_10um: 0x0000,
+            version: 0x97,
+            error_code: 0x00,
+        });
+    }
+
     #[test]
     fn fails_on_checksum_errors() {
         let mut f = ONE_GOOD_FRAME.clone();
         f[4] += 1;
-        
+
         assert!(returns_error(&f));
+        let mut d = FrameDecoder::<Pms5003Format>::new();
+        d.push(&f);
+        assert!(d.next_frame().is_none());
+        assert_eq!(d.stats().checksum_errors, 1);
     }
 
     #[test]
-    fn panics_without_frame_start() {
+    fn errors_without_frame_start() {
         let mut f = ONE_GOOD_FRAME.clone();
         f[0] = 0xBA; f[1] = 0xFD;           // could be any error values, just removing 'BM'
 
-        let r = std::panic::catch_unwind(|| 
-            parse_stream_to_payload(&f)
-        );
+        assert!(returns_error(&f));
+    }
+
+    #[test]
+    fn skips_to_frame_start() {
+        let mut d = FrameDecoder::<Pms5003Format>::new();
+        d.push(&OFFSET_START);
 
-        assert!(r.is_err());
+        assert_eq!(d.next_frame().unwrap().reading().pm2_5_cf1, 0x0004);
+        // the text ahead of it is skipped, the partial frame after it held
+        assert_eq!(d.stats().resync_bytes, 0x18);
+        assert!(d.next_frame().is_none());
+        d.push(&OFFSET_START[0x30..0x38]);
+        assert_eq!(d.next_frame().unwrap().reading().pm2_5_cf1, 0x0004);
+    }
+
+    #[test]
+    fn truncated_frames_are_incomplete_not_panics() {
+        for n in 0..FRAME_SIZE {
+            let mut d = FrameDecoder::<Pms5003Format>::new();
+            d.push(&ONE_GOOD_FRAME[..n]);
+            assert!(d.next_frame().is_none(), "{} bytes", n);
+            assert_eq!(d.stats(), Default::default(), "{} bytes", n);
+
+            d.push(&ONE_GOOD_FRAME[n..]);
+            assert!(d.next_frame().is_some(), "{} bytes", n);
+        }
     }
 
     #[test]
@@ -97,14 +143,128 @@ mod payload_tests {
         assert!(returns_error(&f));
     }
 
-    fn returns_error(f: &[u8]) -> bool {
-        match parse_stream_to_payload(&f) {
-            Ok(_b) => return false,
-            Err(e) => {
-                eprintln!("{:?}", e);
-                return true;
+    #[test]
+    fn decoder_yields_back_to_back_frames_in_order() {
+        let mut second = ONE_GOOD_FRAME.clone();
+        second[7] = 0x07; second[31] = 0xb8;     // PM2.5 CF=1 = 7, checksum adjusted
+
+        let mut d = FrameDecoder::<Pms5003Format>::new();
+        d.push(&ONE_GOOD_FRAME);
+        d.push(&second);
+
+        assert_eq!(d.next_frame().unwrap().reading().pm2_5_cf1, 6);
+        assert_eq!(d.next_frame().unwrap().reading().pm2_5_cf1, 7);
+        assert!(d.next_frame().is_none());
+    }
+
+    #[test]
+    fn decoder_reassembles_frames_split_across_reads() {
+        let mut d = FrameDecoder::<Pms5003Format>::new();
+        let mut frames = 0;
+        for b in OFFSET_START.iter().chain(ONE_GOOD_FRAME.iter()) {
+            d.push(std::slice::from_ref(b));
+            while d.next_frame().is_some() {
+                frames += 1;
             }
         }
+
+        // the partial frame at the end of OFFSET_START is followed by a whole
+        //  one -- only the whole one counts
+        assert_eq!(frames, 2);
+    }
+
+    #[test]
+    fn decoder_resyncs_after_corruption() {
+        let mut bad = ONE_GOOD_FRAME.clone();
+        bad[10] ^= 0x55;
+
+        let mut d = FrameDecoder::<Pms5003Format>::new();
+        d.push(&bad);
+        d.push(b"BBM\x00");                // looks like a start, but isn't a frame
+        d.push(&ONE_GOOD_FRAME);
+
+        let p = d.next_frame().unwrap();
+        assert_eq!(p.reading(), decode(&ONE_GOOD_FRAME).unwrap().reading());
+        assert!(d.next_frame().is_none());
+    }
+
+    #[test]
+    fn decoder_holds_partial_frame() {
+        let mut d = FrameDecoder::<Pms5003Format>::new();
+        d.push(&ONE_GOOD_FRAME[..20]);
+        assert!(d.next_frame().is_none());
+
+        d.push(&ONE_GOOD_FRAME[20..]);
+        assert!(d.next_frame().is_some());
+    }
+
+    #[test]
+    fn decoder_counts_what_it_throws_away() {
+        let mut bad_sum = ONE_GOOD_FRAME.clone();
+        bad_sum[10] ^= 0x55;
+        let mut bad_len = ONE_GOOD_FRAME.clone();
+        bad_len[2] = 0x00; bad_len[3] = 0x00;
+
+        let mut d = FrameDecoder::<Pms5003Format>::new();
+        assert!(d.since_last_frame().is_none());
+
+        d.push(&bad_sum);
+        d.push(&bad_len);
+        d.push(&ONE_GOOD_FRAME);
+        while d.next_frame().is_some() {}
+
+        let s = d.stats();
+        assert_eq!(s.frames, 1);
+        assert_eq!(s.checksum_errors, 1);
+        assert_eq!(s.length_errors, 1);
+        // every byte ahead of the good frame was skipped, and only once
+        assert_eq!(s.resync_bytes, 2 * FRAME_SIZE as u32);
+        assert!(d.since_last_frame().is_some());
+    }
+
+    #[test]
+    fn commands_match_the_datasheet() {
+        assert_eq!(Command::PassiveMode.encode(), hex!("42 4d e1 00 00 01 70"));
+        assert_eq!(Command::ActiveMode.encode(), hex!("42 4d e1 00 01 01 71"));
+        assert_eq!(Command::ReadPassive.encode(), hex!("42 4d e2 00 00 01 71"));
+        assert_eq!(Command::Sleep.encode(), hex!("42 4d e4 00 00 01 73"));
+        assert_eq!(Command::Wake.encode(), hex!("42 4d e4 00 01 01 74"));
+    }
+
+    #[test]
+    fn decoder_drops_command_acks_quietly() {
+        let mut d = FrameDecoder::<Pms5003Format>::new();
+        d.push(&hex!("42 4d 00 04 e1 00 01 74"));     // ack for passive mode
+        assert!(d.next_frame().is_none());
+
+        d.push(&hex!("42 4d 00 04"));                 // half of a sleep ack
+        assert!(d.next_frame().is_none());
+        d.push(&hex!("e4 00 01 77"));
+        d.push(&ONE_GOOD_FRAME);
+
+        assert!(d.next_frame().is_some());
+        assert_eq!(d.stats().length_errors, 0);
+        assert_eq!(d.stats().resync_bytes, 0);
+    }
+
+    // the first frame in the bytes
+    fn decode(f: &[u8]) -> Option<Payload> {
+        let mut d = FrameDecoder::<Pms5003Format>::new();
+        d.push(f);
+        d.next_frame()
+    }
+
+    // thrown away, without holding up the good frame behind it
+    fn returns_error(f: &[u8]) -> bool {
+        let mut d = FrameDecoder::<Pms5003Format>::new();
+        d.push(f);
+        d.push(&ONE_GOOD_FRAME);
+
+        let mut frames = 0;
+        while d.next_frame().is_some() {
+            frames += 1;
+        }
+        frames == 1
     }
 
 
@@ -0,0 +1,542 @@
+#[cfg(test)]
+mod tests;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+// Modbus register map
+//
+//  Every published value is declared exactly once in the `register_map!` block
+//  below with its address, encoding, word order and description. Encoding,
+//  decoding, range checks and the printed map are all driven from those
+//  declarations -- adding a new value is one more line in the block.
+
+// how a value is laid out in 16-bit Modbus words
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DataType {
+    U16,
+    U32,
+    F32,
+    // value multiplied by the scale and rounded, as a signed 16-bit word
+    I16Scaled(i32),
+}
+
+// byte order of a 32-bit value across its two registers, named the way PLC and
+//  HMI vendors do -- A is the most significant byte. 16-bit values are always
+//  sent as-is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordOrder {
+    Abcd,   // big endian, high word first
+    Cdab,   // big endian words, low word first
+    Badc,   // high word first, bytes swapped within each word
+    Dcba,   // little endian
+}
+
+impl FromStr for WordOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "ABCD" => Ok(WordOrder::Abcd),
+            "CDAB" => Ok(WordOrder::Cdab),
+            "BADC" => Ok(WordOrder::Badc),
+            "DCBA" => Ok(WordOrder::Dcba),
+            _ => Err(format!("unknown word order '{}', expected ABCD, CDAB, BADC or DCBA", s)),
+        }
+    }
+}
+
+impl fmt::Display for WordOrder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            WordOrder::Abcd => "ABCD",
+            WordOrder::Cdab => "CDAB",
+            WordOrder::Badc => "BADC",
+            WordOrder::Dcba => "DCBA",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// per-deployment encoding choices
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Encoding {
+    // overrides the declared order of every 32-bit point when set
+    pub word_order: Option<WordOrder>,
+    // also publish the SCALED_INTEGER_REGISTERS block
+    pub scaled_integers: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    pub name: &'static str,
+    pub address: u16,
+    pub data_type: DataType,
+    pub word_order: WordOrder,
+    pub unit: &'static str,
+    pub description: &'static str,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegisterError {
+    OutOfRange { point: &'static str, value: f64 },
+}
+
+impl fmt::Display for RegisterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegisterError::OutOfRange { point, value } =>
+                write!(f, "{} can't encode {}", point, value),
+        }
+    }
+}
+
+impl std::error::Error for RegisterError {}
+
+impl DataType {
+    // number of 16-bit registers used
+    pub const fn width(&self) -> u16 {
+        match self {
+            DataType::U16 | DataType::I16Scaled(_) => 1,
+            DataType::U32 | DataType::F32 => 2,
+        }
+    }
+
+    fn name(&self) -> String {
+        match self {
+            DataType::U16 => "u16".to_string(),
+            DataType::U32 => "u32".to_string(),
+            DataType::F32 => "f32".to_string(),
+            DataType::I16Scaled(scale) => format!("i16 x{}", scale),
+        }
+    }
+}
+
+impl Point {
+    pub const fn width(&self) -> u16 {
+        self.data_type.width()
+    }
+
+    // integer types are rounded to the nearest representable value; a scaled
+    //  integer's NaN is 0x8000, which no finite value encodes to
+    pub fn encode(&self, value: f64) -> Result<Vec<u16>, RegisterError> {
+        let out_of_range = || RegisterError::OutOfRange { point: self.name, value };
+
+        match self.data_type {
+            DataType::U16 => {
+                let v = value.round();
+                if !(0.0..=u16::MAX as f64).contains(&v) {
+                    return Err(out_of_range());
+                }
+                Ok(vec![v as u16])
+            },
+            DataType::I16Scaled(_) if value.is_nan() => Ok(vec![i16::MIN as u16]),
+            DataType::I16Scaled(scale) => {
+                let v = (value * scale as f64).round();
+                if !(-i16::MAX as f64..=i16::MAX as f64).contains(&v) {
+                    return Err(out_of_range());
+                }
+                Ok(vec![v as i16 as u16])
+            },
+            DataType::U32 => {
+                let v = value.round();
+                if !(0.0..=u32::MAX as f64).contains(&v) {
+                    return Err(out_of_range());
+                }
+                Ok(self.split(v as u32))
+            },
+            DataType::F32 => Ok(self.split((value as f32).to_bits())),
+        }
+    }
+
+    pub fn decode(&self, words: &[u16]) -> f64 {
+        match self.data_type {
+            DataType::U16 => words[0] as f64,
+            DataType::I16Scaled(_) if words[0] == i16::MIN as u16 => f64::NAN,
+            DataType::I16Scaled(scale) => words[0] as i16 as f64 / scale as f64,
+            DataType::U32 => self.join(words) as f64,
+            DataType::F32 => f32::from_bits(self.join(words)) as f64,
+        }
+    }
+
+    fn split(&self, u: u32) -> Vec<u16> {
+        let (hw, lw) = ((u >> 16) as u16, (u & 0xFFFF) as u16);
+        match self.word_order {
+            WordOrder::Abcd => vec![hw, lw],
+            WordOrder::Cdab => vec![lw, hw],
+            WordOrder::Badc => vec![hw.swap_bytes(), lw.swap_bytes()],
+            WordOrder::Dcba => vec![lw.swap_bytes(), hw.swap_bytes()],
+        }
+    }
+
+    fn join(&self, words: &[u16]) -> u32 {
+        let (hw, lw) = match self.word_order {
+            WordOrder::Abcd => (words[0], words[1]),
+            WordOrder::Cdab => (words[1], words[0]),
+            WordOrder::Badc => (words[0].swap_bytes(), words[1].swap_bytes()),
+            WordOrder::Dcba => (words[1].swap_bytes(), words[0].swap_bytes()),
+        };
+        (hw as u32) << 16 | lw as u32
+    }
+}
+
+macro_rules! register_map {
+    ($map:ident; $( $name:ident @ $addr:literal : $ty:ident $(($scale:literal))?, $order:ident, $unit:literal, $desc:literal; )*) => {
+        $(
+            pub const $name: Point = Point {
+                name: stringify!($name),
+                address: $addr,
+                data_type: DataType::$ty $(($scale))?,
+                word_order: WordOrder::$order,
+                unit: $unit,
+                description: $desc,
+            };
+        )*
+
+        pub const $map: &[Point] = &[ $( $name ),* ];
+    };
+}
+
+register_map! {
+    INPUT_REGISTERS;
+
+    AQI @ 0x00 : U16, Abcd, "", "US EPA AQI, worst of the PM2.5 and PM10 sub-indices";
+    PM_1_0 @ 0x01 : U16, Abcd, "ug/m3", "PM1.0 (CF=1)";
+    PM_2_5 @ 0x02 : U16, Abcd, "ug/m3", "PM2.5 (CF=1)";
+    PM_10 @ 0x03 : U16, Abcd, "ug/m3", "PM10 (CF=1)";
+    AQI_TICK @ 0x04 : U32, Abcd, "s", "AQI timestamp, 32-bit overflowing epoch seconds";
+    TEMP @ 0x06 : F32, Abcd, "degC", "Temperature";
+    HUM @ 0x08 : F32, Abcd, "%RH", "Relative humidity";
+    TEMP_HUM_TICK @ 0x0A : U32, Abcd, "s", "Temp/humidity timestamp, 32-bit overflowing epoch seconds";
+
+    // how far each measurement's latest value can be trusted: 0 valid, 1 no
+    //  reading yet, 2 stale, 3 warming up, 4 out of range, 5 sensor error,
+    //  6 simulated
+    PM_QUALITY @ 0x0C : U16, Abcd, "", "Particulate quality";
+    TEMP_HUM_QUALITY @ 0x0D : U16, Abcd, "", "Temperature and humidity quality";
+    CO2_QUALITY @ 0x0E : U16, Abcd, "", "CO2 quality";
+    GAS_QUALITY @ 0x0F : U16, Abcd, "", "VOC/NOx quality";
+
+    // full PMS5003 frame -- all 12 data words in datasheet order, then version and error code
+    PMS_PM1_0_CF1 @ 0x10 : U16, Abcd, "ug/m3", "PM1.0, standard particle";
+    PMS_PM2_5_CF1 @ 0x11 : U16, Abcd, "ug/m3", "PM2.5, standard particle";
+    PMS_PM10_CF1 @ 0x12 : U16, Abcd, "ug/m3", "PM10, standard particle";
+    PMS_PM1_0_ATM @ 0x13 : U16, Abcd, "ug/m3", "PM1.0, atmospheric environment";
+    PMS_PM2_5_ATM @ 0x14 : U16, Abcd, "ug/m3", "PM2.5, atmospheric environment";
+    PMS_PM10_ATM @ 0x15 : U16, Abcd, "ug/m3", "PM10, atmospheric environment";
+    PMS_PARTICLES_0_3UM @ 0x16 : U16, Abcd, "/0.1L", "Particles > 0.3um";
+    PMS_PARTICLES_0_5UM @ 0x17 : U16, Abcd, "/0.1L", "Particles > 0.5um";
+    PMS_PARTICLES_1_0UM @ 0x18 : U16, Abcd, "/0.1L", "Particles > 1.0um";
+    PMS_PARTICLES_2_5UM @ 0x19 : U16, Abcd, "/0.1L", "Particles > 2.5um";
+    PMS_PARTICLES_5_0UM @ 0x1A : U16, Abcd, "/0.1L", "Particles > 5.0um";
+    PMS_PARTICLES_10UM @ 0x1B : U16, Abcd, "/0.1L", "Particles > 10um";
+    PMS_VERSION @ 0x1C : U16, Abcd, "", "PMS5003 version, high byte of the reserved word";
+    PMS_ERROR_CODE @ 0x1D : U16, Abcd, "", "PMS5003 error code, low byte of the reserved word";
+
+    // frame decoder health -- counters wrap at 2^32
+    //  0x1E..0x1F and 0x22..0x27 are left free; 0x20..0x21 hold the optional scaled mirrors
+    PMS_FRAMES @ 0x28 : U32, Abcd, "", "PMS5003 frames decoded";
+    PMS_CHECKSUM_ERRORS @ 0x2A : U32, Abcd, "", "PMS5003 frames with a bad checksum";
+    PMS_LENGTH_ERRORS @ 0x2C : U32, Abcd, "", "PMS5003 frames with a bad length";
+    PMS_RESYNC_BYTES @ 0x2E : U32, Abcd, "", "Bytes skipped looking for a frame start";
+    PMS_FRAME_AGE @ 0x30 : U32, Abcd, "s", "Seconds since the last good frame, 0xFFFFFFFF if none yet";
+    PMS_STATE @ 0x32 : U16, Abcd, "", "PMS5003 duty cycle: 0 sampling, 1 fan stabilizing, 2 asleep";
+
+    // US EPA AQI detail -- 0x00 is the largest of the sub-indices
+    AQI_NOWCAST @ 0x34 : U16, Abcd, "", "NowCast AQI over the last 12 hours, 0xFFFF until there is enough history";
+    AQI_DOMINANT @ 0x35 : U16, Abcd, "", "Pollutant driving the AQI: 0 PM2.5, 1 PM10";
+    AQI_PM2_5 @ 0x36 : U16, Abcd, "", "PM2.5 sub-index";
+    AQI_PM10 @ 0x37 : U16, Abcd, "", "PM10 sub-index";
+
+    // the index scheme chosen with [aqi] scheme -- the same as 0x00 for us-epa
+    INDEX @ 0x38 : U16, Abcd, "", "Index value in the configured scheme";
+    INDEX_CATEGORY @ 0x39 : U16, Abcd, "", "Index category, 0 = best, in the configured scheme";
+    INDEX_SCHEME @ 0x3A : U16, Abcd, "", "Index scheme: 0 US EPA, 1 EU CAQI, 2 UK DAQI, 3 India NAQI, 4 China AQI, 5 Canada AQHI+";
+
+    // PM2.5 as fed to the indices -- 0x02 and 0x11 stay the raw sensor value
+    PM_2_5_CORRECTION @ 0x3B : U16, Abcd, "", "PM2.5 correction: 0 none, 1 US EPA, 2 LRAPA, 3 AQ&U";
+    PM_2_5_CORRECTED @ 0x3C : F32, Abcd, "ug/m3", "PM2.5, corrected";
+
+    // which sensor 0x01..0x03 and the indices come from
+    PM_SENSOR @ 0x3E : U16, Abcd, "", "Particulate sensor: 0 PMS5003, 1 SDS011, 2 SPS30, 3 simulated";
+
+    // full SPS30 reading, in datasheet order
+    //  0x3F is left free
+    SPS30_PM1_0 @ 0x40 : F32, Abcd, "ug/m3", "SPS30 PM1.0";
+    SPS30_PM2_5 @ 0x42 : F32, Abcd, "ug/m3", "SPS30 PM2.5";
+    SPS30_PM4_0 @ 0x44 : F32, Abcd, "ug/m3", "SPS30 PM4.0";
+    SPS30_PM10 @ 0x46 : F32, Abcd, "ug/m3", "SPS30 PM10";
+    SPS30_NC0_5 @ 0x48 : F32, Abcd, "/cm3", "SPS30 particles 0.3..0.5um";
+    SPS30_NC1_0 @ 0x4A : F32, Abcd, "/cm3", "SPS30 particles 0.3..1.0um";
+    SPS30_NC2_5 @ 0x4C : F32, Abcd, "/cm3", "SPS30 particles 0.3..2.5um";
+    SPS30_NC4_0 @ 0x4E : F32, Abcd, "/cm3", "SPS30 particles 0.3..4.0um";
+    SPS30_NC10 @ 0x50 : F32, Abcd, "/cm3", "SPS30 particles 0.3..10um";
+    SPS30_TYPICAL_SIZE @ 0x52 : F32, Abcd, "um", "SPS30 typical particle size";
+
+    // CO2, from whichever sensor the unit has
+    CO2 @ 0x54 : U16, Abcd, "ppm", "CO2, compensated if [co2] compensate is on";
+    CO2_RAW @ 0x55 : U16, Abcd, "ppm", "CO2 as the sensor reported it";
+    CO2_SENSOR @ 0x56 : U16, Abcd, "", "CO2 sensor: 0 Senseair S8, 1 MH-Z19, 2 SCD30, 3 SCD4x";
+    CO2_COMPENSATED @ 0x57 : U16, Abcd, "", "1 if 0x54 is compensated for temperature and humidity";
+    CO2_TICK @ 0x58 : U32, Abcd, "s", "CO2 timestamp, 32-bit overflowing epoch seconds";
+
+    // VOC and NOx, from whichever gas sensor the unit has -- the indices are 0
+    //  for the first 45 s, and from sensors without the signal
+    VOC_RAW @ 0x5A : U16, Abcd, "ticks", "SGP4x raw VOC signal";
+    NOX_RAW @ 0x5B : U16, Abcd, "ticks", "SGP41 raw NOx signal";
+    VOC_INDEX @ 0x5C : U16, Abcd, "", "Sensirion VOC index, 1..500, 100 is average";
+    NOX_INDEX @ 0x5D : U16, Abcd, "", "Sensirion NOx index, 1..500, 1 is normal";
+    GAS_SENSOR @ 0x5E : U16, Abcd, "", "Gas sensor: 0 SGP40, 1 SGP41, 2 BME680/BME688";
+    //  0x5F is left free
+    GAS_TICK @ 0x60 : U32, Abcd, "s", "Gas timestamp, 32-bit overflowing epoch seconds";
+
+    // full BME680 reading; NaN without one
+    GAS_RESISTANCE @ 0x62 : F32, Abcd, "ohm", "BME680 hot plate resistance, NaN if it didn't reach temperature";
+    BME680_TEMP @ 0x64 : F32, Abcd, "degC", "BME680 temperature";
+    BME680_HUM @ 0x66 : F32, Abcd, "%RH", "BME680 relative humidity";
+    PRESSURE @ 0x68 : F32, Abcd, "hPa", "Barometric pressure, from the BME680 or BME280";
+
+    // combined indoor air score -- see iaq.rs
+    IAQ_SCORE @ 0x6A : U16, Abcd, "", "Indoor air score: 1 excellent .. 5 unhealthy, 0 before any reading";
+    IAQ_DOMINANT @ 0x6B : U16, Abcd, "", "Measurement driving the score: 0 PM2.5, 1 CO2, 2 VOC, 3 NOx";
+
+    // AM2302 / DHT read health -- counters wrap at 2^32
+    //  0x6C..0x6D are left free
+    CLIMATE_READINGS @ 0x6E : U32, Abcd, "", "AM2302 readings accepted";
+    CLIMATE_ERRORS @ 0x70 : U32, Abcd, "", "AM2302 failed reads, retries included";
+    CLIMATE_REJECTED @ 0x72 : U32, Abcd, "", "AM2302 readings rejected as implausible";
+    CLIMATE_AGE @ 0x74 : U32, Abcd, "s", "Seconds since the last accepted reading, 0xFFFFFFFF if none yet";
+}
+
+// optional mirrors of the float points for devices without float support
+register_map! {
+    SCALED_INTEGER_REGISTERS;
+
+    TEMP_X10 @ 0x20 : I16Scaled(10), Abcd, "degC", "Temperature x10";
+    HUM_X10 @ 0x21 : I16Scaled(10), Abcd, "%RH", "Relative humidity x10";
+}
+
+// rolling statistics over the last 1 minute, 10 minutes, 1 hour and 24 hours
+//  one block per measurement at 0x100, 0x140, 0x180 and 0x1C0, then 0x10 per
+//  window; NaN until the window has a reading
+register_map! {
+    ROLLING_REGISTERS;
+
+    PM_2_5_1M_MEAN @ 0x100 : F32, Abcd, "ug/m3", "PM2.5 corrected, 1 min mean";
+    PM_2_5_1M_MEDIAN @ 0x102 : F32, Abcd, "ug/m3", "PM2.5 corrected, 1 min median";
+    PM_2_5_1M_MIN @ 0x104 : F32, Abcd, "ug/m3", "PM2.5 corrected, 1 min min";
+    PM_2_5_1M_MAX @ 0x106 : F32, Abcd, "ug/m3", "PM2.5 corrected, 1 min max";
+    PM_2_5_1M_STDDEV @ 0x108 : F32, Abcd, "ug/m3", "PM2.5 corrected, 1 min std dev";
+    PM_2_5_10M_MEAN @ 0x110 : F32, Abcd, "ug/m3", "PM2.5 corrected, 10 min mean";
+    PM_2_5_10M_MEDIAN @ 0x112 : F32, Abcd, "ug/m3", "PM2.5 corrected, 10 min median";
+    PM_2_5_10M_MIN @ 0x114 : F32, Abcd, "ug/m3", "PM2.5 corrected, 10 min min";
+    PM_2_5_10M_MAX @ 0x116 : F32, Abcd, "ug/m3", "PM2.5 corrected, 10 min max";
+    PM_2_5_10M_STDDEV @ 0x118 : F32, Abcd, "ug/m3", "PM2.5 corrected, 10 min std dev";
+    PM_2_5_1H_MEAN @ 0x120 : F32, Abcd, "ug/m3", "PM2.5 corrected, 1 h mean";
+    PM_2_5_1H_MEDIAN @ 0x122 : F32, Abcd, "ug/m3", "PM2.5 corrected, 1 h median";
+    PM_2_5_1H_MIN @ 0x124 : F32, Abcd, "ug/m3", "PM2.5 corrected, 1 h min";
+    PM_2_5_1H_MAX @ 0x126 : F32, Abcd, "ug/m3", "PM2.5 corrected, 1 h max";
+    PM_2_5_1H_STDDEV @ 0x128 : F32, Abcd, "ug/m3", "PM2.5 corrected, 1 h std dev";
+    PM_2_5_24H_MEAN @ 0x130 : F32, Abcd, "ug/m3", "PM2.5 corrected, 24 h mean";
+    PM_2_5_24H_MEDIAN @ 0x132 : F32, Abcd, "ug/m3", "PM2.5 corrected, 24 h median";
+    PM_2_5_24H_MIN @ 0x134 : F32, Abcd, "ug/m3", "PM2.5 corrected, 24 h min";
+    PM_2_5_24H_MAX @ 0x136 : F32, Abcd, "ug/m3", "PM2.5 corrected, 24 h max";
+    PM_2_5_24H_STDDEV @ 0x138 : F32, Abcd, "ug/m3", "PM2.5 corrected, 24 h std dev";
+
+    PM_10_1M_MEAN @ 0x140 : F32, Abcd, "ug/m3", "PM10, 1 min mean";
+    PM_10_1M_MEDIAN @ 0x142 : F32, Abcd, "ug/m3", "PM10, 1 min median";
+    PM_10_1M_MIN @ 0x144 : F32, Abcd, "ug/m3", "PM10, 1 min min";
+    PM_10_1M_MAX @ 0x146 : F32, Abcd, "ug/m3", "PM10, 1 min max";
+    PM_10_1M_STDDEV @ 0x148 : F32, Abcd, "ug/m3", "PM10, 1 min std dev";
+    PM_10_10M_MEAN @ 0x150 : F32, Abcd, "ug/m3", "PM10, 10 min mean";
+    PM_10_10M_MEDIAN @ 0x152 : F32, Abcd, "ug/m3", "PM10, 10 min median";
+    PM_10_10M_MIN @ 0x154 : F32, Abcd, "ug/m3", "PM10, 10 min min";
+    PM_10_10M_MAX @ 0x156 : F32, Abcd, "ug/m3", "PM10, 10 min max";
+    PM_10_10M_STDDEV @ 0x158 : F32, Abcd, "ug/m3", "PM10, 10 min std dev";
+    PM_10_1H_MEAN @ 0x160 : F32, Abcd, "ug/m3", "PM10, 1 h mean";
+    PM_10_1H_MEDIAN @ 0x162 : F32, Abcd, "ug/m3", "PM10, 1 h median";
+    PM_10_1H_MIN @ 0x164 : F32, Abcd, "ug/m3", "PM10, 1 h min";
+    PM_10_1H_MAX @ 0x166 : F32, Abcd, "ug/m3", "PM10, 1 h max";
+    PM_10_1H_STDDEV @ 0x168 : F32, Abcd, "ug/m3", "PM10, 1 h std dev";
+    PM_10_24H_MEAN @ 0x170 : F32, Abcd, "ug/m3", "PM10, 24 h mean";
+    PM_10_24H_MEDIAN @ 0x172 : F32, Abcd, "ug/m3", "PM10, 24 h median";
+    PM_10_24H_MIN @ 0x174 : F32, Abcd, "ug/m3", "PM10, 24 h min";
+    PM_10_24H_MAX @ 0x176 : F32, Abcd, "ug/m3", "PM10, 24 h max";
+    PM_10_24H_STDDEV @ 0x178 : F32, Abcd, "ug/m3", "PM10, 24 h std dev";
+
+    TEMP_1M_MEAN @ 0x180 : F32, Abcd, "degC", "Temperature, 1 min mean";
+    TEMP_1M_MEDIAN @ 0x182 : F32, Abcd, "degC", "Temperature, 1 min median";
+    TEMP_1M_MIN @ 0x184 : F32, Abcd, "degC", "Temperature, 1 min min";
+    TEMP_1M_MAX @ 0x186 : F32, Abcd, "degC", "Temperature, 1 min max";
+    TEMP_1M_STDDEV @ 0x188 : F32, Abcd, "degC", "Temperature, 1 min std dev";
+    TEMP_10M_MEAN @ 0x190 : F32, Abcd, "degC", "Temperature, 10 min mean";
+    TEMP_10M_MEDIAN @ 0x192 : F32, Abcd, "degC", "Temperature, 10 min median";
+    TEMP_10M_MIN @ 0x194 : F32, Abcd, "degC", "Temperature, 10 min min";
+    TEMP_10M_MAX @ 0x196 : F32, Abcd, "degC", "Temperature, 10 min max";
+    TEMP_10M_STDDEV @ 0x198 : F32, Abcd, "degC", "Temperature, 10 min std dev";
+    TEMP_1H_MEAN @ 0x1A0 : F32, Abcd, "degC", "Temperature, 1 h mean";
+    TEMP_1H_MEDIAN @ 0x1A2 : F32, Abcd, "degC", "Temperature, 1 h median";
+    TEMP_1H_MIN @ 0x1A4 : F32, Abcd, "degC", "Temperature, 1 h min";
+    TEMP_1H_MAX @ 0x1A6 : F32, Abcd, "degC", "Temperature, 1 h max";
+    TEMP_1H_STDDEV @ 0x1A8 : F32, Abcd, "degC", "Temperature, 1 h std dev";
+    TEMP_24H_MEAN @ 0x1B0 : F32, Abcd, "degC", "Temperature, 24 h mean";
+    TEMP_24H_MEDIAN @ 0x1B2 : F32, Abcd, "degC", "Temperature, 24 h median";
+    TEMP_24H_MIN @ 0x1B4 : F32, Abcd, "degC", "Temperature, 24 h min";
+    TEMP_24H_MAX @ 0x1B6 : F32, Abcd, "degC", "Temperature, 24 h max";
+    TEMP_24H_STDDEV @ 0x1B8 : F32, Abcd, "degC", "Temperature, 24 h std dev";
+
+    HUM_1M_MEAN @ 0x1C0 : F32, Abcd, "%RH", "Relative humidity, 1 min mean";
+    HUM_1M_MEDIAN @ 0x1C2 : F32, Abcd, "%RH", "Relative humidity, 1 min median";
+    HUM_1M_MIN @ 0x1C4 : F32, Abcd, "%RH", "Relative humidity, 1 min min";
+    HUM_1M_MAX @ 0x1C6 : F32, Abcd, "%RH", "Relative humidity, 1 min max";
+    HUM_1M_STDDEV @ 0x1C8 : F32, Abcd, "%RH", "Relative humidity, 1 min std dev";
+    HUM_10M_MEAN @ 0x1D0 : F32, Abcd, "%RH", "Relative humidity, 10 min mean";
+    HUM_10M_MEDIAN @ 0x1D2 : F32, Abcd, "%RH", "Relative humidity, 10 min median";
+    HUM_10M_MIN @ 0x1D4 : F32, Abcd, "%RH", "Relative humidity, 10 min min";
+    HUM_10M_MAX @ 0x1D6 : F32, Abcd, "%RH", "Relative humidity, 10 min max";
+    HUM_10M_STDDEV @ 0x1D8 : F32, Abcd, "%RH", "Relative humidity, 10 min std dev";
+    HUM_1H_MEAN @ 0x1E0 : F32, Abcd, "%RH", "Relative humidity, 1 h mean";
+    HUM_1H_MEDIAN @ 0x1E2 : F32, Abcd, "%RH", "Relative humidity, 1 h median";
+    HUM_1H_MIN @ 0x1E4 : F32, Abcd, "%RH", "Relative humidity, 1 h min";
+    HUM_1H_MAX @ 0x1E6 : F32, Abcd, "%RH", "Relative humidity, 1 h max";
+    HUM_1H_STDDEV @ 0x1E8 : F32, Abcd, "%RH", "Relative humidity, 1 h std dev";
+    HUM_24H_MEAN @ 0x1F0 : F32, Abcd, "%RH", "Relative humidity, 24 h mean";
+    HUM_24H_MEDIAN @ 0x1F2 : F32, Abcd, "%RH", "Relative humidity, 24 h median";
+    HUM_24H_MIN @ 0x1F4 : F32, Abcd, "%RH", "Relative humidity, 24 h min";
+    HUM_24H_MAX @ 0x1F6 : F32, Abcd, "%RH", "Relative humidity, 24 h max";
+    HUM_24H_STDDEV @ 0x1F8 : F32, Abcd, "%RH", "Relative humidity, 24 h std dev";
+}
+
+// in Sps30Reading order
+pub const SPS30_VALUES: [Point; 10] = [
+    SPS30_PM1_0, SPS30_PM2_5, SPS30_PM4_0, SPS30_PM10,
+    SPS30_NC0_5, SPS30_NC1_0, SPS30_NC2_5, SPS30_NC4_0, SPS30_NC10,
+    SPS30_TYPICAL_SIZE,
+];
+
+// by window, in measurements::STANDARD_WINDOWS order, then statistic
+pub const PM_2_5_ROLLING: [[Point; 5]; 4] = [
+    [PM_2_5_1M_MEAN, PM_2_5_1M_MEDIAN, PM_2_5_1M_MIN, PM_2_5_1M_MAX, PM_2_5_1M_STDDEV],
+    [PM_2_5_10M_MEAN, PM_2_5_10M_MEDIAN, PM_2_5_10M_MIN, PM_2_5_10M_MAX, PM_2_5_10M_STDDEV],
+    [PM_2_5_1H_MEAN, PM_2_5_1H_MEDIAN, PM_2_5_1H_MIN, PM_2_5_1H_MAX, PM_2_5_1H_STDDEV],
+    [PM_2_5_24H_MEAN, PM_2_5_24H_MEDIAN, PM_2_5_24H_MIN, PM_2_5_24H_MAX, PM_2_5_24H_STDDEV],
+];
+
+pub const PM_10_ROLLING: [[Point; 5]; 4] = [
+    [PM_10_1M_MEAN, PM_10_1M_MEDIAN, PM_10_1M_MIN, PM_10_1M_MAX, PM_10_1M_STDDEV],
+    [PM_10_10M_MEAN, PM_10_10M_MEDIAN, PM_10_10M_MIN, PM_10_10M_MAX, PM_10_10M_STDDEV],
+    [PM_10_1H_MEAN, PM_10_1H_MEDIAN, PM_10_1H_MIN, PM_10_1H_MAX, PM_10_1H_STDDEV],
+    [PM_10_24H_MEAN, PM_10_24H_MEDIAN, PM_10_24H_MIN, PM_10_24H_MAX, PM_10_24H_STDDEV],
+];
+
+pub const TEMP_ROLLING: [[Point; 5]; 4] = [
+    [TEMP_1M_MEAN, TEMP_1M_MEDIAN, TEMP_1M_MIN, TEMP_1M_MAX, TEMP_1M_STDDEV],
+    [TEMP_10M_MEAN, TEMP_10M_MEDIAN, TEMP_10M_MIN, TEMP_10M_MAX, TEMP_10M_STDDEV],
+    [TEMP_1H_MEAN, TEMP_1H_MEDIAN, TEMP_1H_MIN, TEMP_1H_MAX, TEMP_1H_STDDEV],
+    [TEMP_24H_MEAN, TEMP_24H_MEDIAN, TEMP_24H_MIN, TEMP_24H_MAX, TEMP_24H_STDDEV],
+];
+
+pub const HUM_ROLLING: [[Point; 5]; 4] = [
+    [HUM_1M_MEAN, HUM_1M_MEDIAN, HUM_1M_MIN, HUM_1M_MAX, HUM_1M_STDDEV],
+    [HUM_10M_MEAN, HUM_10M_MEDIAN, HUM_10M_MIN, HUM_10M_MAX, HUM_10M_STDDEV],
+    [HUM_1H_MEAN, HUM_1H_MEDIAN, HUM_1H_MIN, HUM_1H_MAX, HUM_1H_STDDEV],
+    [HUM_24H_MEAN, HUM_24H_MEDIAN, HUM_24H_MIN, HUM_24H_MAX, HUM_24H_STDDEV],
+];
+
+// human-readable map, one row per point, in address order
+pub fn dump(points: &[Point]) -> String {
+    let mut sorted = points.to_vec();
+    sorted.sort_by_key(|p| p.address);
+
+    let mut s = String::from("| Address | Name | Type | Order | Unit | Description |\n| --- | --- | --- | --- | --- | --- |\n");
+    for p in sorted {
+        let order = if p.width() > 1 { p.word_order.to_string() } else { String::new() };
+        s.push_str(&format!("| 0x{:02X} | {} | {} | {} | {} | {} |\n",
+            p.address, p.name, p.data_type.name(), order, p.unit, p.description));
+    }
+
+    s
+}
+
+// register storage behind the Modbus server
+//  only addresses belonging to a point in the bank exist; everything else is an
+//  illegal address to the server, and writes to points left out are ignored
+#[derive(Debug, Default)]
+pub struct RegisterBank {
+    points: Vec<Point>,
+    words: HashMap<u16, u16>,
+}
+
+impl RegisterBank {
+    // all points start out as 0
+    pub fn new(points: &[Point], word_order: Option<WordOrder>) -> Self {
+        let points: Vec<Point> = points.iter()
+            .map(|p| match word_order {
+                Some(order) if p.width() > 1 => Point { word_order: order, ..*p },
+                _ => *p,
+            })
+            .collect();
+
+        let mut words = HashMap::with_capacity(points.len() * 2);
+        for p in &points {
+            for a in p.address..p.address + p.width() {
+                words.insert(a, 0);
+            }
+        }
+
+        RegisterBank { points, words }
+    }
+
+    pub fn with_encoding(encoding: &Encoding) -> Self {
+        let mut points = INPUT_REGISTERS.to_vec();
+        points.extend_from_slice(ROLLING_REGISTERS);
+        if encoding.scaled_integers {
+            points.extend_from_slice(SCALED_INTEGER_REGISTERS);
+        }
+
+        RegisterBank::new(&points, encoding.word_order)
+    }
+
+    // the points as published, with the deployment word order applied
+    pub fn points(&self) -> &[Point] {
+        &self.points
+    }
+
+    pub fn set(&mut self, point: &Point, value: f64) -> Result<(), RegisterError> {
+        let Some(p) = self.lookup(point) else {
+            return Ok(());
+        };
+
+        let encoded = p.encode(value)?;
+        for (a, w) in (p.address..).zip(encoded) {
+            self.words.insert(a, w);
+        }
+
+        Ok(())
+    }
+
+    // reads a point back, for the alarms
+    pub fn get(&self, point: &Point) -> f64 {
+        let p = self.lookup(point).unwrap_or(*point);
+        let words: Vec<u16> = (p.address..p.address + p.width())
+            .map(|a| *self.words.get(&a).unwrap_or(&0))
+            .collect();
+
+        p.decode(&words)
+    }
+
+    // raw words for a Modbus read -- None if any address in the range isn't mapped
+    pub fn read(&self, addr: u16, cnt: u16) -> Option<Vec<u16>> {
+        (0..cnt)
+            .map(|i| addr.checked_add(i).and_then(|a| self.words.get(&a).copied()))
+            .collect()
+    }
+
+    fn lookup(&self, point: &Point) -> Option<Point> {
+        self.points.iter().find(|p| p.name == point.name).copied()
+    }
+}
+
+// NaN until the BME680 has a reading
+pub const BME680_VALUES: [Point; 4] = [GAS_RESISTANCE, BME680_TEMP, BME680_HUM, PRESSURE];
@@ -0,0 +1,144 @@
+#[cfg(test)]
+mod registers_tests {
+    use crate::registers::*;
+
+    #[test]
+    fn points_do_not_overlap() {
+        let mut sorted: Vec<Point> = INPUT_REGISTERS.iter().chain(SCALED_INTEGER_REGISTERS).chain(ROLLING_REGISTERS).copied().collect();
+        sorted.sort_by_key(|p| p.address);
+
+        for w in sorted.windows(2) {
+            assert!(w[0].address + w[0].width() <= w[1].address,
+                "{} overlaps {}", w[0].name, w[1].name);
+        }
+    }
+
+    #[test]
+    fn legacy_addresses_are_stable() {
+        assert_eq!(AQI.address, 0x00);
+        assert_eq!(PM_1_0.address, 0x01);
+        assert_eq!(PM_2_5.address, 0x02);
+        assert_eq!(PM_10.address, 0x03);
+        assert_eq!(AQI_TICK.address, 0x04);
+        assert_eq!(TEMP.address, 0x06);
+        assert_eq!(HUM.address, 0x08);
+        assert_eq!(TEMP_HUM_TICK.address, 0x0A);
+    }
+
+    #[test]
+    fn f32_is_big_endian_high_word_first() {
+        assert_eq!(TEMP.encode(-40.0).unwrap(), vec![0xC220, 0x0000]);
+        assert_eq!(TEMP.decode(&[0xC220, 0x0000]), -40.0);
+    }
+
+    #[test]
+    fn u32_round_trips() {
+        let words = AQI_TICK.encode(0x1234_5678 as f64).unwrap();
+        assert_eq!(words, vec![0x1234, 0x5678]);
+        assert_eq!(AQI_TICK.decode(&words), 0x1234_5678 as f64);
+    }
+
+    #[test]
+    fn every_word_order_round_trips() {
+        let cases = [
+            (WordOrder::Abcd, [0x1234, 0x5678]),
+            (WordOrder::Cdab, [0x5678, 0x1234]),
+            (WordOrder::Badc, [0x3412, 0x7856]),
+            (WordOrder::Dcba, [0x7856, 0x3412]),
+        ];
+        for (order, words) in cases {
+            let p = Point { word_order: order, ..AQI_TICK };
+            assert_eq!(p.encode(0x1234_5678 as f64).unwrap(), words.to_vec(), "{}", order);
+            assert_eq!(p.decode(&words), 0x1234_5678 as f64, "{}", order);
+        }
+    }
+
+    #[test]
+    fn parses_word_order_names() {
+        assert_eq!("cdab".parse::<WordOrder>().unwrap(), WordOrder::Cdab);
+        assert_eq!("DCBA".parse::<WordOrder>().unwrap(), WordOrder::Dcba);
+        assert!("ABDC".parse::<WordOrder>().is_err());
+    }
+
+    #[test]
+    fn deployment_word_order_applies_to_32_bit_points_only() {
+        let mut bank = RegisterBank::new(INPUT_REGISTERS, Some(WordOrder::Cdab));
+        bank.set(&TEMP, -40.0).unwrap();
+        bank.set(&AQI, 0x1234 as f64).unwrap();
+
+        assert_eq!(bank.read(TEMP.address, 2).unwrap(), vec![0x0000, 0xC220]);
+        assert_eq!(bank.read(AQI.address, 1).unwrap(), vec![0x1234]);
+        assert_eq!(bank.get(&TEMP), -40.0);
+    }
+
+    #[test]
+    fn scaled_integer_block_is_optional() {
+        let mut bank = RegisterBank::with_encoding(&Encoding::default());
+        bank.set(&TEMP_X10, 21.5).unwrap();
+        assert!(bank.read(TEMP_X10.address, 2).is_none());
+
+        let mut bank = RegisterBank::with_encoding(&Encoding { scaled_integers: true, ..Encoding::default() });
+        bank.set(&TEMP_X10, -21.5).unwrap();
+        bank.set(&HUM_X10, 45.25).unwrap();
+        assert_eq!(bank.read(TEMP_X10.address, 2).unwrap(), vec![(-215i16) as u16, 453]);
+    }
+
+    #[test]
+    fn scaled_integers_round_and_sign_extend() {
+        let p = Point { data_type: DataType::I16Scaled(10), ..AQI };
+        assert_eq!(p.encode(-12.34).unwrap(), vec![(-123i16) as u16]);
+        assert_eq!(p.decode(&[(-123i16) as u16]), -12.3);
+    }
+
+    #[test]
+    fn rejects_values_out_of_range() {
+        assert!(AQI.encode(-1.0).is_err());
+        assert!(AQI.encode(65536.0).is_err());
+        assert!(AQI.encode(f64::NAN).is_err());
+        assert!(AQI_TICK.encode(u32::MAX as f64 + 1.0).is_err());
+
+        let p = Point { data_type: DataType::I16Scaled(10), ..AQI };
+        assert!(p.encode(3276.8).is_err());
+        assert!(p.encode(-3276.8).is_err());
+    }
+
+    #[test]
+    fn scaled_integer_nan_is_0x8000() {
+        let p = Point { data_type: DataType::I16Scaled(10), ..AQI };
+        assert_eq!(p.encode(f64::NAN).unwrap(), vec![0x8000]);
+        assert!(p.decode(&[0x8000]).is_nan());
+    }
+
+    #[test]
+    fn bank_only_maps_declared_points() {
+        let mut bank = RegisterBank::new(INPUT_REGISTERS, None);
+        bank.set(&TEMP, 21.5).unwrap();
+
+        assert_eq!(bank.get(&TEMP), 21.5);
+        assert_eq!(bank.read(AQI.address, 12).unwrap().len(), 12);
+        assert!(bank.read(0x1E, 1).is_none());
+        assert!(bank.read(u16::MAX, 2).is_none());
+    }
+
+    #[test]
+    fn rolling_tables_follow_the_layout() {
+        let tables = [PM_2_5_ROLLING, PM_10_ROLLING, TEMP_ROLLING, HUM_ROLLING];
+        for (m, table) in tables.iter().enumerate() {
+            for (w, stats) in table.iter().enumerate() {
+                for (s, p) in stats.iter().enumerate() {
+                    assert_eq!(p.address as usize, 0x100 + m * 0x40 + w * 0x10 + s * 2, "{}", p.name);
+                }
+            }
+        }
+        assert_eq!(ROLLING_REGISTERS.len(), 80);
+    }
+
+    #[test]
+    fn dump_lists_every_point() {
+        let all: Vec<Point> = INPUT_REGISTERS.iter().chain(SCALED_INTEGER_REGISTERS).chain(ROLLING_REGISTERS).copied().collect();
+        let d = dump(&all);
+        for p in &all {
+            assert!(d.contains(p.name));
+        }
+    }
+}
@@ -0,0 +1,114 @@
+#[cfg(test)]
+mod tests;
+
+use std::{io, time::Duration};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio_modbus::{bytes::Bytes, server::Service, Exception, Request, SlaveId};
+
+use crate::modbus_crc::crc16;
+
+// Modbus RTU slave
+//
+//  tokio-modbus's RTU server replies to every frame it decodes, whoever it's
+//  addressed to -- on a shared RS-485 bus that means talking over the other
+//  slaves. So the line is framed here: a frame ends when the line has been
+//  quiet for 3.5 character times, and only a frame with a good CRC for our
+//  slave ID gets a reply. Broadcasts (slave 0) are carried out without one;
+//  everything else is left for the slave it's meant for. The PDUs are still
+//  decoded and encoded by tokio-modbus.
+
+const BROADCAST: SlaveId = 0;
+// address, PDU and CRC
+const MAX_FRAME: usize = 256;
+
+// the silence that ends a frame: 3.5 characters of 11 bits, fixed at 1.75 ms
+//  above 19200 baud
+pub fn frame_gap(baud: u32) -> Duration {
+    if baud > 19200 {
+        Duration::from_micros(1750)
+    } else {
+        Duration::from_micros(38_500_000 / baud as u64)
+    }
+}
+
+// the address and PDU of a frame for `slave` or a broadcast -- None for
+//  anything else, and for a frame that's too short, too long or fails its CRC
+pub fn receive(frame: &[u8], slave: SlaveId) -> Option<(SlaveId, Bytes)> {
+    if !(4..=MAX_FRAME).contains(&frame.len()) {
+        return None;
+    }
+    let (body, crc) = frame.split_at(frame.len() - 2);
+    if crc16(body).to_le_bytes() != crc {
+        return None;
+    }
+
+    let addr = body[0];
+    (addr == slave || addr == BROADCAST).then(|| (addr, Bytes::copy_from_slice(&body[1..])))
+}
+
+pub fn frame(slave: SlaveId, pdu: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(pdu.len() + 3);
+    frame.push(slave);
+    frame.extend_from_slice(pdu);
+    let crc = crc16(&frame);
+    frame.extend(crc.to_le_bytes());
+    frame
+}
+
+// the reply PDU to a request PDU, an exception if the service turns it down
+//  or it can't be decoded
+pub async fn handle<S>(service: &S, pdu: Bytes) -> Bytes
+where
+    S: Service<Request = Request<'static>>,
+{
+    let function = pdu[0];
+    let result = match Request::try_from(pdu) {
+        Ok(request) => service.call(request).await,
+        Err(_) => Err(Exception::IllegalDataValue),
+    };
+
+    match result {
+        Ok(response) => response.into(),
+        Err(e) => Bytes::from(vec![function | 0x80, e.into()]),
+    }
+}
+
+// until the port closes
+pub async fn serve<T, S>(mut port: T, slave: SlaveId, gap: Duration, service: S) -> io::Result<()>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+    S: Service<Request = Request<'static>>,
+{
+    let mut buf = [0u8; MAX_FRAME];
+    let mut received = Vec::with_capacity(2 * MAX_FRAME);
+    loop {
+        let n = port.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        received.clear();
+        received.extend_from_slice(&buf[..n]);
+
+        // the rest of the frame, until the line goes quiet -- noise that never
+        //  stops is kept short enough to be thrown away
+        loop {
+            match tokio::time::timeout(gap, port.read(&mut buf)).await {
+                Ok(Ok(0)) => return Ok(()),
+                Ok(Ok(n)) if received.len() <= MAX_FRAME => received.extend_from_slice(&buf[..n]),
+                Ok(Ok(_)) => {},
+                Ok(Err(e)) => return Err(e),
+                Err(_) => break,
+            }
+        }
+
+        let Some((addr, pdu)) = receive(&received, slave) else {
+            continue;
+        };
+        let reply = handle(&service, pdu).await;
+        if addr != BROADCAST {
+            port.write_all(&frame(slave, &reply)).await?;
+            port.flush().await?;
+        }
+    }
+}
@@ -0,0 +1,100 @@
+#[cfg(test)]
+mod rtu_tests {
+    use std::{
+        future,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_modbus::{bytes::Bytes, server::Service, Exception, Request, Response};
+
+    use crate::rtu::*;
+
+    // holding registers that all read 0x1234, and remembers what it's asked
+    #[derive(Clone, Default)]
+    struct Recorder {
+        requests: Arc<Mutex<Vec<Request<'static>>>>,
+    }
+
+    impl Service for Recorder {
+        type Request = Request<'static>;
+        type Future = future::Ready<Result<Response, Exception>>;
+
+        fn call(&self, req: Self::Request) -> Self::Future {
+            self.requests.lock().unwrap().push(req.clone());
+            future::ready(match req {
+                Request::ReadHoldingRegisters(_, cnt) => Ok(Response::ReadHoldingRegisters(vec![0x1234; cnt.into()])),
+                Request::WriteSingleRegister(addr, value) => Ok(Response::WriteSingleRegister(addr, value)),
+                _ => Err(Exception::IllegalFunction),
+            })
+        }
+    }
+
+    #[test]
+    fn gap_is_three_and_a_half_characters() {
+        assert_eq!(frame_gap(9600), Duration::from_micros(4010));
+        assert_eq!(frame_gap(19200), Duration::from_micros(2005));
+        assert_eq!(frame_gap(115200), Duration::from_micros(1750));
+    }
+
+    #[test]
+    fn frames_carry_a_crc() {
+        assert_eq!(frame(1, &[0x03, 0x00, 0x00, 0x00, 0x01]), vec![0x01, 0x03, 0x00, 0x00, 0x00, 0x01, 0x84, 0x0A]);
+    }
+
+    #[test]
+    fn only_our_frames_and_broadcasts_are_taken() {
+        let pdu = [0x03, 0x00, 0x00, 0x00, 0x01];
+        assert_eq!(receive(&frame(7, &pdu), 7), Some((7, Bytes::copy_from_slice(&pdu))));
+        assert_eq!(receive(&frame(0, &pdu), 7), Some((0, Bytes::copy_from_slice(&pdu))));
+        assert_eq!(receive(&frame(8, &pdu), 7), None);
+
+        let mut corrupt = frame(7, &pdu);
+        corrupt[3] ^= 1;
+        assert_eq!(receive(&corrupt, 7), None);
+        assert_eq!(receive(&[7, 0x03, 0x00], 7), None);
+        assert_eq!(receive(&frame(7, &[0; 300]), 7), None);
+    }
+
+    #[tokio::test]
+    async fn exceptions_for_refused_and_malformed_requests() {
+        let service = Recorder::default();
+        let reply = handle(&service, Bytes::from_static(&[0x03, 0x00, 0x00, 0x00, 0x02])).await;
+        assert_eq!(&reply[..], &[0x03, 0x04, 0x12, 0x34, 0x12, 0x34]);
+
+        let reply = handle(&service, Bytes::from_static(&[0x01, 0x00, 0x00, 0x00, 0x01])).await;
+        assert_eq!(&reply[..], &[0x81, 0x01]);
+
+        let reply = handle(&service, Bytes::from_static(&[0x03, 0x00])).await;
+        assert_eq!(&reply[..], &[0x83, 0x03]);
+    }
+
+    #[tokio::test]
+    async fn silent_for_other_slaves_and_broadcasts() {
+        let service = Recorder::default();
+        let (mut master, port) = tokio::io::duplex(1024);
+        let server = tokio::spawn(serve(port, 7, Duration::from_millis(5), service.clone()));
+        let pause = || tokio::time::sleep(Duration::from_millis(50));
+
+        // another slave's request and its reply, then a broadcast write
+        master.write_all(&frame(8, &[0x03, 0x00, 0x00, 0x00, 0x01])).await.unwrap();
+        pause().await;
+        master.write_all(&frame(8, &[0x03, 0x02, 0xAB, 0xCD])).await.unwrap();
+        pause().await;
+        master.write_all(&frame(0, &[0x06, 0x00, 0x01, 0x00, 0x2A])).await.unwrap();
+        pause().await;
+        master.write_all(&frame(7, &[0x03, 0x00, 0x00, 0x00, 0x01])).await.unwrap();
+
+        // the first thing back is the answer to the last
+        let mut reply = [0u8; 7];
+        master.read_exact(&mut reply).await.unwrap();
+        assert_eq!(reply.to_vec(), frame(7, &[0x03, 0x02, 0x12, 0x34]));
+
+        let requests = service.requests.lock().unwrap().clone();
+        assert_eq!(requests, vec![Request::WriteSingleRegister(1, 42), Request::ReadHoldingRegisters(0, 1)]);
+
+        drop(master);
+        server.await.unwrap().unwrap();
+    }
+}
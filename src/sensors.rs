@@ -0,0 +1,297 @@
+#[cfg(test)]
+mod tests;
+
+pub mod am2302;
+pub mod bme280;
+pub mod bme680;
+pub mod mhz19;
+pub mod pms5003;
+pub mod scd30;
+pub mod scd4x;
+pub mod sds011;
+pub mod senseair_s8;
+pub mod sgp4x;
+pub mod sht;
+pub mod simulated;
+pub mod sps30;
+
+use std::{
+    fmt,
+    io,
+    ops::RangeInclusive,
+    thread,
+    time::Duration,
+};
+
+use rppal::i2c::{self, I2c};
+
+use crate::bus::{Bus, Event, Quality};
+use crate::config::Config;
+use crate::payload::sensirion::{self, I2cError};
+use crate::serial::SerialError;
+
+// Sensor drivers
+//
+//  Every sensor is driven the same way, on its own thread by `run`: `init` to
+//  open and set it up, then `sample` over and over, publishing whatever each
+//  pass produces on the bus. A failed sample means the sensor is set up again
+//  from scratch. Drivers pace themselves -- `sample` blocks until there is
+//  something to report, for a read timeout or a sample interval.
+//
+//  Each sensor's readings come with a quality word, published whenever it
+//  changes: `run` knows about failed reads and readings gone stale, the driver
+//  about warm-up times and measuring ranges.
+//
+//  Which sensors a unit has is configuration: [sensors] enabled lists driver
+//  names from DRIVERS, and each driver reads its own section. There can be only
+//  one sensor of each kind -- two particulate sensors would take turns driving
+//  the same registers.
+
+pub trait Sensor: Send {
+    fn metadata(&self) -> Metadata;
+
+    // called before the first sample, and again after any sample fails
+    fn init(&mut self) -> Result<(), SensorError>;
+
+    // one pass -- the readings and status to publish, possibly none
+    fn sample(&mut self) -> Result<Vec<Event>, SensorError>;
+
+    fn health(&self) -> Health;
+
+    // the longest a reading stands for -- without a newer one after this, it's
+    //  reported stale
+    fn stale_after(&self) -> Duration;
+
+    // the driver's own rating of its latest reading: warming up, out of range
+    //  or valid
+    fn quality(&self) -> Quality {
+        Quality::Valid
+    }
+
+    // everything published on the bus, before each sample; for drivers that
+    //  compensate with another sensor's readings
+    fn observe(&mut self, _event: &Event) {}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metadata {
+    pub model: &'static str,
+    // device path, bus address or pin
+    pub port: String,
+}
+
+impl fmt::Display for Metadata {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} on {}", self.model, self.port)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Health {
+    pub readings: u64,
+    // failed reads and bad frames
+    pub errors: u64,
+    // None before the first reading
+    pub since_last: Option<Duration>,
+}
+
+impl fmt::Display for Health {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} readings, {} errors", self.readings, self.errors)?;
+        match self.since_last {
+            Some(d) => write!(f, ", last reading {}s ago", d.as_secs()),
+            None => write!(f, ", no reading yet"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SensorError {
+    // sample before a successful init
+    NotReady,
+    Serial(SerialError),
+    Io(io::Error),
+    I2c(i2c::Error),
+    // the sensor answered, but not with anything usable
+    Protocol(String),
+}
+
+impl fmt::Display for SensorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SensorError::NotReady => write!(f, "not initialised"),
+            SensorError::Serial(e) => write!(f, "{}", e),
+            SensorError::Io(e) => write!(f, "{}", e),
+            SensorError::I2c(e) => write!(f, "I2C: {}", e),
+            SensorError::Protocol(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SensorError {}
+
+impl From<SerialError> for SensorError {
+    fn from(e: SerialError) -> Self {
+        SensorError::Serial(e)
+    }
+}
+
+impl From<io::Error> for SensorError {
+    fn from(e: io::Error) -> Self {
+        SensorError::Io(e)
+    }
+}
+
+impl From<i2c::Error> for SensorError {
+    fn from(e: i2c::Error) -> Self {
+        SensorError::I2c(e)
+    }
+}
+
+impl From<I2cError> for SensorError {
+    fn from(e: I2cError) -> Self {
+        SensorError::Protocol(e.to_string())
+    }
+}
+
+// what a sensor measures
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Particulate,
+    Climate,
+    Co2,
+    Gas,
+}
+
+type Constructor = fn(&Config) -> Box<dyn Sensor>;
+
+// every driver, by the name used in [sensors] enabled
+const DRIVERS: &[(&str, Kind, Constructor)] = &[
+    ("pms5003", Kind::Particulate, |c| Box::new(pms5003::Pms5003::new(&c.pms5003))),
+    ("sds011", Kind::Particulate, |c| Box::new(sds011::Sds011::new(&c.sds011))),
+    ("sps30", Kind::Particulate, |c| Box::new(sps30::Sps30::new(&c.sps30))),
+    ("am2302", Kind::Climate, |c| Box::new(am2302::Am2302::new(am2302::Model::Am2302, &c.am2302))),
+    ("dht22", Kind::Climate, |c| Box::new(am2302::Am2302::new(am2302::Model::Dht22, &c.am2302))),
+    ("dht11", Kind::Climate, |c| Box::new(am2302::Am2302::new(am2302::Model::Dht11, &c.am2302))),
+    ("sht3x", Kind::Climate, |c| Box::new(sht::Sht::new(sht::Model::Sht3x, &c.climate))),
+    ("sht4x", Kind::Climate, |c| Box::new(sht::Sht::new(sht::Model::Sht4x, &c.climate))),
+    ("bme280", Kind::Climate, |c| Box::new(bme280::Bme280::new(&c.climate))),
+    ("s8", Kind::Co2, |c| Box::new(senseair_s8::SenseairS8::new(&c.co2))),
+    ("mhz19", Kind::Co2, |c| Box::new(mhz19::Mhz19::new(&c.co2))),
+    ("scd30", Kind::Co2, |c| Box::new(scd30::Scd30::new(&c.co2))),
+    ("scd4x", Kind::Co2, |c| Box::new(scd4x::Scd4x::new(&c.co2))),
+    ("sgp40", Kind::Gas, |c| Box::new(sgp4x::Sgp4x::new(sgp4x::Model::Sgp40, &c.gas))),
+    ("sgp41", Kind::Gas, |c| Box::new(sgp4x::Sgp4x::new(sgp4x::Model::Sgp41, &c.gas))),
+    ("bme680", Kind::Gas, |c| Box::new(bme680::Bme680::new(&c.gas))),
+    ("simulated-pm", Kind::Particulate, |c| Box::new(simulated::Simulated::new(simulated::Model::Particulate, &c.simulated))),
+    ("simulated-climate", Kind::Climate, |c| Box::new(simulated::Simulated::new(simulated::Model::Climate, &c.simulated))),
+];
+
+// None for names that aren't drivers
+pub fn kind_of(name: &str) -> Option<Kind> {
+    DRIVERS.iter().find(|(n, _, _)| *n == name).map(|(_, k, _)| *k)
+}
+
+pub fn driver_names() -> Vec<&'static str> {
+    DRIVERS.iter().map(|(n, _, _)| *n).collect()
+}
+
+// the enabled sensors, in the order given -- validate() has already rejected
+//  unknown names
+pub fn from_config(config: &Config) -> Vec<(Kind, Box<dyn Sensor>)> {
+    config.sensors.enabled.iter()
+        .filter_map(|name| DRIVERS.iter().find(|(n, _, _)| n == name))
+        .map(|(_, kind, new)| (*kind, new(config)))
+        .collect()
+}
+
+// a reading is stale once three in a row are missing, and never sooner than
+//  MIN_STALE
+const MIN_STALE: Duration = Duration::from_secs(30);
+
+pub fn stale_after(interval: Duration) -> Duration {
+    (interval * 3).max(MIN_STALE)
+}
+
+// Valid, or OutOfRange if any of the values is beyond the sensor's measuring
+//  range
+pub fn range_quality(range: RangeInclusive<f64>, values: &[f64]) -> Quality {
+    if values.iter().all(|v| range.contains(v)) {
+        Quality::Valid
+    } else {
+        Quality::OutOfRange
+    }
+}
+
+// the quality after a pass without errors, from the sensor's health and its
+//  own rating
+pub fn rate(health: &Health, stale_after: Duration, rated: Quality) -> Quality {
+    match (health.since_last, rated) {
+        (_, Quality::WarmingUp) => Quality::WarmingUp,
+        (None, _) => Quality::NoData,
+        (Some(age), _) if age > stale_after => Quality::Stale,
+        (Some(_), rated) => rated,
+    }
+}
+
+// a Sensirion I2C command and its response: `wait` for the sensor to execute
+//  it, then `words` words read back and checked
+pub fn sensirion_exchange(i2c: &mut I2c, command: &[u8], wait: Duration, words: usize) -> Result<Vec<u16>, SensorError> {
+    i2c.write(command)?;
+    thread::sleep(wait);
+    if words == 0 {
+        return Ok(vec![]);
+    }
+
+    let mut raw = vec![0; sensirion::response_size(words)];
+    i2c.read(&mut raw)?;
+    Ok(sensirion::words(&raw)?)
+}
+
+// how long to wait before trying a sensor that wouldn't initialise again
+const RETRY: Duration = Duration::from_secs(10);
+
+// drives one sensor forever -- a missing or failing sensor is reported and
+//  retried rather than killing the thread
+pub fn run(mut sensor: Box<dyn Sensor>, kind: Kind, bus: Bus<Event>) {
+    let events = bus.subscribe();
+    let mut ready = false;
+
+    let mut quality = Quality::NoData;
+    let mut report = |q: Quality| {
+        if q != quality {
+            quality = q;
+            bus.publish(Event::Quality(kind, q));
+        }
+    };
+
+    loop {
+        for e in events.try_iter() {
+            sensor.observe(&e);
+        }
+
+        if !ready {
+            if let Err(e) = sensor.init() {
+                eprintln!("{}: {}", sensor.metadata(), e);
+                report(Quality::SensorError);
+                thread::sleep(RETRY);
+                continue;
+            }
+            ready = true;
+        }
+
+        match sensor.sample() {
+            Ok(out) => {
+                for e in out {
+                    bus.publish(e);
+                }
+                report(rate(&sensor.health(), sensor.stale_after(), sensor.quality()));
+            },
+            Err(e) => {
+                eprintln!("{}: {} ({})", sensor.metadata(), e, sensor.health());
+                report(Quality::SensorError);
+                ready = false;
+            },
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::{
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use gpio_am2302_rs::try_read;
+
+use crate::bus::{Climate, ClimateHealth, Event, Quality};
+use crate::climate::{Plausibility, Verdict};
+use crate::config::Am2302Config;
+use crate::sensors::{Health, Metadata, Sensor, SensorError};
+
+// AM2302 (DHT22) and DHT11 temperature and humidity, bit-banged on a GPIO pin
+//
+//  One read every interval. A failed read -- a timeout or a bad checksum -- is
+//  counted and tried again up to [am2302] retries times; the sensor wants 2 s
+//  between reads. A reading that gets through still has to pass the
+//  plausibility filter in climate.rs. Every pass publishes the read counts as a
+//  ClimateHealth; after stale_secs without a good reading the values are
+//  reported stale.
+//
+//  gpio_am2302_rs decodes the DHT22's bytes: tenths of a %RH and of a degC,
+//  big endian, the top bit the temperature's sign. The DHT11 sends the same
+//  five bytes over the same wire protocol, but as integer and tenths bytes --
+//  so its readings are taken apart again and decoded the DHT11's way.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Model {
+    Am2302,
+    Dht22,
+    Dht11,
+}
+
+// a DHT11 reading that went through the DHT22 decoding, as degC and %
+pub fn dht11_from_dht22(temperature: f32, humidity: f32) -> (f64, f64) {
+    let [rh_int, rh_dec] = ((humidity * 10.0).round() as u16).to_be_bytes();
+    let [t_int, t_dec] = ((temperature.abs() * 10.0).round() as u16).to_be_bytes();
+
+    // below 0 degC newer DHT11s set the top bit of the tenths instead
+    let negative = temperature < 0.0 || t_dec & 0x80 != 0;
+    let t = t_int as f64 + (t_dec & 0x7F) as f64 / 10.0;
+
+    (if negative { -t } else { t }, rh_int as f64 + rh_dec as f64 / 10.0)
+}
+
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+pub struct Am2302 {
+    model: Model,
+    config: Am2302Config,
+    filter: Plausibility,
+    next: Instant,
+    health: Health,
+    rejected: u64,
+    last: Option<Instant>,
+    quality: Quality,
+}
+
+impl Am2302 {
+    pub fn new(model: Model, config: &Am2302Config) -> Self {
+        // the datasheet measuring ranges
+        let (temperature, humidity) = match model {
+            Model::Am2302 | Model::Dht22 => (-40.0..=80.0, 0.0..=100.0),
+            Model::Dht11 => (0.0..=50.0, 0.0..=100.0),
+        };
+        Am2302 {
+            model,
+            config: config.clone(),
+            filter: Plausibility::new(temperature, humidity, config.max_temp_step, config.max_humidity_step),
+            next: Instant::now(),
+            health: Health::default(),
+            rejected: 0,
+            last: None,
+            quality: Quality::Valid,
+        }
+    }
+
+    // degC and %RH, or None after every try failed
+    fn read(&mut self) -> Option<(f64, f64)> {
+        for attempt in 0..=self.config.retries {
+            if attempt > 0 {
+                thread::sleep(RETRY_DELAY);
+            }
+            match try_read(self.config.gpio) {
+                Ok(reading) => return Some(match self.model {
+                    Model::Dht11 => dht11_from_dht22(reading.temperature, reading.humidity),
+                    Model::Am2302 | Model::Dht22 => (reading.temperature as f64, reading.humidity as f64),
+                }),
+                Err(_) => self.health.errors += 1,
+            }
+        }
+        None
+    }
+
+    fn stale(&self) -> bool {
+        self.last.is_some_and(|t| t.elapsed() > self.stale_after())
+    }
+}
+
+impl Sensor for Am2302 {
+    fn metadata(&self) -> Metadata {
+        let model = match self.model {
+            Model::Am2302 => "AM2302",
+            Model::Dht22 => "DHT22",
+            Model::Dht11 => "DHT11",
+        };
+        Metadata { model, port: format!("GPIO {}", self.config.gpio) }
+    }
+
+    // nothing to open -- every read sets the pin up from scratch
+    fn init(&mut self) -> Result<(), SensorError> {
+        Ok(())
+    }
+
+    fn sample(&mut self) -> Result<Vec<Event>, SensorError> {
+        if let Some(wait) = self.next.checked_duration_since(Instant::now()) {
+            thread::sleep(wait);
+        }
+        self.next = Instant::now() + Duration::from_secs(self.config.interval_secs);
+
+        // the last good reading is too old to judge the next one by
+        if self.stale() {
+            self.filter.reset();
+        }
+
+        let mut events = vec![];
+        if let Some((temperature, humidity)) = self.read() {
+            match self.filter.check(temperature, humidity) {
+                Verdict::Accept => {
+                    self.health.readings += 1;
+                    self.last = Some(Instant::now());
+                    self.quality = Quality::Valid;
+                    events.push(Event::Climate(Climate { temperature, humidity, pressure: None, at: SystemTime::now() }));
+                },
+                Verdict::OutOfRange => {
+                    self.rejected += 1;
+                    self.quality = Quality::OutOfRange;
+                },
+                Verdict::Jump => self.rejected += 1,
+            }
+        }
+
+        events.push(Event::ClimateHealth(ClimateHealth { health: self.health(), rejected: self.rejected }));
+        Ok(events)
+    }
+
+    fn health(&self) -> Health {
+        Health { since_last: self.last.map(|t| t.elapsed()), ..self.health }
+    }
+
+    fn stale_after(&self) -> Duration {
+        match self.config.stale_secs {
+            0 => Duration::MAX,
+            secs => Duration::from_secs(secs),
+        }
+    }
+
+    // until the next reading is accepted
+    fn quality(&self) -> Quality {
+        self.quality
+    }
+}
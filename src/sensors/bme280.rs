@@ -0,0 +1,140 @@
+use std::{
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use rppal::i2c::I2c;
+
+use crate::bus::{Climate, Event};
+use crate::config::ClimateConfig;
+use crate::payload::bme280::{self, Calibration, DATA_SIZE};
+use crate::sensors::{self, Health, Metadata, Sensor, SensorError};
+
+// Bosch BME280 on I2C
+//
+//  In forced mode: each pass starts one measurement and reads it back once the
+//  sensor is done, and the sensor sleeps in between.
+
+// x1 oversampling of all three takes under 10 ms
+const MEASURE_TIME: Duration = Duration::from_millis(10);
+const RESULT_TIMEOUT: Duration = Duration::from_millis(100);
+const RESET_TIME: Duration = Duration::from_millis(5);
+
+struct Device {
+    i2c: I2c,
+    calibration: Calibration,
+}
+
+impl Device {
+    fn read(&self, register: u8, buf: &mut [u8]) -> Result<(), SensorError> {
+        Ok(self.i2c.write_read(&[register], buf)?)
+    }
+
+    fn write(&self, register: u8, value: u8) -> Result<(), SensorError> {
+        Ok(self.i2c.smbus_write_byte(register, value)?)
+    }
+}
+
+pub struct Bme280 {
+    config: ClimateConfig,
+    device: Option<Device>,
+    next: Instant,
+    health: Health,
+    last: Option<Instant>,
+}
+
+impl Bme280 {
+    pub fn new(config: &ClimateConfig) -> Self {
+        Bme280 { config: config.clone(), device: None, next: Instant::now(), health: Health::default(), last: None }
+    }
+
+    fn address(&self) -> u16 {
+        self.config.address.unwrap_or(bme280::I2C_ADDRESSES[0])
+    }
+
+    fn open(&self) -> Result<Device, SensorError> {
+        let mut i2c = I2c::with_bus(self.config.i2c_bus)?;
+        i2c.set_slave_address(self.address())?;
+        let mut device = Device { i2c, calibration: Calibration::default() };
+
+        let mut id = [0; 1];
+        device.read(bme280::REG_CHIP_ID, &mut id)?;
+        if id[0] != bme280::CHIP_ID {
+            return Err(SensorError::Protocol(format!("chip id 0x{:02X}, not a BME280", id[0])));
+        }
+        device.write(bme280::REG_RESET, bme280::RESET)?;
+        thread::sleep(RESET_TIME);
+
+        let mut c1 = [0; bme280::CALIB_1.1];
+        let mut c2 = [0; bme280::CALIB_2.1];
+        device.read(bme280::CALIB_1.0, &mut c1)?;
+        device.read(bme280::CALIB_2.0, &mut c2)?;
+        device.calibration = Calibration::parse(&c1, &c2);
+
+        device.write(bme280::REG_CTRL_HUM, bme280::CTRL_HUM)?;
+        Ok(device)
+    }
+
+    // degC, %, hPa
+    fn measure(&self) -> Result<(f64, f64, f64), SensorError> {
+        let device = self.device.as_ref().ok_or(SensorError::NotReady)?;
+        device.write(bme280::REG_CTRL_MEAS, bme280::CTRL_MEAS_FORCED)?;
+        thread::sleep(MEASURE_TIME);
+
+        let deadline = Instant::now() + RESULT_TIMEOUT;
+        let mut status = [0; 1];
+        loop {
+            device.read(bme280::REG_STATUS, &mut status)?;
+            if status[0] & bme280::MEASURING == 0 {
+                break;
+            }
+            if Instant::now() >= deadline {
+                return Err(SensorError::Protocol("BME280 measurement didn't finish".to_string()));
+            }
+            thread::sleep(MEASURE_TIME);
+        }
+
+        let mut data = [0; DATA_SIZE];
+        device.read(bme280::REG_DATA, &mut data)?;
+        Ok(device.calibration.reading(&data))
+    }
+}
+
+impl Sensor for Bme280 {
+    fn metadata(&self) -> Metadata {
+        Metadata { model: "BME280", port: format!("I2C bus {} at 0x{:02X}", self.config.i2c_bus, self.address()) }
+    }
+
+    fn init(&mut self) -> Result<(), SensorError> {
+        self.device = None;
+        self.device = Some(self.open()?);
+        Ok(())
+    }
+
+    fn sample(&mut self) -> Result<Vec<Event>, SensorError> {
+        if let Some(wait) = self.next.checked_duration_since(Instant::now()) {
+            thread::sleep(wait);
+        }
+        self.next = Instant::now() + Duration::from_secs(self.config.interval_secs);
+
+        match self.measure() {
+            Ok((temperature, humidity, pressure)) => {
+                self.health.readings += 1;
+                self.last = Some(Instant::now());
+                Ok(vec![Event::Climate(Climate { temperature, humidity, pressure: Some(pressure), at: SystemTime::now() })])
+            },
+            Err(e) => {
+                self.health.errors += 1;
+                Err(e)
+            },
+        }
+    }
+
+    fn health(&self) -> Health {
+        Health { since_last: self.last.map(|t| t.elapsed()), ..self.health }
+    }
+
+    fn stale_after(&self) -> Duration {
+        sensors::stale_after(Duration::from_secs(self.config.interval_secs))
+    }
+}
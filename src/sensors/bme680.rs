@@ -0,0 +1,166 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use rppal::i2c::I2c;
+
+use crate::bus::{Event, GasReading};
+use crate::config::GasConfig;
+use crate::payload::bme680::{self, Bme680Reading, Calibration, Field, Variant, FIELD_SIZE};
+use crate::sensors::{self, Health, Metadata, Sensor, SensorError};
+
+// Bosch BME680 / BME688 on I2C
+//
+//  In forced mode: each pass starts one measurement -- temperature, pressure
+//  and humidity, then the hot plate heated for heater_ms and its resistance
+//  measured -- and reads it back once it's done. The heater setting depends on
+//  the temperature of the air, so it's worked out again from each reading for
+//  the next.
+
+// temperature, pressure and humidity at x1 oversampling take under 10 ms; the
+//  heating time comes on top
+const MEASURE_TIME: Duration = Duration::from_millis(10);
+// how long past that to keep asking for the result
+const RESULT_TIMEOUT: Duration = Duration::from_millis(200);
+const RESET_TIME: Duration = Duration::from_millis(10);
+// for the first heater setting, before there's a reading
+const AMBIENT_GUESS: f64 = 25.0;
+
+struct Device {
+    i2c: I2c,
+    variant: Variant,
+    calibration: Calibration,
+}
+
+impl Device {
+    fn read(&self, register: u8, buf: &mut [u8]) -> Result<(), SensorError> {
+        Ok(self.i2c.write_read(&[register], buf)?)
+    }
+
+    fn write(&self, register: u8, value: u8) -> Result<(), SensorError> {
+        Ok(self.i2c.smbus_write_byte(register, value)?)
+    }
+}
+
+pub struct Bme680 {
+    config: GasConfig,
+    device: Option<Device>,
+    ambient: f64,
+    next: Instant,
+    health: Health,
+    last: Option<Instant>,
+}
+
+impl Bme680 {
+    pub fn new(config: &GasConfig) -> Self {
+        Bme680 {
+            config: config.clone(),
+            device: None,
+            ambient: AMBIENT_GUESS,
+            next: Instant::now(),
+            health: Health::default(),
+            last: None,
+        }
+    }
+
+    fn open(&self) -> Result<Device, SensorError> {
+        let mut i2c = I2c::with_bus(self.config.i2c_bus)?;
+        i2c.set_slave_address(self.config.address)?;
+        let mut device = Device { i2c, variant: Variant::Bme680, calibration: Calibration::default() };
+
+        let mut id = [0; 1];
+        device.read(bme680::REG_CHIP_ID, &mut id)?;
+        if id[0] != bme680::CHIP_ID {
+            return Err(SensorError::Protocol(format!("chip id 0x{:02X}, not a BME680 or BME688", id[0])));
+        }
+        device.write(bme680::REG_RESET, bme680::RESET)?;
+        thread::sleep(RESET_TIME);
+
+        device.read(bme680::REG_VARIANT_ID, &mut id)?;
+        device.variant = Variant::from_id(id[0]);
+
+        let mut c1 = [0; bme680::COEFF_1.1];
+        let mut c2 = [0; bme680::COEFF_2.1];
+        let mut c3 = [0; bme680::COEFF_3.1];
+        device.read(bme680::COEFF_1.0, &mut c1)?;
+        device.read(bme680::COEFF_2.0, &mut c2)?;
+        device.read(bme680::COEFF_3.0, &mut c3)?;
+        device.calibration = Calibration::parse(&c1, &c2, &c3);
+
+        device.write(bme680::REG_CTRL_HUM, bme680::CTRL_HUM)?;
+        device.write(bme680::REG_GAS_WAIT_0, bme680::gas_wait(self.config.heater_ms))?;
+        device.write(bme680::REG_CTRL_GAS_1, device.variant.run_gas())?;
+        Ok(device)
+    }
+
+    fn measure(&mut self) -> Result<Option<Bme680Reading>, SensorError> {
+        let device = self.device.as_ref().ok_or(SensorError::NotReady)?;
+
+        let heat = device.calibration.heater_resistance(self.config.heater_temp, self.ambient);
+        device.write(bme680::REG_RES_HEAT_0, heat)?;
+        device.write(bme680::REG_CTRL_MEAS, bme680::CTRL_MEAS_FORCED)?;
+        thread::sleep(MEASURE_TIME + Duration::from_millis(self.config.heater_ms as u64));
+
+        let deadline = Instant::now() + RESULT_TIMEOUT;
+        let mut raw = [0; FIELD_SIZE];
+        loop {
+            device.read(bme680::REG_FIELD_0, &mut raw)?;
+            let field = Field::parse(&raw, device.variant);
+            if field.new_data {
+                let r = field.reading(&device.calibration, device.variant);
+                self.ambient = r.temperature;
+                return Ok(Some(r));
+            }
+            if Instant::now() >= deadline {
+                return Ok(None);
+            }
+            thread::sleep(MEASURE_TIME);
+        }
+    }
+}
+
+impl Sensor for Bme680 {
+    fn metadata(&self) -> Metadata {
+        let model = match self.device.as_ref().map(|d| d.variant) {
+            Some(Variant::Bme688) => "BME688",
+            _ => "BME680",
+        };
+        Metadata { model, port: format!("I2C bus {} at 0x{:02X}", self.config.i2c_bus, self.config.address) }
+    }
+
+    fn init(&mut self) -> Result<(), SensorError> {
+        self.device = None;
+        self.device = Some(self.open()?);
+        Ok(())
+    }
+
+    fn sample(&mut self) -> Result<Vec<Event>, SensorError> {
+        if let Some(wait) = self.next.checked_duration_since(Instant::now()) {
+            thread::sleep(wait);
+        }
+        self.next = Instant::now() + Duration::from_secs(self.config.interval_secs);
+
+        match self.measure() {
+            Ok(Some(r)) => {
+                self.health.readings += 1;
+                self.last = Some(Instant::now());
+                Ok(vec![Event::Voc(GasReading::Bme680(r))])
+            },
+            // missed this one; the next pass starts another
+            Ok(None) => Ok(vec![]),
+            Err(e) => {
+                self.health.errors += 1;
+                Err(e)
+            },
+        }
+    }
+
+    fn health(&self) -> Health {
+        Health { since_last: self.last.map(|t| t.elapsed()), ..self.health }
+    }
+
+    fn stale_after(&self) -> Duration {
+        sensors::stale_after(Duration::from_secs(self.config.interval_secs))
+    }
+}
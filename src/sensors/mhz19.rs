@@ -0,0 +1,120 @@
+use std::{
+    io::Write,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::bus::{Co2Sensor, Event, Quality};
+use crate::co2::Compensator;
+use crate::config::Co2Config;
+use crate::payload::mhz19::{self, Mhz19Error, FRAME_SIZE};
+use crate::sensors::{self, Health, Metadata, Sensor, SensorError};
+use crate::serial::SerialPort;
+
+// Winsen MH-Z19 on a UART
+//
+//  One read command every interval. The sensor's automatic baseline correction
+//  is left as it is -- on from the factory, which suits rooms that are aired out
+//  now and then. Its readings are off for the first 3 minutes after power on,
+//  taken to be when airq starts.
+
+const RESPONSE_TIMEOUT: Duration = Duration::from_millis(500);
+const PREHEAT: Duration = Duration::from_secs(180);
+// ppm, the factory setting
+const RANGE: f64 = 5000.0;
+
+pub struct Mhz19 {
+    config: Co2Config,
+    port: Option<SerialPort>,
+    compensator: Compensator,
+    next: Instant,
+    health: Health,
+    last: Option<Instant>,
+    started: Instant,
+    quality: Quality,
+}
+
+impl Mhz19 {
+    pub fn new(config: &Co2Config) -> Self {
+        Mhz19 {
+            config: config.clone(),
+            port: None,
+            compensator: Compensator::new(config.compensate),
+            next: Instant::now(),
+            health: Health::default(),
+            last: None,
+            started: Instant::now(),
+            quality: Quality::Valid,
+        }
+    }
+
+    fn read_co2(&mut self) -> Result<u16, SensorError> {
+        let f = self.port.as_mut().ok_or(SensorError::NotReady)?;
+        f.write_all(&mhz19::co2_request())?;
+        f.flush()?;
+
+        let mut frame = [0; FRAME_SIZE];
+        if f.read_within(&mut frame, RESPONSE_TIMEOUT)? < FRAME_SIZE {
+            return Err(Mhz19Error::Unexpected.into());
+        }
+        Ok(mhz19::co2_response(&frame)?)
+    }
+}
+
+impl From<Mhz19Error> for SensorError {
+    fn from(e: Mhz19Error) -> Self {
+        SensorError::Protocol(e.to_string())
+    }
+}
+
+impl Sensor for Mhz19 {
+    fn metadata(&self) -> Metadata {
+        Metadata { model: "MH-Z19", port: self.config.device.clone() }
+    }
+
+    fn init(&mut self) -> Result<(), SensorError> {
+        self.port = None;
+        self.port = Some(SerialPort::open(&self.config.device, mhz19::BAUD, RESPONSE_TIMEOUT)?);
+        println!("{} open at {} baud, 8N1", self.config.device, mhz19::BAUD);
+        Ok(())
+    }
+
+    fn sample(&mut self) -> Result<Vec<Event>, SensorError> {
+        if let Some(wait) = self.next.checked_duration_since(Instant::now()) {
+            thread::sleep(wait);
+        }
+        self.next = Instant::now() + Duration::from_secs(self.config.interval_secs);
+
+        match self.read_co2() {
+            Ok(ppm) => {
+                self.health.readings += 1;
+                self.last = Some(Instant::now());
+                self.quality = sensors::range_quality(0.0..=RANGE, &[ppm as f64]);
+                Ok(vec![self.compensator.event(Co2Sensor::Mhz19, ppm as f64)])
+            },
+            Err(e) => {
+                self.health.errors += 1;
+                Err(e)
+            },
+        }
+    }
+
+    fn health(&self) -> Health {
+        Health { since_last: self.last.map(|t| t.elapsed()), ..self.health }
+    }
+
+    fn stale_after(&self) -> Duration {
+        sensors::stale_after(Duration::from_secs(self.config.interval_secs))
+    }
+
+    fn quality(&self) -> Quality {
+        if self.started.elapsed() < PREHEAT {
+            return Quality::WarmingUp;
+        }
+        self.quality
+    }
+
+    fn observe(&mut self, event: &Event) {
+        self.compensator.observe(event);
+    }
+}
@@ -0,0 +1,206 @@
+use std::{
+    io::{Read, Write},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::bus::{Event, Link, PmReading, Quality};
+use crate::config::{Pms5003Config, Pms5003Mode};
+use crate::duty_cycle::{DutyCycle, Phase};
+use crate::payload::{Command, FrameDecoder, Payload, Pms5003Format};
+use crate::sensors::{self, Health, Metadata, Sensor, SensorError};
+use crate::serial::SerialPort;
+
+// Plantower PMS5003 on a UART
+//
+//  Keeps the sensor in the configured mode, reads frames and publishes the
+//  stabilized ones -- at most one per interval -- and, with a duty cycle,
+//  sleeps the fan between readings. The link health goes out on every pass,
+//  frames or not, so a silent sensor shows up as a growing frame age.
+
+const CHUNK_SIZE: usize = 64;
+
+// how long to wait between checks when there's nothing to read
+const IDLE_STEP: Duration = Duration::from_millis(250);
+
+// the datasheet's time to stable data after the fan starts, and its maximum
+//  range in ug/m^3
+const WARM_UP: Duration = Duration::from_secs(30);
+const RANGE: f64 = 1000.0;
+
+pub struct Pms5003 {
+    config: Pms5003Config,
+    duty_cycle: Option<DutyCycle>,
+    port: Option<SerialPort>,
+    decoder: FrameDecoder<Pms5003Format>,
+    last_published: Option<Instant>,
+    // when the sensor was last woken, and whether it has given a reading since
+    woke: Instant,
+    sampled: bool,
+    asleep_until: Option<Instant>,
+    quality: Quality,
+}
+
+impl Pms5003 {
+    pub fn new(config: &Pms5003Config) -> Self {
+        Pms5003 {
+            config: config.clone(),
+            duty_cycle: config.duty_cycle.as_ref().map(DutyCycle::from),
+            port: None,
+            decoder: FrameDecoder::new(),
+            last_published: None,
+            woke: Instant::now(),
+            sampled: false,
+            asleep_until: None,
+            quality: Quality::Valid,
+        }
+    }
+
+    // (re)wake the sensor and set its mode -- a previous run may have left it
+    //  asleep or in the other mode
+    fn wake(&mut self) -> Result<(), SensorError> {
+        let f = self.port.as_mut().ok_or(SensorError::NotReady)?;
+        send_command(f, Command::Wake)?;
+        match self.config.mode {
+            Pms5003Mode::Active => send_command(f, Command::ActiveMode)?,
+            Pms5003Mode::Passive => send_command(f, Command::PassiveMode)?,
+        }
+
+        self.woke = Instant::now();
+        self.sampled = false;
+        self.asleep_until = None;
+        Ok(())
+    }
+
+    fn link(&self, phase: Phase) -> Event {
+        Event::Link(Link { stats: self.decoder.stats(), frame_age: self.decoder.since_last_frame(), phase })
+    }
+}
+
+impl Sensor for Pms5003 {
+    fn metadata(&self) -> Metadata {
+        Metadata { model: "PMS5003", port: self.config.device.clone() }
+    }
+
+    fn init(&mut self) -> Result<(), SensorError> {
+        self.port = None;
+        let timeout = Duration::from_millis(self.config.read_timeout_ms);
+        self.port = Some(SerialPort::open(&self.config.device, self.config.baud, timeout)?);
+        println!("{} open at {} baud, 8N1", self.config.device, self.config.baud);
+
+        self.wake()
+    }
+
+    fn sample(&mut self) -> Result<Vec<Event>, SensorError> {
+        // fan and laser off for the sleep part of the duty cycle; the link
+        //  health keeps being published meanwhile
+        if let Some(until) = self.asleep_until {
+            match until.checked_duration_since(Instant::now()).filter(|d| !d.is_zero()) {
+                Some(left) => {
+                    thread::sleep(left.min(IDLE_STEP));
+                    return Ok(vec![self.link(Phase::Sleeping)]);
+                },
+                None => self.wake()?,
+            }
+        }
+
+        let phase = self.duty_cycle.map_or(Phase::Sampling, |c| c.phase(self.woke.elapsed(), self.sampled));
+        let f = self.port.as_mut().ok_or(SensorError::NotReady)?;
+
+        if let (Phase::Sleeping, Some(c)) = (phase, self.duty_cycle) {
+            send_command(f, Command::Sleep)?;
+            self.asleep_until = Some(Instant::now() + c.sleep);
+            return Ok(vec![self.link(Phase::Sleeping)]);
+        }
+
+        let interval = Duration::from_secs(self.config.interval_secs);
+        let due = self.last_published.is_none_or(|t| t.elapsed() >= interval);
+        let polled = match self.config.mode {
+            // every frame is decoded in order, even while the fan settles, so the
+            //  stream doesn't back up; only the newest is kept
+            Pms5003Mode::Active => read_available(f, &mut self.decoder)?,
+            Pms5003Mode::Passive if phase == Phase::Sampling && due => {
+                send_command(f, Command::ReadPassive)?;
+                read_one_frame(f, &mut self.decoder, Duration::from_millis(self.config.read_timeout_ms))?
+            },
+            // nothing arrives unasked in passive mode -- just wait
+            Pms5003Mode::Passive => {
+                thread::sleep(IDLE_STEP);
+                None
+            },
+        };
+
+        let mut events = Vec::with_capacity(2);
+        // readings while the fan is still settling are never published, so
+        //  AQI_TICK only ever marks a stabilized one
+        if let Some(p) = polled.filter(|_| phase == Phase::Sampling && due) {
+            self.last_published = Some(Instant::now());
+            self.sampled = true;
+            let r = p.reading();
+            self.quality = sensors::range_quality(0.0..=RANGE, &[r.pm2_5_cf1 as f64, r.pm10_cf1 as f64]);
+            events.push(Event::Pm(PmReading::Pms5003(r)));
+        }
+        events.push(self.link(phase));
+
+        Ok(events)
+    }
+
+    fn health(&self) -> Health {
+        let stats = self.decoder.stats();
+        Health {
+            readings: stats.frames as u64,
+            errors: stats.checksum_errors as u64 + stats.length_errors as u64,
+            since_last: self.decoder.since_last_frame(),
+        }
+    }
+
+    // no frames at all while it sleeps
+    fn stale_after(&self) -> Duration {
+        let interval = Duration::from_secs(self.config.interval_secs);
+        sensors::stale_after(self.duty_cycle.map_or(interval, |c| interval.max(c.awake + c.sleep)))
+    }
+
+    // the duty cycle has its own stabilize time, and publishes nothing before it
+    fn quality(&self) -> Quality {
+        if self.duty_cycle.is_none() && self.woke.elapsed() < WARM_UP {
+            return Quality::WarmingUp;
+        }
+        self.quality
+    }
+}
+
+// one read's worth of bytes -- blocks until some arrive or the read timeout
+//  passes, no spinning -- and the newest frame they complete
+fn read_available(f: &mut SerialPort, decoder: &mut FrameDecoder<Pms5003Format>) -> std::io::Result<Option<Payload>> {
+    let mut d = [0; CHUNK_SIZE];
+    let n = f.read(&mut d)?;
+    decoder.push(&d[..n]);
+
+    let mut latest = None;
+    while let Some(p) = decoder.next_frame() {
+        latest = Some(p);
+    }
+
+    Ok(latest)
+}
+
+fn send_command(f: &mut SerialPort, command: Command) -> std::io::Result<()> {
+    f.write_all(&command.encode())?;
+    f.flush()
+}
+
+// reads until the decoder has a frame, or gives up after the timeout
+fn read_one_frame(f: &mut SerialPort, decoder: &mut FrameDecoder<Pms5003Format>, timeout: Duration) -> std::io::Result<Option<Payload>> {
+    let deadline = Instant::now() + timeout;
+    let mut d = [0; CHUNK_SIZE];
+
+    while Instant::now() < deadline {
+        let n = f.read(&mut d)?;
+        decoder.push(&d[..n]);
+        if let Some(p) = decoder.next_frame() {
+            return Ok(Some(p));
+        }
+    }
+
+    Ok(None)
+}
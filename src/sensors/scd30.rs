@@ -0,0 +1,119 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use rppal::i2c::I2c;
+
+use crate::bus::{Co2Sensor, Event, Quality};
+use crate::co2::Compensator;
+use crate::config::Co2Config;
+use crate::payload::scd30::{self, Command};
+use crate::sensors::{self, Health, Metadata, Sensor, SensorError};
+
+// Sensirion SCD30 on I2C
+//
+//  Measures continuously at the configured interval and compensates with its
+//  own temperature and humidity sensor. Each pass checks for a new reading
+//  and reads it.
+
+// the interface description asks for at least 3 ms between a command and its
+//  response
+const EXECUTION_TIME: Duration = Duration::from_millis(5);
+// how long to wait again when a reading isn't ready yet
+const RETRY: Duration = Duration::from_millis(500);
+// ppm
+const RANGE: f64 = 10_000.0;
+
+pub struct Scd30 {
+    config: Co2Config,
+    i2c: Option<I2c>,
+    next: Instant,
+    health: Health,
+    last: Option<Instant>,
+    quality: Quality,
+}
+
+impl Scd30 {
+    pub fn new(config: &Co2Config) -> Self {
+        Scd30 {
+            config: config.clone(),
+            i2c: None,
+            next: Instant::now(),
+            health: Health::default(),
+            last: None,
+            quality: Quality::Valid,
+        }
+    }
+
+    fn send(&mut self, command: Command) -> Result<Vec<u16>, SensorError> {
+        let i2c = self.i2c.as_mut().ok_or(SensorError::NotReady)?;
+        sensors::sensirion_exchange(i2c, &command.encode(), EXECUTION_TIME, command.response_words())
+    }
+
+    // None if there's no new reading yet
+    fn read_co2(&mut self) -> Result<Option<f64>, SensorError> {
+        if !scd30::data_ready(&self.send(Command::DataReady)?) {
+            return Ok(None);
+        }
+
+        let words = self.send(Command::ReadMeasurement)?;
+        let reading = scd30::reading(&words).ok_or(SensorError::Protocol("short SCD30 reading".to_string()))?;
+        Ok(Some(reading.co2))
+    }
+}
+
+impl Sensor for Scd30 {
+    fn metadata(&self) -> Metadata {
+        Metadata { model: "SCD30", port: format!("I2C bus {}", self.config.i2c_bus) }
+    }
+
+    fn init(&mut self) -> Result<(), SensorError> {
+        self.i2c = None;
+        let mut i2c = I2c::with_bus(self.config.i2c_bus)?;
+        i2c.set_slave_address(scd30::I2C_ADDRESS)?;
+        self.i2c = Some(i2c);
+
+        let interval = self.config.interval_secs.clamp(2, 1800) as u16;
+        self.send(Command::MeasurementInterval(interval))?;
+        self.send(Command::StartContinuous(0))?;
+        Ok(())
+    }
+
+    fn sample(&mut self) -> Result<Vec<Event>, SensorError> {
+        if let Some(wait) = self.next.checked_duration_since(Instant::now()) {
+            thread::sleep(wait);
+        }
+
+        match self.read_co2() {
+            Ok(Some(ppm)) => {
+                self.next = Instant::now() + Duration::from_secs(self.config.interval_secs);
+                self.health.readings += 1;
+                self.last = Some(Instant::now());
+                self.quality = sensors::range_quality(0.0..=RANGE, &[ppm]);
+                // nothing to compensate
+                Ok(vec![Compensator::new(false).event(Co2Sensor::Scd30, ppm)])
+            },
+            Ok(None) => {
+                self.next = Instant::now() + RETRY;
+                Ok(vec![])
+            },
+            Err(e) => {
+                self.health.errors += 1;
+                Err(e)
+            },
+        }
+    }
+
+    fn health(&self) -> Health {
+        Health { since_last: self.last.map(|t| t.elapsed()), ..self.health }
+    }
+
+    fn stale_after(&self) -> Duration {
+        sensors::stale_after(Duration::from_secs(self.config.interval_secs))
+    }
+
+    fn quality(&self) -> Quality {
+        self.quality
+    }
+}
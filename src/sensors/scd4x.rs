@@ -0,0 +1,120 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use rppal::i2c::I2c;
+
+use crate::bus::{Co2Sensor, Event, Quality};
+use crate::co2::Compensator;
+use crate::config::Co2Config;
+use crate::payload::scd4x::{self, Command};
+use crate::sensors::{self, Health, Metadata, Sensor, SensorError};
+
+// Sensirion SCD40 / SCD41 on I2C
+//
+//  Measures every 5 s in periodic mode and compensates with its own temperature
+//  and humidity sensor. Each pass checks for a new reading and reads it.
+
+// 1 ms for everything airq sends, except stopping
+const EXECUTION_TIME: Duration = Duration::from_millis(1);
+const STOP_TIME: Duration = Duration::from_millis(500);
+// how long to wait again when a reading isn't ready yet
+const RETRY: Duration = Duration::from_millis(500);
+// ppm, the SCD41's; the SCD40 is specified to 2000
+const RANGE: f64 = 5000.0;
+
+pub struct Scd4x {
+    config: Co2Config,
+    i2c: Option<I2c>,
+    next: Instant,
+    health: Health,
+    last: Option<Instant>,
+    quality: Quality,
+}
+
+impl Scd4x {
+    pub fn new(config: &Co2Config) -> Self {
+        Scd4x {
+            config: config.clone(),
+            i2c: None,
+            next: Instant::now(),
+            health: Health::default(),
+            last: None,
+            quality: Quality::Valid,
+        }
+    }
+
+    fn send(&mut self, command: Command) -> Result<Vec<u16>, SensorError> {
+        let i2c = self.i2c.as_mut().ok_or(SensorError::NotReady)?;
+        let wait = if command == Command::StopPeriodic { STOP_TIME } else { EXECUTION_TIME };
+        sensors::sensirion_exchange(i2c, &command.encode(), wait, command.response_words())
+    }
+
+    // None if there's no new reading yet
+    fn read_co2(&mut self) -> Result<Option<f64>, SensorError> {
+        if !scd4x::data_ready(&self.send(Command::DataReady)?) {
+            return Ok(None);
+        }
+
+        let words = self.send(Command::ReadMeasurement)?;
+        let reading = scd4x::reading(&words).ok_or(SensorError::Protocol("short SCD4x reading".to_string()))?;
+        Ok(Some(reading.co2))
+    }
+}
+
+impl Sensor for Scd4x {
+    fn metadata(&self) -> Metadata {
+        Metadata { model: "SCD4x", port: format!("I2C bus {}", self.config.i2c_bus) }
+    }
+
+    fn init(&mut self) -> Result<(), SensorError> {
+        self.i2c = None;
+        let mut i2c = I2c::with_bus(self.config.i2c_bus)?;
+        i2c.set_slave_address(scd4x::I2C_ADDRESS)?;
+        self.i2c = Some(i2c);
+
+        // a previous run may have left it measuring, and starting again is
+        //  refused
+        self.send(Command::StopPeriodic)?;
+        self.send(Command::StartPeriodic)?;
+        Ok(())
+    }
+
+    fn sample(&mut self) -> Result<Vec<Event>, SensorError> {
+        if let Some(wait) = self.next.checked_duration_since(Instant::now()) {
+            thread::sleep(wait);
+        }
+
+        match self.read_co2() {
+            Ok(Some(ppm)) => {
+                self.next = Instant::now() + Duration::from_secs(self.config.interval_secs);
+                self.health.readings += 1;
+                self.last = Some(Instant::now());
+                self.quality = sensors::range_quality(0.0..=RANGE, &[ppm]);
+                // nothing to compensate
+                Ok(vec![Compensator::new(false).event(Co2Sensor::Scd4x, ppm)])
+            },
+            Ok(None) => {
+                self.next = Instant::now() + RETRY;
+                Ok(vec![])
+            },
+            Err(e) => {
+                self.health.errors += 1;
+                Err(e)
+            },
+        }
+    }
+
+    fn health(&self) -> Health {
+        Health { since_last: self.last.map(|t| t.elapsed()), ..self.health }
+    }
+
+    fn stale_after(&self) -> Duration {
+        sensors::stale_after(Duration::from_secs(self.config.interval_secs))
+    }
+
+    fn quality(&self) -> Quality {
+        self.quality
+    }
+}
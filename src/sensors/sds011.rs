@@ -0,0 +1,108 @@
+use std::{
+    io::{Read, Write},
+    time::{Duration, Instant},
+};
+
+use crate::bus::{Event, PmReading, Quality};
+use crate::config::Sds011Config;
+use crate::payload::FrameDecoder;
+use crate::payload::sds011::{Command, Sds011Format};
+use crate::sensors::{self, Health, Metadata, Sensor, SensorError};
+use crate::serial::SerialPort;
+
+// Nova Fitness SDS011 / SDS021 on a UART -- always 9600 baud
+//
+//  The sensor is put in its active reporting mode and streams a reading every
+//  second, or once per working period; the newest is published at most once
+//  per interval.
+
+const BAUD: u32 = 9600;
+const CHUNK_SIZE: usize = 64;
+// ug/m^3
+const RANGE: f64 = 999.9;
+
+pub struct Sds011 {
+    config: Sds011Config,
+    port: Option<SerialPort>,
+    decoder: FrameDecoder<Sds011Format>,
+    last_published: Option<Instant>,
+    quality: Quality,
+}
+
+impl Sds011 {
+    pub fn new(config: &Sds011Config) -> Self {
+        Sds011 {
+            config: config.clone(),
+            port: None,
+            decoder: FrameDecoder::new(),
+            last_published: None,
+            quality: Quality::Valid,
+        }
+    }
+}
+
+impl Sensor for Sds011 {
+    fn metadata(&self) -> Metadata {
+        Metadata { model: "SDS011", port: self.config.device.clone() }
+    }
+
+    fn init(&mut self) -> Result<(), SensorError> {
+        self.port = None;
+        let timeout = Duration::from_millis(self.config.read_timeout_ms);
+        let mut f = SerialPort::open(&self.config.device, BAUD, timeout)?;
+        println!("{} open at {} baud, 8N1", self.config.device, BAUD);
+
+        // a previous run may have left it asleep, or polled
+        for command in [Command::Work, Command::ActiveMode, Command::WorkingPeriod(self.config.work_period_mins)] {
+            f.write_all(&command.encode())?;
+        }
+        f.flush()?;
+
+        self.port = Some(f);
+        Ok(())
+    }
+
+    fn sample(&mut self) -> Result<Vec<Event>, SensorError> {
+        let f = self.port.as_mut().ok_or(SensorError::NotReady)?;
+
+        // one read's worth of bytes -- blocks until some arrive or the read
+        //  timeout passes -- and the newest reading they complete
+        let mut d = [0; CHUNK_SIZE];
+        let n = f.read(&mut d)?;
+        self.decoder.push(&d[..n]);
+        let mut latest = None;
+        while let Some(r) = self.decoder.next_frame() {
+            latest = Some(r);
+        }
+
+        let interval = Duration::from_secs(self.config.interval_secs);
+        let due = self.last_published.is_none_or(|t| t.elapsed() >= interval);
+        match latest.filter(|_| due) {
+            Some(r) => {
+                self.last_published = Some(Instant::now());
+                self.quality = sensors::range_quality(0.0..=RANGE, &[r.pm2_5, r.pm10]);
+                Ok(vec![Event::Pm(PmReading::Sds011(r))])
+            },
+            None => Ok(vec![]),
+        }
+    }
+
+    fn health(&self) -> Health {
+        let stats = self.decoder.stats();
+        Health {
+            readings: stats.frames as u64,
+            errors: stats.checksum_errors as u64,
+            since_last: self.decoder.since_last_frame(),
+        }
+    }
+
+    // a reading every second, or every working period
+    fn stale_after(&self) -> Duration {
+        let period = Duration::from_secs(self.config.work_period_mins as u64 * 60);
+        sensors::stale_after(Duration::from_secs(self.config.interval_secs).max(period))
+    }
+
+    fn quality(&self) -> Quality {
+        self.quality
+    }
+}
@@ -0,0 +1,110 @@
+use std::{
+    io::Write,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::bus::{Co2Sensor, Event, Quality};
+use crate::co2::Compensator;
+use crate::config::Co2Config;
+use crate::payload::senseair_s8::{self, S8Error, RESPONSE_SIZE};
+use crate::sensors::{self, Health, Metadata, Sensor, SensorError};
+use crate::serial::SerialPort;
+
+// Senseair S8 on a UART, over Modbus-RTU
+//
+//  The sensor measures every 2 s on its own; one request for the CO2 register
+//  every interval.
+
+const RESPONSE_TIMEOUT: Duration = Duration::from_millis(500);
+// ppm, the extended range
+const RANGE: f64 = 10_000.0;
+
+pub struct SenseairS8 {
+    config: Co2Config,
+    port: Option<SerialPort>,
+    compensator: Compensator,
+    next: Instant,
+    health: Health,
+    last: Option<Instant>,
+    quality: Quality,
+}
+
+impl SenseairS8 {
+    pub fn new(config: &Co2Config) -> Self {
+        SenseairS8 {
+            config: config.clone(),
+            port: None,
+            compensator: Compensator::new(config.compensate),
+            next: Instant::now(),
+            health: Health::default(),
+            last: None,
+            quality: Quality::Valid,
+        }
+    }
+
+    fn read_co2(&mut self) -> Result<u16, SensorError> {
+        let f = self.port.as_mut().ok_or(SensorError::NotReady)?;
+        f.write_all(&senseair_s8::co2_request())?;
+        f.flush()?;
+
+        let mut frame = [0; RESPONSE_SIZE];
+        let n = f.read_within(&mut frame, RESPONSE_TIMEOUT)?;
+        Ok(senseair_s8::co2_response(&frame[..n])?)
+    }
+}
+
+impl From<S8Error> for SensorError {
+    fn from(e: S8Error) -> Self {
+        SensorError::Protocol(e.to_string())
+    }
+}
+
+impl Sensor for SenseairS8 {
+    fn metadata(&self) -> Metadata {
+        Metadata { model: "Senseair S8", port: self.config.device.clone() }
+    }
+
+    fn init(&mut self) -> Result<(), SensorError> {
+        self.port = None;
+        self.port = Some(SerialPort::open(&self.config.device, senseair_s8::BAUD, RESPONSE_TIMEOUT)?);
+        println!("{} open at {} baud, 8N1", self.config.device, senseair_s8::BAUD);
+        Ok(())
+    }
+
+    fn sample(&mut self) -> Result<Vec<Event>, SensorError> {
+        if let Some(wait) = self.next.checked_duration_since(Instant::now()) {
+            thread::sleep(wait);
+        }
+        self.next = Instant::now() + Duration::from_secs(self.config.interval_secs);
+
+        match self.read_co2() {
+            Ok(ppm) => {
+                self.health.readings += 1;
+                self.last = Some(Instant::now());
+                self.quality = sensors::range_quality(0.0..=RANGE, &[ppm as f64]);
+                Ok(vec![self.compensator.event(Co2Sensor::SenseairS8, ppm as f64)])
+            },
+            Err(e) => {
+                self.health.errors += 1;
+                Err(e)
+            },
+        }
+    }
+
+    fn health(&self) -> Health {
+        Health { since_last: self.last.map(|t| t.elapsed()), ..self.health }
+    }
+
+    fn stale_after(&self) -> Duration {
+        sensors::stale_after(Duration::from_secs(self.config.interval_secs))
+    }
+
+    fn quality(&self) -> Quality {
+        self.quality
+    }
+
+    fn observe(&mut self, event: &Event) {
+        self.compensator.observe(event);
+    }
+}
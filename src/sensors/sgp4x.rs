@@ -0,0 +1,167 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use rppal::i2c::I2c;
+
+use crate::bus::{Climate, Event, GasReading, Quality};
+use crate::config::GasConfig;
+use crate::payload::sgp4x::{self, Command};
+use crate::sensors::{self, Health, Metadata, Sensor, SensorError};
+
+// Sensirion SGP40 / SGP41 on I2C
+//
+//  The sensors don't measure on their own -- every reading is a command, sent
+//  on a steady beat of interval_secs, as the gas index algorithm downstream
+//  expects. With [gas] compensate each command carries the latest
+//  temperature and humidity reading. The SGP41's NOx pixel needs 10 s of conditioning
+//  after it's set up, and reports nothing until then. The indices take another
+//  45 s to start.
+
+const CONDITIONING_TIME: Duration = Duration::from_secs(10);
+// the gas index algorithm's blackout
+const INDEX_START: Duration = Duration::from_secs(45);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Model {
+    Sgp40,
+    Sgp41,
+}
+
+pub struct Sgp4x {
+    model: Model,
+    config: GasConfig,
+    i2c: Option<I2c>,
+    climate: Option<Climate>,
+    conditioned_at: Instant,
+    // the indices start counting from the first reading after this
+    started: Instant,
+    next: Instant,
+    health: Health,
+    last: Option<Instant>,
+}
+
+impl Sgp4x {
+    pub fn new(model: Model, config: &GasConfig) -> Self {
+        let now = Instant::now();
+        Sgp4x {
+            model,
+            config: config.clone(),
+            i2c: None,
+            climate: None,
+            conditioned_at: now,
+            started: now,
+            next: now,
+            health: Health::default(),
+            last: None,
+        }
+    }
+
+    fn send(&mut self, command: Command) -> Result<Vec<u16>, SensorError> {
+        let i2c = self.i2c.as_mut().ok_or(SensorError::NotReady)?;
+        let wait = Duration::from_millis(command.execution_ms());
+        sensors::sensirion_exchange(i2c, &command.encode(), wait, command.response_words())
+    }
+
+    // humidity and temperature ticks for the next command
+    fn compensation(&self) -> (u16, u16) {
+        match self.climate.filter(|_| self.config.compensate) {
+            Some(c) => (sgp4x::humidity_ticks(c.humidity), sgp4x::temperature_ticks(c.temperature)),
+            None => (sgp4x::DEFAULT_HUMIDITY, sgp4x::DEFAULT_TEMPERATURE),
+        }
+    }
+
+    // None while the SGP41 is conditioning
+    fn measure(&mut self) -> Result<Option<GasReading>, SensorError> {
+        let (rh, t) = self.compensation();
+        let short = || SensorError::Protocol("short SGP4x reading".to_string());
+
+        match self.model {
+            Model::Sgp40 => {
+                let words = self.send(Command::MeasureRaw(rh, t))?;
+                let voc = *words.first().ok_or_else(short)?;
+                Ok(Some(GasReading::Sgp40 { voc }))
+            },
+            Model::Sgp41 if Instant::now() < self.conditioned_at => {
+                self.send(Command::Conditioning(rh, t))?;
+                Ok(None)
+            },
+            Model::Sgp41 => {
+                let words = self.send(Command::MeasureRawSignals(rh, t))?;
+                let [voc, nox] = *words.as_slice() else {
+                    return Err(short());
+                };
+                Ok(Some(GasReading::Sgp41 { voc, nox }))
+            },
+        }
+    }
+}
+
+impl Sensor for Sgp4x {
+    fn metadata(&self) -> Metadata {
+        let model = match self.model {
+            Model::Sgp40 => "SGP40",
+            Model::Sgp41 => "SGP41",
+        };
+        Metadata { model, port: format!("I2C bus {}", self.config.i2c_bus) }
+    }
+
+    fn init(&mut self) -> Result<(), SensorError> {
+        self.i2c = None;
+        let mut i2c = I2c::with_bus(self.config.i2c_bus)?;
+        i2c.set_slave_address(sgp4x::I2C_ADDRESS)?;
+        self.i2c = Some(i2c);
+
+        self.conditioned_at = Instant::now() + CONDITIONING_TIME;
+        self.next = Instant::now();
+        Ok(())
+    }
+
+    fn sample(&mut self) -> Result<Vec<Event>, SensorError> {
+        if let Some(wait) = self.next.checked_duration_since(Instant::now()) {
+            thread::sleep(wait);
+        }
+        // on the beat, unless a slow bus has put it behind
+        let interval = Duration::from_secs(self.config.interval_secs);
+        self.next = (self.next + interval).max(Instant::now());
+
+        match self.measure() {
+            Ok(Some(r)) => {
+                self.health.readings += 1;
+                self.last = Some(Instant::now());
+                Ok(vec![Event::Voc(r)])
+            },
+            Ok(None) => Ok(vec![]),
+            Err(e) => {
+                self.health.errors += 1;
+                Err(e)
+            },
+        }
+    }
+
+    fn health(&self) -> Health {
+        Health { since_last: self.last.map(|t| t.elapsed()), ..self.health }
+    }
+
+    fn stale_after(&self) -> Duration {
+        sensors::stale_after(Duration::from_secs(self.config.interval_secs))
+    }
+
+    fn quality(&self) -> Quality {
+        let conditioning = match self.model {
+            Model::Sgp40 => Duration::ZERO,
+            Model::Sgp41 => CONDITIONING_TIME,
+        };
+        if self.started.elapsed() < conditioning + INDEX_START {
+            return Quality::WarmingUp;
+        }
+        Quality::Valid
+    }
+
+    fn observe(&mut self, event: &Event) {
+        if let Event::Climate(c) = event {
+            self.climate = Some(*c);
+        }
+    }
+}
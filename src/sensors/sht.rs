@@ -0,0 +1,110 @@
+use std::{
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use rppal::i2c::I2c;
+
+use crate::bus::{Climate, Event};
+use crate::config::ClimateConfig;
+use crate::payload::{sht3x, sht4x};
+use crate::sensors::{self, Health, Metadata, Sensor, SensorError};
+
+// Sensirion SHT3x / SHT4x on I2C
+//
+//  One single shot measurement every interval. Between them the sensor sleeps,
+//  so it doesn't warm itself up.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Model {
+    Sht3x,
+    Sht4x,
+}
+
+impl Model {
+    fn default_address(self) -> u16 {
+        match self {
+            Model::Sht3x => sht3x::I2C_ADDRESSES[0],
+            Model::Sht4x => sht4x::I2C_ADDRESSES[0],
+        }
+    }
+}
+
+pub struct Sht {
+    model: Model,
+    config: ClimateConfig,
+    i2c: Option<I2c>,
+    next: Instant,
+    health: Health,
+    last: Option<Instant>,
+}
+
+impl Sht {
+    pub fn new(model: Model, config: &ClimateConfig) -> Self {
+        Sht { model, config: config.clone(), i2c: None, next: Instant::now(), health: Health::default(), last: None }
+    }
+
+    fn address(&self) -> u16 {
+        self.config.address.unwrap_or(self.model.default_address())
+    }
+
+    fn measure(&mut self) -> Result<(f64, f64), SensorError> {
+        let i2c = self.i2c.as_mut().ok_or(SensorError::NotReady)?;
+        let (command, wait) = match self.model {
+            Model::Sht3x => (sht3x::command(), sht3x::MEASURE_MS),
+            Model::Sht4x => (sht4x::command(), sht4x::MEASURE_MS),
+        };
+
+        let words = sensors::sensirion_exchange(i2c, &command, Duration::from_millis(wait), 2)?;
+        let reading = match self.model {
+            Model::Sht3x => sht3x::reading(&words),
+            Model::Sht4x => sht4x::reading(&words),
+        };
+        reading.ok_or(SensorError::Protocol("short SHT reading".to_string()))
+    }
+}
+
+impl Sensor for Sht {
+    fn metadata(&self) -> Metadata {
+        let model = match self.model {
+            Model::Sht3x => "SHT3x",
+            Model::Sht4x => "SHT4x",
+        };
+        Metadata { model, port: format!("I2C bus {} at 0x{:02X}", self.config.i2c_bus, self.address()) }
+    }
+
+    fn init(&mut self) -> Result<(), SensorError> {
+        self.i2c = None;
+        let mut i2c = I2c::with_bus(self.config.i2c_bus)?;
+        i2c.set_slave_address(self.address())?;
+        self.i2c = Some(i2c);
+        Ok(())
+    }
+
+    fn sample(&mut self) -> Result<Vec<Event>, SensorError> {
+        if let Some(wait) = self.next.checked_duration_since(Instant::now()) {
+            thread::sleep(wait);
+        }
+        self.next = Instant::now() + Duration::from_secs(self.config.interval_secs);
+
+        match self.measure() {
+            Ok((temperature, humidity)) => {
+                self.health.readings += 1;
+                self.last = Some(Instant::now());
+                Ok(vec![Event::Climate(Climate { temperature, humidity, pressure: None, at: SystemTime::now() })])
+            },
+            Err(e) => {
+                self.health.errors += 1;
+                Err(e)
+            },
+        }
+    }
+
+    fn health(&self) -> Health {
+        Health { since_last: self.last.map(|t| t.elapsed()), ..self.health }
+    }
+
+    fn stale_after(&self) -> Duration {
+        sensors::stale_after(Duration::from_secs(self.config.interval_secs))
+    }
+}
@@ -0,0 +1,109 @@
+use std::{
+    f64::consts::TAU,
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::bus::{Climate, Event, PmReading, Quality};
+use crate::config::SimulatedConfig;
+use crate::sensors::{self, Health, Metadata, Sensor, SensorError};
+
+// Simulated sensors, for running airq on a bench without the hardware
+//
+//  Readings that swing slowly up and down around typical indoor values, one
+//  every interval, so the registers, the LCD and the log all have something
+//  to show. They go out with quality Simulated -- valid, but never mistaken
+//  for a measurement by anything reading the quality registers.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Model {
+    Particulate,
+    Climate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimulatedPm {
+    // ug/m^3
+    pub pm2_5: f64,
+    pub pm10: f64,
+}
+
+// one full swing up and down
+const PERIOD: Duration = Duration::from_secs(600);
+
+// the reading `elapsed` into the run
+pub fn reading(model: Model, elapsed: Duration, at: SystemTime) -> Event {
+    let wave = (elapsed.as_secs_f64() / PERIOD.as_secs_f64() * TAU).sin();
+    match model {
+        Model::Particulate => {
+            let pm2_5 = 12.0 + 8.0 * wave;
+            Event::Pm(PmReading::Simulated(SimulatedPm { pm2_5, pm10: 1.5 * pm2_5 }))
+        },
+        // humidity falls as the temperature rises, as it does indoors
+        Model::Climate => Event::Climate(Climate {
+            temperature: 21.0 + 2.0 * wave,
+            humidity: 45.0 - 10.0 * wave,
+            pressure: None,
+            at,
+        }),
+    }
+}
+
+pub struct Simulated {
+    model: Model,
+    interval: Duration,
+    started: Instant,
+    next: Instant,
+    health: Health,
+    last: Option<Instant>,
+}
+
+impl Simulated {
+    pub fn new(model: Model, config: &SimulatedConfig) -> Self {
+        Simulated {
+            model,
+            interval: Duration::from_secs(config.interval_secs),
+            started: Instant::now(),
+            next: Instant::now(),
+            health: Health::default(),
+            last: None,
+        }
+    }
+}
+
+impl Sensor for Simulated {
+    fn metadata(&self) -> Metadata {
+        let model = match self.model {
+            Model::Particulate => "simulated PM",
+            Model::Climate => "simulated climate",
+        };
+        Metadata { model, port: "no hardware".to_string() }
+    }
+
+    fn init(&mut self) -> Result<(), SensorError> {
+        Ok(())
+    }
+
+    fn sample(&mut self) -> Result<Vec<Event>, SensorError> {
+        if let Some(wait) = self.next.checked_duration_since(Instant::now()) {
+            thread::sleep(wait);
+        }
+        self.next = Instant::now() + self.interval;
+
+        self.health.readings += 1;
+        self.last = Some(Instant::now());
+        Ok(vec![reading(self.model, self.started.elapsed(), SystemTime::now())])
+    }
+
+    fn health(&self) -> Health {
+        Health { since_last: self.last.map(|t| t.elapsed()), ..self.health }
+    }
+
+    fn stale_after(&self) -> Duration {
+        sensors::stale_after(self.interval)
+    }
+
+    fn quality(&self) -> Quality {
+        Quality::Simulated
+    }
+}
@@ -0,0 +1,240 @@
+use std::{
+    io::{Read, Write},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use rppal::i2c::I2c;
+
+use crate::bus::{Event, PmReading, Quality};
+use crate::config::{Sps30Config, Sps30Interface};
+use crate::payload::sensirion;
+use crate::payload::sps30::{self, Command, Sps30Error, Sps30Reading, SHDLC_BAUD, SHDLC_DELIMITER, VALUES_SIZE};
+use crate::sensors::{self, Health, Metadata, Sensor, SensorError};
+use crate::serial::SerialPort;
+
+// Sensirion SPS30 over UART (SHDLC) or I2C
+//
+//  Measures continuously once started; each pass waits out the interval, then
+//  asks for the newest reading. Fan cleaning is scheduled here rather than by
+//  the sensor, whose own schedule is switched off: it restarts whenever the
+//  sensor loses power, so a unit that is unplugged now and then never cleaned.
+//  Cleaning runs at fixed times -- every interval since the epoch -- so
+//  restarting airq doesn't postpone it either.
+
+// a response takes a few ms; give a busy bus some slack
+const RESPONSE_TIMEOUT: Duration = Duration::from_millis(500);
+// the sensor needs this long after an I2C command before it can be read
+const I2C_EXECUTION_TIME: Duration = Duration::from_millis(20);
+// the fan runs flat out for 10 s
+const CLEANING_TIME: Duration = Duration::from_secs(10);
+// ug/m^3
+const RANGE: f64 = 1000.0;
+
+enum Port {
+    Uart(SerialPort),
+    I2c(I2c),
+}
+
+impl Port {
+    // sends the command and returns the data of its response
+    fn send(&mut self, command: Command) -> Result<Vec<u8>, SensorError> {
+        match self {
+            Port::Uart(f) => {
+                f.write_all(&command.shdlc())?;
+                f.flush()?;
+                let frame = read_shdlc_frame(f)?;
+                Ok(command.shdlc_response(&frame)?)
+            },
+            Port::I2c(i2c) => {
+                i2c.write(&command.i2c())?;
+                thread::sleep(I2C_EXECUTION_TIME);
+                let mut raw = vec![0; command.i2c_response_size()];
+                if !raw.is_empty() {
+                    i2c.read(&mut raw)?;
+                }
+                Ok(sensirion::data(&raw)?)
+            },
+        }
+    }
+
+    // None if there is no reading since the last one
+    fn read_values(&mut self) -> Result<Option<Sps30Reading>, SensorError> {
+        if let Port::I2c(_) = self {
+            let ready = self.send(Command::DataReady)?;
+            if ready.get(1) != Some(&1) {
+                return Ok(None);
+            }
+        }
+
+        // over UART, no data means no new reading
+        let data = self.send(Command::ReadValues)?;
+        match <&[u8; VALUES_SIZE]>::try_from(data.as_slice()) {
+            Ok(values) => Ok(Some(Sps30Reading::from_be_bytes(values))),
+            Err(_) if data.is_empty() => Ok(None),
+            Err(_) => Err(Sps30Error::Length(data.len()).into()),
+        }
+    }
+}
+
+// one whole frame, delimiters included -- anything before the first 0x7E is
+//  line noise
+fn read_shdlc_frame(f: &mut SerialPort) -> Result<Vec<u8>, SensorError> {
+    let deadline = Instant::now() + RESPONSE_TIMEOUT;
+    let mut frame = Vec::with_capacity(2 * VALUES_SIZE + 8);
+    let mut b = [0; 1];
+
+    while Instant::now() < deadline {
+        if f.read(&mut b)? == 0 {
+            continue;
+        }
+        match (frame.len(), b[0]) {
+            (0, SHDLC_DELIMITER) => frame.push(b[0]),
+            (0, _) => {},
+            // back-to-back delimiters: the first one ended some other frame
+            (1, SHDLC_DELIMITER) => {},
+            (_, SHDLC_DELIMITER) => {
+                frame.push(b[0]);
+                return Ok(frame);
+            },
+            _ => frame.push(b[0]),
+        }
+    }
+
+    Err(Sps30Error::Framing.into())
+}
+
+impl From<Sps30Error> for SensorError {
+    fn from(e: Sps30Error) -> Self {
+        SensorError::Protocol(e.to_string())
+    }
+}
+
+pub struct Sps30 {
+    config: Sps30Config,
+    port: Option<Port>,
+    health: Health,
+    last_reading: Option<Instant>,
+    next_clean: Option<SystemTime>,
+    quality: Quality,
+}
+
+impl Sps30 {
+    pub fn new(config: &Sps30Config) -> Self {
+        Sps30 {
+            config: config.clone(),
+            port: None,
+            health: Health::default(),
+            last_reading: None,
+            next_clean: None,
+            quality: Quality::Valid,
+        }
+    }
+
+    fn send(&mut self, command: Command) -> Result<Vec<u8>, SensorError> {
+        self.port.as_mut().ok_or(SensorError::NotReady)?.send(command)
+    }
+
+    fn open(&self) -> Result<Port, SensorError> {
+        match self.config.interface {
+            Sps30Interface::Uart => {
+                // short reads; read_shdlc_frame keeps its own deadline
+                let f = SerialPort::open(&self.config.device, SHDLC_BAUD, Duration::from_millis(100))?;
+                println!("{} open at {} baud, 8N1", self.config.device, SHDLC_BAUD);
+                Ok(Port::Uart(f))
+            },
+            Sps30Interface::I2c => {
+                let mut i2c = I2c::with_bus(self.config.i2c_bus)?;
+                i2c.set_slave_address(sps30::I2C_ADDRESS)?;
+                Ok(Port::I2c(i2c))
+            },
+        }
+    }
+
+    // the next multiple of the cleaning interval since the epoch, if cleaning
+    //  is on
+    fn schedule_clean(&mut self, after: SystemTime) {
+        let interval = self.config.cleaning_interval_hours * 3600;
+        self.next_clean = (interval > 0).then(|| {
+            let secs = after.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+            UNIX_EPOCH + Duration::from_secs((secs / interval + 1) * interval)
+        });
+    }
+
+    fn try_sample(&mut self) -> Result<Vec<Event>, SensorError> {
+        let now = SystemTime::now();
+        if self.next_clean.is_some_and(|t| now >= t) {
+            println!("{}: fan cleaning", self.metadata());
+            self.send(Command::Clean)?;
+            self.schedule_clean(now);
+            // readings while the fan is cleaning are meaningless
+            thread::sleep(CLEANING_TIME);
+            return Ok(vec![]);
+        }
+
+        // the sensor has a new reading every second; there's no point asking
+        //  more often than readings are published
+        let interval = Duration::from_secs(self.config.interval_secs);
+        if let Some(left) = self.last_reading.and_then(|t| interval.checked_sub(t.elapsed())) {
+            thread::sleep(left);
+        }
+
+        match self.port.as_mut().ok_or(SensorError::NotReady)?.read_values()? {
+            Some(r) => {
+                self.health.readings += 1;
+                self.last_reading = Some(Instant::now());
+                self.quality = sensors::range_quality(0.0..=RANGE, &[r.pm1_0, r.pm2_5, r.pm4_0, r.pm10]);
+                Ok(vec![Event::Pm(PmReading::Sps30(r))])
+            },
+            None => {
+                thread::sleep(Duration::from_millis(250));
+                Ok(vec![])
+            },
+        }
+    }
+}
+
+impl Sensor for Sps30 {
+    fn metadata(&self) -> Metadata {
+        let port = match self.config.interface {
+            Sps30Interface::Uart => self.config.device.clone(),
+            Sps30Interface::I2c => format!("I2C bus {}", self.config.i2c_bus),
+        };
+        Metadata { model: "SPS30", port }
+    }
+
+    fn init(&mut self) -> Result<(), SensorError> {
+        self.port = None;
+        let mut port = self.open()?;
+
+        // a previous run may have left it measuring, and starting again is
+        //  refused -- so stop first, whatever state it's in
+        let _ = port.send(Command::Stop);
+        port.send(Command::CleaningInterval(0))?;
+        port.send(Command::Start)?;
+
+        self.port = Some(port);
+        self.schedule_clean(SystemTime::now());
+        Ok(())
+    }
+
+    fn sample(&mut self) -> Result<Vec<Event>, SensorError> {
+        let out = self.try_sample();
+        if out.is_err() {
+            self.health.errors += 1;
+        }
+        out
+    }
+
+    fn health(&self) -> Health {
+        Health { since_last: self.last_reading.map(|t| t.elapsed()), ..self.health }
+    }
+
+    fn stale_after(&self) -> Duration {
+        sensors::stale_after(Duration::from_secs(self.config.interval_secs))
+    }
+
+    fn quality(&self) -> Quality {
+        self.quality
+    }
+}
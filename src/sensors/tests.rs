@@ -0,0 +1,100 @@
+#[cfg(test)]
+mod sensors_tests {
+    use crate::bus::{Event, PmReading, Quality};
+    use crate::config::Config;
+    use crate::sensors::*;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn builds_the_enabled_sensors_in_order() {
+        let mut c = Config::default();
+        let models: Vec<&str> = from_config(&c).iter().map(|(_, s)| s.metadata().model).collect();
+        assert_eq!(models, vec!["PMS5003", "AM2302"]);
+
+        c.sensors.enabled = vec!["am2302".to_string()];
+        let sensors = from_config(&c);
+        assert_eq!(sensors.len(), 1);
+        assert_eq!(sensors[0].1.metadata().to_string(), "AM2302 on GPIO 4");
+    }
+
+    #[test]
+    fn every_driver_is_registered_by_name() {
+        for name in driver_names() {
+            assert!(kind_of(name).is_some());
+        }
+        assert_eq!(kind_of("sds011"), Some(Kind::Particulate));
+        assert_eq!(kind_of("sgp41"), Some(Kind::Gas));
+        assert_eq!(kind_of("PMS5003"), None);
+    }
+
+    #[test]
+    fn dht11_bytes_from_a_dht22_reading() {
+        // 45 %RH and 23.4 degC sent as integer and tenths bytes: 0x2D 0x00 0x17
+        //  0x04, which the DHT22 decoding reads as 1152.0 %RH and 589.2 degC
+        assert_eq!(am2302::dht11_from_dht22(589.2, 1152.0), (23.4, 45.0));
+        // and -2.1 degC, with the sign in the tenths byte: 0x02 0x81
+        assert_eq!(am2302::dht11_from_dht22(64.1, 1152.0), (-2.1, 45.0));
+    }
+
+    #[test]
+    fn health_reads_like_a_log_line() {
+        let h = Health { readings: 12, errors: 1, since_last: Some(Duration::from_secs(3)) };
+        assert_eq!(h.to_string(), "12 readings, 1 errors, last reading 3s ago");
+        assert_eq!(Health::default().to_string(), "0 readings, 0 errors, no reading yet");
+    }
+
+    #[test]
+    fn quality_from_health() {
+        let h = |secs: Option<u64>| Health { readings: 1, errors: 0, since_last: secs.map(Duration::from_secs) };
+        let stale = Duration::from_secs(30);
+
+        assert_eq!(rate(&h(None), stale, Quality::Valid), Quality::NoData);
+        assert_eq!(rate(&h(Some(5)), stale, Quality::Valid), Quality::Valid);
+        assert_eq!(rate(&h(Some(31)), stale, Quality::Valid), Quality::Stale);
+        assert_eq!(rate(&h(Some(5)), stale, Quality::OutOfRange), Quality::OutOfRange);
+        assert_eq!(rate(&h(Some(31)), stale, Quality::OutOfRange), Quality::Stale);
+        // conditioning sensors have nothing to show while they warm up
+        assert_eq!(rate(&h(None), stale, Quality::WarmingUp), Quality::WarmingUp);
+    }
+
+    #[test]
+    fn staleness_and_ranges() {
+        assert_eq!(stale_after(Duration::from_secs(1)), Duration::from_secs(30));
+        assert_eq!(stale_after(Duration::from_secs(60)), Duration::from_secs(180));
+
+        assert_eq!(range_quality(0.0..=1000.0, &[12.0, 1000.0]), Quality::Valid);
+        assert_eq!(range_quality(0.0..=1000.0, &[12.0, 1001.0]), Quality::OutOfRange);
+    }
+
+    #[test]
+    fn pms5003_needs_init_before_sampling() {
+        let mut c = Config::default();
+        c.pms5003.device = "/dev/airq-no-such-port".to_string();
+        let (_, mut s) = from_config(&c).remove(0);
+
+        assert!(matches!(s.sample(), Err(SensorError::NotReady)));
+        assert!(matches!(s.init(), Err(SensorError::Serial(_))));
+        assert_eq!(s.health().readings, 0);
+    }
+
+    #[test]
+    fn simulated_readings_swing_and_say_so() {
+        let at = SystemTime::now();
+        let pm = |secs| match simulated::reading(simulated::Model::Particulate, Duration::from_secs(secs), at) {
+            Event::Pm(PmReading::Simulated(r)) => (r.pm2_5, r.pm10),
+            e => panic!("{:?}", e),
+        };
+        assert_eq!(pm(0), (12.0, 18.0));
+        assert_eq!(pm(150), (20.0, 30.0));
+        assert!(matches!(simulated::reading(simulated::Model::Climate, Duration::ZERO, at),
+            Event::Climate(c) if c.temperature == 21.0 && c.humidity == 45.0));
+
+        let mut c = Config::default();
+        c.sensors.enabled = vec!["simulated-pm".to_string(), "simulated-climate".to_string()];
+        assert!(c.validate().is_ok());
+        for (_, mut sensor) in from_config(&c) {
+            assert_eq!(sensor.sample().unwrap().len(), 1);
+            assert_eq!(rate(&sensor.health(), sensor.stale_after(), sensor.quality()), Quality::Simulated);
+        }
+    }
+}
@@ -0,0 +1,170 @@
+#[cfg(test)]
+mod tests;
+
+use std::{
+    fmt,
+    fs::{File, OpenOptions},
+    io::{self, Read, Write},
+    os::{fd::AsRawFd, unix::fs::OpenOptionsExt},
+    time::{Duration, Instant},
+};
+
+use nix::{
+    errno::Errno,
+    fcntl::{fcntl, FcntlArg, OFlag},
+    sys::termios::{self, BaudRate, ControlFlags, FlushArg, SetArg, SpecialCharacterIndices},
+};
+
+// Serial port setup for the sensor UARTs
+//
+//  Replaces the stty incantation start.sh used to run: the port is opened
+//  exclusively, put in raw 8N1 mode at the requested speed, and reads block
+//  for at most the given timeout -- a read returning 0 bytes means the line
+//  was idle for that long.
+
+nix::ioctl_none_bad!(tiocexcl, nix::libc::TIOCEXCL);
+
+#[derive(Debug)]
+pub enum SerialError {
+    Missing(String),
+    Busy(String),
+    PermissionDenied(String),
+    NotATty(String),
+    UnsupportedBaud(u32),
+    Io { path: String, source: io::Error },
+}
+
+impl fmt::Display for SerialError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SerialError::Missing(path) =>
+                write!(f, "{}: no such device -- is the UART enabled and the sensor plugged in?", path),
+            SerialError::Busy(path) =>
+                write!(f, "{}: device busy -- another process (a login getty or a second airq?) has it open", path),
+            SerialError::PermissionDenied(path) =>
+                write!(f, "{}: permission denied -- is the user in the dialout group?", path),
+            SerialError::NotATty(path) =>
+                write!(f, "{}: not a serial port", path),
+            SerialError::UnsupportedBaud(baud) =>
+                write!(f, "unsupported baud rate {}", baud),
+            SerialError::Io { path, source } =>
+                write!(f, "{}: {}", path, source),
+        }
+    }
+}
+
+impl std::error::Error for SerialError {}
+
+#[derive(Debug)]
+pub struct SerialPort {
+    file: File,
+}
+
+impl SerialPort {
+    // 8 data bits, no parity, 1 stop bit, raw mode
+    pub fn open(path: &str, baud: u32, timeout: Duration) -> Result<SerialPort, SerialError> {
+        let speed = baud_rate(baud).ok_or(SerialError::UnsupportedBaud(baud))?;
+
+        // O_NONBLOCK so the open can't hang waiting for carrier detect; it's
+        //  cleared again once the port is set up
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags((OFlag::O_NOCTTY | OFlag::O_NONBLOCK).bits())
+            .open(path)
+            .map_err(|e| open_error(path, e))?;
+
+        let io_error = |e: Errno| SerialError::Io { path: path.to_string(), source: e.into() };
+
+        let mut t = match termios::tcgetattr(&file) {
+            Ok(t) => t,
+            Err(Errno::ENOTTY) | Err(Errno::EINVAL) => return Err(SerialError::NotATty(path.to_string())),
+            Err(e) => return Err(io_error(e)),
+        };
+
+        // nobody else gets to open it while we have it
+        unsafe { tiocexcl(file.as_raw_fd()) }.map_err(io_error)?;
+
+        termios::cfmakeraw(&mut t);
+        t.control_flags &= !(ControlFlags::PARENB | ControlFlags::CSTOPB | ControlFlags::CSIZE | ControlFlags::CRTSCTS);
+        t.control_flags |= ControlFlags::CS8 | ControlFlags::CLOCAL | ControlFlags::CREAD;
+        termios::cfsetspeed(&mut t, speed).map_err(io_error)?;
+
+        // VMIN = 0, VTIME = timeout: a read returns as soon as any byte arrives,
+        //  or with nothing once the timeout passes
+        t.control_chars[SpecialCharacterIndices::VMIN as usize] = 0;
+        t.control_chars[SpecialCharacterIndices::VTIME as usize] = deciseconds(timeout);
+
+        termios::tcsetattr(&file, SetArg::TCSANOW, &t).map_err(io_error)?;
+        termios::tcflush(&file, FlushArg::TCIOFLUSH).map_err(io_error)?;
+        fcntl(file.as_raw_fd(), FcntlArg::F_SETFL(OFlag::empty())).map_err(io_error)?;
+
+        Ok(SerialPort { file })
+    }
+
+    // as much of `buf` as arrives before the timeout, for request/response
+    //  protocols -- the number of bytes read
+    pub fn read_within(&mut self, buf: &mut [u8], timeout: Duration) -> io::Result<usize> {
+        let deadline = Instant::now() + timeout;
+        let mut n = 0;
+        while n < buf.len() && Instant::now() < deadline {
+            n += self.read(&mut buf[n..])?;
+        }
+
+        Ok(n)
+    }
+}
+
+impl Read for SerialPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+impl Write for SerialPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        termios::tcdrain(&self.file).map_err(io::Error::from)
+    }
+}
+
+fn open_error(path: &str, e: io::Error) -> SerialError {
+    match (e.kind(), e.raw_os_error()) {
+        (io::ErrorKind::NotFound, _) | (_, Some(nix::libc::ENXIO)) | (_, Some(nix::libc::ENODEV)) =>
+            SerialError::Missing(path.to_string()),
+        (io::ErrorKind::PermissionDenied, _) => SerialError::PermissionDenied(path.to_string()),
+        (_, Some(nix::libc::EBUSY)) => SerialError::Busy(path.to_string()),
+        _ => SerialError::Io { path: path.to_string(), source: e },
+    }
+}
+
+// termios only takes the standard rates
+fn baud_rate(baud: u32) -> Option<BaudRate> {
+    let speed = match baud {
+        1200 => BaudRate::B1200,
+        2400 => BaudRate::B2400,
+        4800 => BaudRate::B4800,
+        9600 => BaudRate::B9600,
+        19200 => BaudRate::B19200,
+        38400 => BaudRate::B38400,
+        57600 => BaudRate::B57600,
+        115200 => BaudRate::B115200,
+        230400 => BaudRate::B230400,
+        _ => return None,
+    };
+
+    Some(speed)
+}
+
+// VTIME counts tenths of a second in a byte -- round up, and never 0, which
+//  would make reads return immediately
+fn deciseconds(timeout: Duration) -> u8 {
+    timeout.as_millis().div_ceil(100).clamp(1, u8::MAX as u128) as u8
+}
+
+pub fn is_supported_baud(baud: u32) -> bool {
+    baud_rate(baud).is_some()
+}
@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod serial_tests {
+    use crate::serial::*;
+    use std::time::Duration;
+
+    const TIMEOUT: Duration = Duration::from_millis(500);
+
+    #[test]
+    fn missing_device_is_reported_as_missing() {
+        let r = SerialPort::open("/dev/ttyNOPE0", 9600, TIMEOUT);
+
+        assert!(matches!(r, Err(SerialError::Missing(_))));
+    }
+
+    #[test]
+    fn regular_file_is_not_a_tty() {
+        let path = std::env::temp_dir().join(format!("airq-serial-{}", std::process::id()));
+        std::fs::write(&path, b"BM").unwrap();
+
+        let r = SerialPort::open(path.to_str().unwrap(), 9600, TIMEOUT);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(r, Err(SerialError::NotATty(_))));
+    }
+
+    #[test]
+    fn rejects_non_standard_baud() {
+        let r = SerialPort::open("/dev/ttyS0", 9601, TIMEOUT);
+
+        assert!(matches!(r, Err(SerialError::UnsupportedBaud(9601))));
+        assert!(is_supported_baud(9600));
+        assert!(!is_supported_baud(0));
+    }
+
+    #[test]
+    fn reads_raw_bytes_and_times_out_when_idle() {
+        use nix::pty::openpty;
+        use std::io::{Read, Write};
+
+        let pty = openpty(None, None).unwrap();
+        let path = nix::unistd::ttyname(&pty.slave).unwrap();
+        let mut master = std::fs::File::from(pty.master);
+
+        let mut port = SerialPort::open(path.to_str().unwrap(), 9600, Duration::from_millis(100)).unwrap();
+
+        // raw mode -- CR, NUL and ^D must come through untouched
+        master.write_all(b"BM\r\n\x00\x04").unwrap();
+        let mut buf = [0u8; 16];
+        let mut got = Vec::new();
+        while got.len() < 6 {
+            let n = port.read(&mut buf).unwrap();
+            assert!(n > 0, "timed out after {:?}", got);
+            got.extend_from_slice(&buf[..n]);
+        }
+        assert_eq!(got, b"BM\r\n\x00\x04");
+
+        // nothing more to read: returns 0 after the timeout instead of blocking
+        assert_eq!(port.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn errors_name_the_device() {
+        let e = SerialError::Busy("/dev/ttyS0".to_string());
+
+        assert!(e.to_string().starts_with("/dev/ttyS0: device busy"));
+    }
+}
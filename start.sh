@@ -1,14 +1,6 @@
 #!/bin/bash
 
-stty -F /dev/ttyS0 9600 -parenb -parodd -cmspar cs8 \
-    hupcl -cstopb cread clocal -crtscts \
-    -ignbrk -brkint -ignpar -parmrk -inpck -istrip -inlcr -igncr \
-    -icrnl -ixon -imaxbel -flusho  \
-    -ixoff -iuclc -ixany -iutf8 \
-    -opost -olcuc \
-    -ocrnl onlcr -onocr -onlret -ofill -ofdel nl0 cr0 tab0 bs0 vt0 ff0 \
-    -isig -icanon -iexten -echo echoe echok -echonl -noflsh -xcase \
-    -tostop -echoprt echoctl echoke -extproc 
+# airq opens and configures the serial port itself (9600 8N1, raw) -- no stty needed
 
 cd ~
 